@@ -0,0 +1,389 @@
+//! Runs the level builders outside the game, to see what they make.
+//!
+//! ```text
+//! mapgen [--depth N] [--branch NAME] [--seed N] [--width N] [--height N] [--chain NAME] [--batch N] [--timeout SECS]
+//! ```
+//!
+//! Levels come from whichever chain in `level_chains.json` covers the depth of the main dungeon (or of the side
+//! branch named by `--branch`), or from the one named by `--chain`, at the size the chain asks for unless `--width`
+//! or `--height` say otherwise.
+//! A single run prints the level as ASCII with its statistics. `--batch N` builds N levels from consecutive seeds
+//! and only reports the ones that came out broken: a panic, no start, no way down or no way to reach it. Levels
+//! that only passed their checks on a rebuild are listed too, and falling back to the plain hall counts as broken.
+//! Each seed is built twice, and one that doesn't give the same level both times is broken as well.
+//! `--timeout SECS` cancels any build still going after that long, which leaves a level that was being tiled by wave
+//! function collapse as it was, and sends one that then fails its checks to the fallback.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::panic::{self, AssertUnwindSafe};
+
+use rouge_like_game::*;
+
+struct Options {
+    depth: i32,
+    branch: Option<String>,
+    seed: u64,
+    width: Option<i32>,
+    height: Option<i32>,
+    chain: Option<String>,
+    batch: Option<u64>,
+    timeout: Option<u64>,
+}
+
+struct Stats {
+    floor_percent: f32,
+    regions: usize,
+    path_length: Option<usize>,
+    rooms: Option<usize>,
+    spawns: BTreeMap<String, usize>,
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!(
+                "Usage: mapgen [--depth N] [--branch NAME] [--seed N] [--width N] [--height N] [--chain NAME] [--batch N] [--timeout SECS]"
+            );
+            std::process::exit(2);
+        },
+    };
+
+    if let Err(e) = raws::load_raws() {
+        eprintln!("Unable to load the raws: {}", e);
+        std::process::exit(1);
+    }
+    RexAssets::new();
+
+    if let Some(name) = &options.chain {
+        if raws::get_level_chain(&RAWS.lock().unwrap(), name).is_none() {
+            eprintln!("No level chain called {}", name);
+            std::process::exit(2);
+        }
+    }
+
+    if let Some(name) = &options.branch {
+        if !raws::get_branches(&RAWS.lock().unwrap())
+            .iter()
+            .any(|(_, b)| &b.name == name)
+        {
+            eprintln!("No dungeon branch called {}", name);
+            std::process::exit(2);
+        }
+    }
+
+    match options.batch {
+        None => single(&options),
+        Some(count) => batch(&options, count),
+    }
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        depth: 1,
+        branch: None,
+        seed: 1,
+        width: None,
+        height: None,
+        chain: None,
+        batch: None,
+        timeout: None,
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next().ok_or(format!("{} needs a value", arg))?;
+        if arg == "--chain" {
+            options.chain = Some(value);
+            continue;
+        }
+        if arg == "--branch" {
+            options.branch = Some(value);
+            continue;
+        }
+
+        let bad = |_| format!("{} expects a number, not '{}'", arg, value);
+        match arg.as_str() {
+            "--depth" => options.depth = value.parse().map_err(bad)?,
+            "--seed" => options.seed = value.parse().map_err(bad)?,
+            "--width" => options.width = Some(value.parse().map_err(bad)?),
+            "--height" => options.height = Some(value.parse().map_err(bad)?),
+            "--batch" => options.batch = Some(value.parse().map_err(bad)?),
+            "--timeout" => options.timeout = Some(value.parse().map_err(bad)?),
+            _ => return Err(format!("Unknown option {}", arg)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn build(options: &Options, seed: u64) -> BuilderChain {
+    rng::reseed(seed);
+    let size = match (options.width, options.height) {
+        (None, None) => None,
+        (width, height) => Some((width.unwrap_or(80), height.unwrap_or(50))),
+    };
+    let mut builder = match &options.chain {
+        Some(name) => named_builder(name, options.depth, size).expect("Unknown level chain"),
+        None => {
+            let branch = raws::branch_of(&RAWS.lock().unwrap(), &options.branch);
+            level_builder(branch, options.depth, size)
+        },
+    };
+
+    if let Some(seconds) = options.timeout {
+        let cancel = builder.cancel_flag();
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_secs(seconds));
+            cancel.store(true, std::sync::atomic::Ordering::Relaxed);
+        });
+    }
+
+    builder.build_map();
+    builder
+}
+
+fn single(options: &Options) {
+    let builder = build(options, options.seed);
+    let data = &builder.build_data;
+    let stats = measure(data);
+
+    println!("{} (depth {}, seed {})", data.map.name, data.map.depth, options.seed);
+    for y in 0..data.map.height {
+        let row: String = (0..data.map.width)
+            .map(|x| {
+                let idx = data.map.xy_idx(x, y);
+                match &data.starting_position {
+                    Some(start) if start.x == x && start.y == y => '@',
+                    _ => map::tile_ascii(data.map.tiles[idx]),
+                }
+            })
+            .collect();
+        println!("{}", row);
+    }
+
+    let report = &data.map.report;
+    println!();
+    println!(
+        "Attempts:     {}{}",
+        report.attempts,
+        if report.fallback { " (fallback)" } else { "" }
+    );
+    println!("Passed:       {}", list(&report.passed));
+    println!("Failed:       {}", list(&report.failed));
+    println!("Floor:        {:.1}%", stats.floor_percent);
+    println!("Regions:      {}", stats.regions);
+    match stats.path_length {
+        Some(length) => println!("Start to exit: {} steps", length),
+        None => println!("Start to exit: unreachable"),
+    }
+    match stats.rooms {
+        Some(rooms) => println!("Rooms:        {}", rooms),
+        None => println!("Rooms:        -"),
+    }
+    println!("Locks:        {}", data.locks.len());
+    for lock in data.locks.iter() {
+        println!(
+            "  {:>4} door at {}, key in room {}, opens rooms {:?}",
+            lock.id, lock.door, lock.key_room, lock.opens
+        );
+    }
+    println!("Spawns:       {}", stats.spawns.values().sum::<usize>());
+    for (name, count) in stats.spawns.iter() {
+        println!("  {:>4} {}", count, name);
+    }
+}
+
+fn batch(options: &Options, count: u64) {
+    // Panics are reported as failures below; don't let the default hook spam the output
+    panic::set_hook(Box::new(|_| {}));
+
+    let mut failures = 0;
+    let mut rebuilt = 0;
+    let mut floor_total = 0.0;
+    let mut path_total = 0;
+    let mut paths = 0;
+    for seed in options.seed..options.seed + count {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let builder = build(options, seed);
+            let mut problems = problems(&builder.build_data);
+            if !same_level(&builder.build_data, &build(options, seed).build_data) {
+                problems.push("not the same level when built again".to_string());
+            }
+            (
+                problems,
+                builder.build_data.map.report.clone(),
+                measure(&builder.build_data),
+            )
+        }));
+
+        match result {
+            Err(cause) => {
+                failures += 1;
+                println!("seed {}: panicked: {}", seed, panic_message(&cause));
+            },
+            Ok((problems, report, stats)) => {
+                floor_total += stats.floor_percent;
+                if let Some(length) = stats.path_length {
+                    path_total += length;
+                    paths += 1;
+                }
+                if report.fallback {
+                    failures += 1;
+                    println!("seed {}: fell back after {} attempts", seed, report.attempts);
+                } else if report.attempts > 1 {
+                    rebuilt += 1;
+                    println!("seed {}: passed its checks on attempt {}", seed, report.attempts);
+                }
+                if !problems.is_empty() {
+                    failures += 1;
+                    println!("seed {}: {}", seed, problems.join(", "));
+                }
+            },
+        }
+    }
+
+    let _ = panic::take_hook();
+    println!();
+    println!("{} levels built, {} rebuilt, {} failed", count, rebuilt, failures);
+    if count > 0 {
+        println!("Average floor: {:.1}%", floor_total / count as f32);
+    }
+    if paths > 0 {
+        println!("Average start to exit: {:.1} steps", path_total as f32 / paths as f32);
+    }
+
+    if failures > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn problems(data: &BuilderMap) -> Vec<String> {
+    let mut problems = Vec::new();
+    match &data.starting_position {
+        None => problems.push("no starting position".to_string()),
+        Some(start) => {
+            let idx = data.map.xy_idx(start.x, start.y);
+            if !map::tile_walkable(data.map.tiles[idx]) {
+                problems.push("start is inside a wall".to_string());
+            }
+        },
+    }
+
+    if !data.map.tiles.contains(&TileType::DownStairs) {
+        problems.push("no down stairs".to_string());
+    } else if data.starting_position.is_some() && path_to_exit(data).is_none() {
+        problems.push("down stairs unreachable".to_string());
+    }
+
+    problems
+}
+
+/// Whether two builds made the same level: the same tiles, start and spawns.
+fn same_level(a: &BuilderMap, b: &BuilderMap) -> bool {
+    let start = |data: &BuilderMap| data.starting_position.as_ref().map(|start| (start.x, start.y));
+    a.map.tiles == b.map.tiles && start(a) == start(b) && a.spawn_list == b.spawn_list
+}
+
+fn list(checks: &[map::LevelCheck]) -> String {
+    if checks.is_empty() {
+        "-".to_string()
+    } else {
+        checks
+            .iter()
+            .map(|check| check.to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+}
+
+fn measure(data: &BuilderMap) -> Stats {
+    let map = &data.map;
+    let floor = map.tiles.iter().filter(|tt| map::tile_walkable(**tt)).count();
+
+    let mut spawns = BTreeMap::new();
+    for (_, name) in data.spawn_list.iter() {
+        *spawns.entry(name.clone()).or_insert(0) += 1;
+    }
+
+    Stats {
+        floor_percent: floor as f32 * 100.0 / map.tiles.len() as f32,
+        regions: count_regions(map),
+        path_length: path_to_exit(data),
+        rooms: data.rooms.as_ref().map(|rooms| rooms.len()),
+        spawns,
+    }
+}
+
+/// Walkable neighbours of a tile, including diagonals - the same moves the player has.
+fn neighbours(map: &Map, idx: usize) -> impl Iterator<Item = usize> + '_ {
+    let (x, y) = (idx as i32 % map.width, idx as i32 / map.width);
+    [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)]
+        .into_iter()
+        .map(move |(dx, dy)| (x + dx, y + dy))
+        .filter(|(x, y)| *x >= 0 && *y >= 0 && *x < map.width && *y < map.height)
+        .map(|(x, y)| map.xy_idx(x, y))
+        .filter(|idx| map::tile_walkable(map.tiles[*idx]))
+}
+
+/// Breadth-first distances from one tile to every walkable tile that can be reached from it.
+fn distances(map: &Map, from: usize) -> Vec<Option<usize>> {
+    let mut distance = vec![None; map.tiles.len()];
+    let mut open = VecDeque::new();
+    distance[from] = Some(0);
+    open.push_back(from);
+
+    while let Some(idx) = open.pop_front() {
+        let next = distance[idx].unwrap() + 1;
+        for n in neighbours(map, idx) {
+            if distance[n].is_none() {
+                distance[n] = Some(next);
+                open.push_back(n);
+            }
+        }
+    }
+
+    distance
+}
+
+fn count_regions(map: &Map) -> usize {
+    let mut seen = vec![false; map.tiles.len()];
+    let mut regions = 0;
+    for idx in 0..map.tiles.len() {
+        if seen[idx] || !map::tile_walkable(map.tiles[idx]) {
+            continue;
+        }
+
+        regions += 1;
+        for (tile, distance) in distances(map, idx).iter().enumerate() {
+            if distance.is_some() {
+                seen[tile] = true;
+            }
+        }
+    }
+
+    regions
+}
+
+fn path_to_exit(data: &BuilderMap) -> Option<usize> {
+    let start = data.starting_position.as_ref()?;
+    let distance = distances(&data.map, data.map.xy_idx(start.x, start.y));
+    data.map
+        .tiles
+        .iter()
+        .enumerate()
+        .filter(|(_, tt)| **tt == TileType::DownStairs)
+        .filter_map(|(idx, _)| distance[idx])
+        .min()
+}
+
+fn panic_message(cause: &Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = cause.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = cause.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
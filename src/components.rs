@@ -22,6 +22,10 @@ pub struct OtherLevelPosition {
     pub x: i32,
     pub y: i32,
     pub depth: i32,
+    #[serde(default)]
+    pub branch: usize,
+    #[serde(default)]
+    pub area: crate::map::WorldCoord,
 }
 
 #[derive(Component, ConvertSaveload, Clone)]
@@ -56,6 +60,9 @@ pub struct Initiative {
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Vendor {
     pub categories: Vec<String>,
+    /// How many of each item have been bought since the vendor last restocked.
+    #[serde(default)]
+    pub sold: HashMap<String, i32>,
 }
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
@@ -76,6 +83,8 @@ pub struct ApplyTeleport {
     pub dest_x: i32,
     pub dest_y: i32,
     pub dest_depth: i32,
+    #[serde(default)]
+    pub dest_branch: usize,
 }
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
@@ -156,6 +165,8 @@ pub enum Skill {
     Melee,
     Defense,
     Magic,
+    Perception,
+    Lockpicking,
 }
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
@@ -211,6 +222,10 @@ pub struct AttributeBonus {
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct CursedItem {}
 
+/// On an item that its carrier tried to equip and couldn't. They leave it be until it changes hands.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct EquipFailed {}
+
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Consumable {
     pub max_charges: i32,
@@ -259,11 +274,31 @@ pub struct ProvidesHealing {
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct BlocksVisibility {}
 
+/// A prop that a ranged fighter can shelter behind.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ProvidesCover {}
+
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Door {
     pub open: bool,
+    /// Won't open until it is unlocked, its lock is picked or it is broken down.
+    #[serde(default)]
+    pub locked: bool,
+    /// The id of the `Key` that unlocks it, if any does.
+    #[serde(default)]
+    pub key: Option<i32>,
 }
 
+/// Unlocks the locked door with the same key id.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Key {
+    pub id: i32,
+}
+
+/// A door made to pass for a wall. It stays shut, and `Hidden`, until somebody finds it.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct SecretDoor {}
+
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct InBackpack {
     pub owner: Entity,
@@ -408,6 +443,8 @@ pub struct TeleportTo {
     pub x: i32,
     pub y: i32,
     pub depth: i32,
+    #[serde(default)]
+    pub branch: usize,
     pub player_only: bool,
 }
 
@@ -470,6 +507,11 @@ pub struct DamageOverTime {
     pub damage: i32,
 }
 
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Knockback {
+    pub distance: i32,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SpecialAbility {
     pub spell: String,
@@ -505,6 +547,42 @@ pub struct WantsToShoot {
     pub target: Entity,
 }
 
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct UsesItems {}
+
+/// Hazardous terrain this entity can cross unharmed, by the `hazard` names in `tiles.json`.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct HazardResistance {
+    pub hazards: Vec<String>,
+}
+
+/// Digs through diggable terrain, leaving `into` behind, and breaks any door in the way. On an equipped weapon
+/// this lets its wielder tunnel by walking into walls.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Digs {
+    pub into: super::map::TileType,
+}
+
+/// Catches fire along with the ground it stands on, and is consumed when the fire burns out.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Flammable {}
+
+/// Sets the tiles it hits alight for this many turns.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Ignites {
+    pub turns: i32,
+}
+
+/// Releases a cloud of poison gas of this density.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct ReleasesGas {
+    pub density: i32,
+}
+
+/// Sees as far in the dark as in daylight.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Darkvision {}
+
 // Serialization helper code. We need to implement ConvertSaveLoad for each type that contains an
 // Entity.
 
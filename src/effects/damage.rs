@@ -168,8 +168,8 @@ pub fn death(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
     let mut pools = ecs.write_storage::<Pools>();
     let mut attributes = ecs.write_storage::<Attributes>();
 
-    if let Some(pos) = entity_position(ecs, target) {
-        crate::spatial::remove_entity(target, pos as usize);
+    for idx in entity_tiles(ecs, target) {
+        crate::spatial::remove_entity(target, idx as usize);
     }
 
     if let Some(source) = effect.creator {
@@ -8,6 +8,7 @@ mod hunger;
 mod movement;
 mod particles;
 mod targeting;
+mod terrain;
 mod triggers;
 
 pub use targeting::*;
@@ -31,7 +32,12 @@ pub enum EffectType {
     TriggerFire { trigger: Entity },
     Slow { initiative_penalty : f32 },
     DamageOverTime { damage : i32 },
-    TeleportTo { x:i32, y:i32, depth: i32, player_only : bool },
+    TeleportTo { x:i32, y:i32, depth: i32, branch: usize, player_only : bool },
+    Knockback { distance : i32 },
+    Stumble,
+    Dig { into : TileType },
+    Ignite { turns : i32 },
+    PoisonGas { density : i32 },
     AttributeEffect { bonus : AttributeBonus, name : String, duration : i32 },
     Particle { glyph: rltk::FontCharType, fg : rltk::RGB, bg: rltk::RGB, lifespan: f32 },
     ParticleProjectile { glyph: rltk::FontCharType, fg : rltk::RGB, bg: rltk::RGB, lifespan: f32, speed: f32, path: Vec<Point> }
@@ -98,6 +104,7 @@ fn tile_effect_hits_entities(effect: &EffectType) -> bool {
             | EffectType::Mana { .. }
             | EffectType::Confusion { .. }
             | EffectType::TeleportTo { .. }
+            | EffectType::Knockback { .. }
             | EffectType::AttributeEffect { .. }
             | EffectType::Slow { .. }
             | EffectType::DamageOverTime { .. }
@@ -114,6 +121,9 @@ fn affect_tile(ecs: &mut World, effect: &mut EffectSpawner, tile_idx: i32) {
         EffectType::Bloodstain => damage::bloodstain(ecs, tile_idx),
         EffectType::Particle { .. } => particles::particle_to_tile(ecs, tile_idx, effect),
         EffectType::ParticleProjectile { .. } => particles::projectile(ecs, tile_idx, &effect),
+        EffectType::Dig { .. } => terrain::dig(ecs, effect, tile_idx),
+        EffectType::Ignite { .. } => terrain::ignite(ecs, effect, tile_idx),
+        EffectType::PoisonGas { .. } => terrain::poison_gas(ecs, effect, tile_idx),
         _ => {},
     }
 }
@@ -137,13 +147,25 @@ fn affect_entity(ecs: &mut World, effect: &mut EffectSpawner, target: Entity) {
         EffectType::DamageOverTime { .. } => damage::damage_over_time(ecs, effect, target),
         EffectType::EntityDeath => damage::death(ecs, effect, target),
         EffectType::Healing { .. } => damage::heal_damage(ecs, effect, target),
+        EffectType::Ignite { .. } => {
+            if let Some(pos) = entity_position(ecs, target) {
+                terrain::ignite(ecs, effect, pos)
+            }
+        },
+        EffectType::Knockback { .. } => movement::knockback(ecs, effect, target),
         EffectType::Mana { .. } => damage::restore_mana(ecs, effect, target),
         EffectType::Particle { .. } => {
             if let Some(pos) = entity_position(ecs, target) {
                 particles::particle_to_tile(ecs, pos, effect)
             }
         },
+        EffectType::PoisonGas { .. } => {
+            if let Some(pos) = entity_position(ecs, target) {
+                terrain::poison_gas(ecs, effect, pos)
+            }
+        },
         EffectType::Slow { .. } => damage::slow(ecs, effect, target),
+        EffectType::Stumble => movement::stumble(ecs, target),
         EffectType::TeleportTo { .. } => movement::apply_teleport(ecs, effect, target),
         EffectType::WellFed => hunger::well_fed(ecs, effect, target),
         _ => {},
@@ -9,6 +9,7 @@ pub fn apply_teleport(ecs: &mut World, destination: &EffectSpawner, target: Enti
         x,
         y,
         depth,
+        branch,
         player_only,
     } = &destination.effect_type
     {
@@ -22,9 +23,82 @@ pub fn apply_teleport(ecs: &mut World, destination: &EffectSpawner, target: Enti
                         dest_x: *x,
                         dest_y: *y,
                         dest_depth: *depth,
+                        dest_branch: *branch,
                     },
                 )
                 .expect("Unable to insert");
         }
     }
 }
+
+/// Shoves the target straight away from whoever caused the effect.
+pub fn knockback(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
+    if let EffectType::Knockback { distance } = &effect.effect_type {
+        let from = effect.creator.and_then(|creator| entity_position(ecs, creator));
+        if let (Some(from), Some(to)) = (from, entity_position(ecs, target)) {
+            let width = ecs.fetch::<Map>().width;
+            let dx = (to % width - from % width).signum();
+            let dy = (to / width - from / width).signum();
+            if dx != 0 || dy != 0 {
+                push(ecs, target, dx, dy, *distance);
+            }
+        }
+    }
+}
+
+/// Staggers the target one tile in a random direction.
+pub fn stumble(ecs: &mut World, target: Entity) {
+    let (dx, dy) = (crate::rng::range(-1, 2), crate::rng::range(-1, 2));
+    if dx != 0 || dy != 0 {
+        push(ecs, target, dx, dy, 1);
+    }
+}
+
+/// Moves the target up to `distance` tiles in a direction, stopping at the first thing in the way. Unlike a
+/// normal move, this will happily put it somewhere dangerous.
+fn push(ecs: &mut World, target: Entity, dx: i32, dy: i32, distance: i32) {
+    // Too big to shove around
+    if ecs.read_storage::<TileSize>().get(target).is_some() {
+        return;
+    }
+
+    let map = ecs.fetch::<Map>();
+    let mut positions = ecs.write_storage::<Position>();
+    let Some(pos) = positions.get_mut(target) else {
+        return;
+    };
+
+    let (mut x, mut y) = (pos.x, pos.y);
+    for _ in 0..distance {
+        let (nx, ny) = (x + dx, y + dy);
+        if nx < 1
+            || ny < 1
+            || nx > map.width - 2
+            || ny > map.height - 2
+            || crate::spatial::is_blocked(map.xy_idx(nx, ny))
+        {
+            break;
+        }
+        (x, y) = (nx, ny);
+    }
+
+    if (x, y) == (pos.x, pos.y) {
+        return;
+    }
+
+    crate::spatial::move_entity(target, map.xy_idx(pos.x, pos.y), map.xy_idx(x, y));
+    pos.x = x;
+    pos.y = y;
+
+    if let Some(viewshed) = ecs.write_storage::<Viewshed>().get_mut(target) {
+        viewshed.dirty = true;
+    }
+    ecs.write_storage::<EntityMoved>()
+        .insert(target, EntityMoved {})
+        .expect("Unable to insert");
+
+    if target == *ecs.fetch::<Entity>() {
+        let mut player_pos = ecs.write_resource::<Point>();
+        *player_pos = Point::new(x, y);
+    }
+}
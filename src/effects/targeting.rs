@@ -1,6 +1,6 @@
 use specs::prelude::*;
 
-use crate::components::{Equipped, InBackpack, Position};
+use crate::components::{Equipped, InBackpack, Position, TileSize};
 use crate::map::Map;
 
 pub fn entity_position(ecs: &World, target: Entity) -> Option<i32> {
@@ -11,6 +11,20 @@ pub fn entity_position(ecs: &World, target: Entity) -> Option<i32> {
     None
 }
 
+/// Every tile the target covers; more than one for large creatures.
+pub fn entity_tiles(ecs: &World, target: Entity) -> Vec<i32> {
+    if let Some(pos) = ecs.read_storage::<Position>().get(target) {
+        let map = ecs.fetch::<Map>();
+        let sizes = ecs.read_storage::<TileSize>();
+        return map
+            .footprint(pos.x, pos.y, sizes.get(target))
+            .iter()
+            .map(|idx| *idx as i32)
+            .collect();
+    }
+    Vec::new()
+}
+
 pub fn aoe_tiles(map: &Map, target: rltk::Point, radius: i32) -> Vec<i32> {
     let mut blast_tiles = rltk::field_of_view(target, radius, &*map);
     blast_tiles.retain(|p| p.x > 0 && p.x < map.width - 1 && p.y > 0 && p.y < map.height - 1);
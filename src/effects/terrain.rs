@@ -0,0 +1,84 @@
+use specs::prelude::*;
+
+use super::*;
+
+/// Digs out the tile if it is diggable, and smashes any door standing in it.
+pub fn dig(ecs: &mut World, effect: &EffectSpawner, tile_idx: i32) {
+    let EffectType::Dig { into } = effect.effect_type else {
+        return;
+    };
+    let idx = tile_idx as usize;
+    let mut changed = false;
+
+    {
+        let mut map = ecs.fetch_mut::<Map>();
+        if map.can_dig(idx) {
+            map.tiles[idx] = into;
+            crate::spatial::set_terrain_blocked(idx, !map::tile_walkable(into));
+
+            // The level keeps the hole when the player leaves and comes back
+            ecs.write_resource::<MasterDungeonMap>().set_tile(&map, idx, into);
+            changed = true;
+        }
+    }
+
+    let doors: Vec<Entity> = {
+        let doors = ecs.read_storage::<Door>();
+        crate::spatial::get_tile_content_clone(idx)
+            .into_iter()
+            .filter(|entity| doors.get(*entity).is_some())
+            .collect()
+    };
+
+    for door in doors {
+        crate::spatial::remove_entity(door, idx);
+        ecs.entities().delete(door).expect("Unable to delete door");
+        ecs.fetch_mut::<Map>().view_blocked.remove(&idx);
+        changed = true;
+
+        if ecs.fetch::<Map>().visible_tiles[idx] {
+            crate::gamelog::Logger::new()
+                .append("The door is smashed to splinters!")
+                .log();
+        }
+    }
+
+    // Everyone may be able to see through the new gap
+    if changed {
+        for viewshed in (&mut ecs.write_storage::<Viewshed>()).join() {
+            viewshed.dirty = true;
+        }
+    }
+}
+
+/// Sets the tile alight. It only keeps burning if there is something there to burn.
+pub fn ignite(ecs: &mut World, effect: &EffectSpawner, tile_idx: i32) {
+    if let EffectType::Ignite { turns } = effect.effect_type {
+        let fuel = map::has_fuel(&ecs.fetch::<Map>(), tile_idx as usize, &ecs.read_storage::<Flammable>());
+        ecs.fetch_mut::<Map>().ignite(tile_idx as usize, turns, fuel);
+    }
+}
+
+pub fn poison_gas(ecs: &mut World, effect: &EffectSpawner, tile_idx: i32) {
+    if let EffectType::PoisonGas { density } = effect.effect_type {
+        ecs.fetch_mut::<Map>().add_gas(tile_idx as usize, density);
+    }
+}
+
+/// The tiles a digging bolt passes through, heading from `start` toward `end` until it has gone `range` tiles.
+pub fn tunnel_tiles(ecs: &World, start: i32, end: i32, range: i32) -> Vec<i32> {
+    let map = ecs.fetch::<Map>();
+    let start_pt = rltk::Point::new(start % map.width, start / map.width);
+    let (dx, dy) = (end % map.width - start_pt.x, end / map.width - start_pt.y);
+    let scale = range as f32 / f32::max(1.0, ((dx * dx + dy * dy) as f32).sqrt());
+    let end_pt = rltk::Point::new(
+        start_pt.x + (dx as f32 * scale).round() as i32,
+        start_pt.y + (dy as f32 * scale).round() as i32,
+    );
+
+    rltk::line2d(rltk::LineAlg::Bresenham, start_pt, end_pt)
+        .iter()
+        .filter(|pt| pt.x > 0 && pt.y > 0 && pt.x < map.width - 1 && pt.y < map.height - 1)
+        .map(|pt| map.xy_idx(pt.x, pt.y) as i32)
+        .collect()
+}
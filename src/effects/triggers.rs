@@ -168,10 +168,15 @@ fn event_trigger(creator: Option<Entity>, entity: Entity, targets: &Targets, ecs
     // Town Portal
     if ecs.read_storage::<TownPortal>().get(entity).is_some() {
         let map = ecs.fetch::<Map>();
-        if map.depth == 1 {
+        if map.on_surface() && map.area == map::WorldCoord::TOWN {
             crate::gamelog::Logger::new()
                 .append("You are already in town, so the scroll does nothing.")
                 .log();
+        } else if map.on_surface() {
+            // The portal back would only lead to the same depth, so it can't tell the wilds from the town
+            crate::gamelog::Logger::new()
+                .append("The scroll only works below ground, so it does nothing.")
+                .log();
         } else {
             crate::gamelog::Logger::new()
                 .append("You are telported back to town!")
@@ -213,6 +218,44 @@ fn event_trigger(creator: Option<Entity>, entity: Entity, targets: &Targets, ecs
         did_something = true;
     }
 
+    // Knockback
+    if let Some(knockback) = ecs.read_storage::<Knockback>().get(entity) {
+        add_effect(
+            creator,
+            EffectType::Knockback {
+                distance: knockback.distance,
+            },
+            targets.clone(),
+        );
+        did_something = true;
+    }
+
+    // Digging
+    if let Some(digs) = ecs.read_storage::<Digs>().get(entity) {
+        // A bolt keeps boring on past the target for as far as it can reach
+        let range = ecs.read_storage::<Ranged>().get(entity).map(|r| r.range);
+        let tunnel = match (targets, targeting::find_item_position(ecs, entity, creator), range) {
+            (Targets::Tile { tile_idx }, Some(start), Some(range)) => Targets::Tiles {
+                tiles: terrain::tunnel_tiles(ecs, start, *tile_idx, range),
+            },
+            _ => targets.clone(),
+        };
+        add_effect(creator, EffectType::Dig { into: digs.into }, tunnel);
+        did_something = true;
+    }
+
+    // Fire
+    if let Some(ignites) = ecs.read_storage::<Ignites>().get(entity) {
+        add_effect(creator, EffectType::Ignite { turns: ignites.turns }, targets.clone());
+        did_something = true;
+    }
+
+    // Poison gas
+    if let Some(gas) = ecs.read_storage::<ReleasesGas>().get(entity) {
+        add_effect(creator, EffectType::PoisonGas { density: gas.density }, targets.clone());
+        did_something = true;
+    }
+
     // Confusion
     if let Some(_confusion) = ecs.read_storage::<Confusion>().get(entity) {
         if let Some(duration) = ecs.read_storage::<Duration>().get(entity) {
@@ -233,6 +276,7 @@ fn event_trigger(creator: Option<Entity>, entity: Entity, targets: &Targets, ecs
                 x: teleport.x,
                 y: teleport.y,
                 depth: teleport.depth,
+                branch: teleport.branch,
                 player_only: teleport.player_only,
             },
             targets.clone(),
@@ -34,6 +34,14 @@ pub fn map_label(ecs: &World, draw_batch: &mut DrawBatch) {
         to_cp437('├'),
     );
     draw_batch.print_color(Point::new(x_pos + 1, 0), &map.name, ColorPair::new(white, black));
+
+    // Levels open to the sky show the time of day
+    if map.daylight {
+        let clock = crate::map::clock_text(ecs.fetch::<MasterDungeonMap>().turn);
+        draw_batch.set(Point::new(41, 0), ColorPair::new(box_gray, black), to_cp437('┤'));
+        draw_batch.set(Point::new(47, 0), ColorPair::new(box_gray, black), to_cp437('├'));
+        draw_batch.print_color(Point::new(42, 0), &clock, ColorPair::new(white, black));
+    }
 }
 
 fn draw_stats(ecs: &World, draw_batch: &mut DrawBatch, player_entity: &Entity) {
@@ -8,12 +8,13 @@ pub enum CheatMenuResult {
     Heal,
     Reveal,
     GodMode,
+    ExportLevel,
 }
 
 pub fn show_cheat_menu(_gs: &mut State, ctx: &mut Rltk) -> CheatMenuResult {
     let mut draw_batch = DrawBatch::new();
 
-    let count = 4;
+    let count = 5;
     let mut y = (25 - (count / 2)) as i32;
     menu_box(&mut draw_batch, 15, y, (count + 3) as i32, "Cheating!");
 
@@ -30,6 +31,8 @@ pub fn show_cheat_menu(_gs: &mut State, ctx: &mut Rltk) -> CheatMenuResult {
     menu_option(&mut draw_batch, 17, y, rltk::to_cp437('R'), "Reveal the map");
     y += 1;
     menu_option(&mut draw_batch, 17, y, rltk::to_cp437('G'), "God Mode (No Death)");
+    y += 1;
+    menu_option(&mut draw_batch, 17, y, rltk::to_cp437('E'), "Export this level");
 
     draw_batch.submit(6000).expect("Failed to submit draw batch cheat menu");
 
@@ -40,6 +43,7 @@ pub fn show_cheat_menu(_gs: &mut State, ctx: &mut Rltk) -> CheatMenuResult {
             VirtualKeyCode::H => CheatMenuResult::Heal,
             VirtualKeyCode::R => CheatMenuResult::Reveal,
             VirtualKeyCode::G => CheatMenuResult::GodMode,
+            VirtualKeyCode::E => CheatMenuResult::ExportLevel,
             VirtualKeyCode::Escape => CheatMenuResult::Cancel,
             _ => CheatMenuResult::NoResponse,
         },
@@ -9,6 +9,7 @@ pub mod main_menu;
 pub mod ranged_target_menu;
 pub mod remove_curse_menu;
 pub mod remove_item_menu;
+pub mod travel_menu;
 pub mod vendor_menu;
 
 pub use cheat_menu::*;
@@ -20,6 +21,7 @@ pub use main_menu::*;
 pub use ranged_target_menu::*;
 pub use remove_curse_menu::*;
 pub use remove_item_menu::*;
+pub use travel_menu::*;
 pub use vendor_menu::*;
 
 pub fn menu_box<T: ToString>(draw_batch: &mut DrawBatch, x: i32, y: i32, width: i32, title: T) {
@@ -0,0 +1,86 @@
+use super::*;
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum TravelResult {
+    NoResponse,
+    /// Close the map and stay where we are.
+    Cancel,
+    Stop,
+    Go {
+        to: map::WorldCoord,
+    },
+}
+
+/// The map of the overworld, for travelling from one area to the next. Areas not visited yet are dimmed, and those
+/// known to have a way down into the dungeon are marked.
+pub fn show_travel_map(gs: &mut State, ctx: &mut Rltk, at: map::WorldCoord) -> TravelResult {
+    let dm = gs.ecs.fetch::<MasterDungeonMap>();
+    let mut draw_batch = DrawBatch::new();
+
+    let y = 20;
+    let grid_x = 21;
+    menu_box(&mut draw_batch, 15, y, 15, "Travel");
+
+    for area in dm.region() {
+        let pt = Point::new(grid_x + area.coord.x * 4, y + (area.coord.y + map::REGION_REACH) * 2);
+        let visited = dm.visited(area.coord);
+        let (glyph, fg) = area.glyph();
+        let fg = if visited { fg } else { fg.to_greyscale() * 0.5 };
+
+        if area.coord == at {
+            draw_batch.set(
+                pt,
+                ColorPair::new(RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK)),
+                rltk::to_cp437('@'),
+            );
+        } else {
+            draw_batch.set(pt, ColorPair::new(fg, RGB::named(rltk::BLACK)), glyph);
+        }
+
+        if visited && area.entrance {
+            draw_batch.set(
+                Point::new(pt.x + 1, pt.y),
+                ColorPair::new(RGB::named(rltk::CYAN), RGB::named(rltk::BLACK)),
+                rltk::to_cp437('>'),
+            );
+        }
+    }
+
+    let name = dm.area(at).map_or(String::new(), |area| area.chain.clone());
+    let text_y = y + map::REGION_REACH * 4 + 2;
+    draw_batch.print_color(
+        Point::new(17, text_y),
+        &name,
+        ColorPair::new(RGB::named(rltk::WHITE), RGB::named(rltk::BLACK)),
+    );
+    draw_batch.print_color(
+        Point::new(17, text_y + 2),
+        "Direction keys to travel",
+        ColorPair::new(RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK)),
+    );
+    draw_batch.print_color(
+        Point::new(17, text_y + 3),
+        "ENTER to stop here",
+        ColorPair::new(RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK)),
+    );
+    draw_batch.print_color(
+        Point::new(17, text_y + 4),
+        "ESCAPE to cancel",
+        ColorPair::new(RGB::named(rltk::YELLOW), RGB::named(rltk::BLACK)),
+    );
+
+    draw_batch.submit(6000).expect("Failed to submit draw batch travel map");
+
+    match ctx.key {
+        None => TravelResult::NoResponse,
+        Some(VirtualKeyCode::Return) => TravelResult::Stop,
+        Some(VirtualKeyCode::Escape) => TravelResult::Cancel,
+        Some(key) => match crate::player::direction(key) {
+            Some((dx, dy)) => {
+                let to = map::WorldCoord::new(at.x + dx, at.y + dy);
+                if dm.area(to).is_some() { TravelResult::Go { to } } else { TravelResult::NoResponse }
+            },
+            None => TravelResult::NoResponse,
+        },
+    }
+}
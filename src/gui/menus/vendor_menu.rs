@@ -109,7 +109,11 @@ fn vendor_buy_menu(
 
     let vendors = gs.ecs.read_storage::<Vendor>();
 
-    let inventory = crate::raws::get_vendor_items(&vendors.get(vendor).unwrap().categories, &RAWS.lock().unwrap());
+    let vendor = vendors.get(vendor).unwrap();
+
+    // Anything the vendor has sold stays off the list until they restock it
+    let mut inventory = crate::raws::get_vendor_items(&vendor.categories, &RAWS.lock().unwrap());
+    inventory.retain(|(name, _)| !vendor.sold.contains_key(name));
     let count = inventory.len();
 
     let mut y = (25 - (count / 2)) as i32;
@@ -54,7 +54,11 @@ pub fn draw_tooltips(ecs: &World, ctx: &mut Rltk) {
     mouse_map_pos.0 += min_x - 1;
     mouse_map_pos.1 += min_y - 1;
 
-    if mouse_pos.0 < 1 || mouse_pos.0 > 49 || mouse_pos.1 < 1 || mouse_pos.1 > 40 {
+    if mouse_pos.0 < 1
+        || mouse_pos.0 > map::camera::VIEW_WIDTH
+        || mouse_pos.1 < 1
+        || mouse_pos.1 > map::camera::VIEW_HEIGHT
+    {
         return;
     }
     if mouse_map_pos.0 >= map.width - 1
@@ -0,0 +1,27 @@
+#![feature(stmt_expr_attributes)]
+#![feature(let_chains)]
+
+#[macro_use]
+extern crate lazy_static;
+extern crate serde;
+
+pub mod effects;
+pub mod gamelog;
+pub mod gamesystem;
+pub mod gui;
+pub mod map;
+pub mod player;
+pub mod raws;
+pub mod rng;
+pub mod spatial;
+pub mod spawner;
+
+mod components;
+mod map_builders;
+mod random_table;
+mod rex_assets;
+mod state;
+mod systems;
+
+mod prelude;
+pub use prelude::*;
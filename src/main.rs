@@ -1,32 +1,24 @@
-#![feature(stmt_expr_attributes)]
-#![feature(let_chains)]
+use rouge_like_game::*;
 
-#[macro_use]
-extern crate lazy_static;
-extern crate serde;
+/// The file named by `--level <file>`, if the game was started with one.
+fn level_arg() -> Option<String> { std::env::args().skip_while(|arg| arg != "--level").nth(1) }
 
-pub mod effects;
-pub mod gamelog;
-pub mod gamesystem;
-pub mod gui;
-pub mod map;
-pub mod player;
-pub mod raws;
-pub mod rng;
-pub mod spatial;
-pub mod spawner;
-
-mod components;
-mod map_builders;
-mod random_table;
-mod rex_assets;
-mod state;
-mod systems;
+fn main() -> rltk::BError {
+    // Level files are read with the tile definitions from the raws
+    if let Err(e) = raws::load_raws() {
+        eprintln!("Unable to load the raws: {}", e);
+        std::process::exit(1);
+    }
 
-mod prelude;
-pub use prelude::*;
+    // Load any custom level up front, so a broken file fails before a window opens
+    let custom_level = level_arg().map(|path| match map::LevelFile::load(&path) {
+        Ok(level) => level,
+        Err(e) => {
+            eprintln!("Unable to load level {}", e);
+            std::process::exit(1);
+        },
+    });
 
-fn main() -> rltk::BError {
     let mut context = RltkBuilder::simple(80, 60)
         .unwrap()
         .with_title("Roguelike Tutorial")
@@ -37,16 +29,7 @@ fn main() -> rltk::BError {
 
     context.with_post_scanlines(true);
 
-    let mut gs = State {
-        ecs: World::new(),
-        mapgen_index: 0,
-        mapgen_history: Vec::new(),
-        mapgen_timer: 0.0,
-        mapgen_next_state: Some(RunState::MainMenu {
-            menu_selection: gui::MainMenuSelection::NewGame,
-        }),
-        dispatcher: crate::systems::build(),
-    };
+    let mut gs = State::new();
 
     gs.ecs.register::<AlwaysTargetsSelf>();
     gs.ecs.register::<ApplyMove>();
@@ -61,21 +44,29 @@ fn main() -> rltk::BError {
     gs.ecs.register::<Consumable>();
     gs.ecs.register::<CursedItem>();
     gs.ecs.register::<DamageOverTime>();
+    gs.ecs.register::<Darkvision>();
+    gs.ecs.register::<Digs>();
     gs.ecs.register::<Door>();
     gs.ecs.register::<Duration>();
     gs.ecs.register::<EntryTrigger>();
     gs.ecs.register::<EntityMoved>();
+    gs.ecs.register::<EquipFailed>();
     gs.ecs.register::<EquipmentChanged>();
     gs.ecs.register::<Equippable>();
     gs.ecs.register::<Equipped>();
     gs.ecs.register::<Faction>();
+    gs.ecs.register::<Flammable>();
+    gs.ecs.register::<HazardResistance>();
     gs.ecs.register::<Hidden>();
     gs.ecs.register::<HungerClock>();
     gs.ecs.register::<IdentifiedItem>();
+    gs.ecs.register::<Ignites>();
     gs.ecs.register::<InBackpack>();
     gs.ecs.register::<InflictsDamage>();
     gs.ecs.register::<Initiative>();
     gs.ecs.register::<Item>();
+    gs.ecs.register::<Key>();
+    gs.ecs.register::<Knockback>();
     gs.ecs.register::<KnownSpells>();
     gs.ecs.register::<LightSource>();
     gs.ecs.register::<LootTable>();
@@ -92,14 +83,17 @@ fn main() -> rltk::BError {
     gs.ecs.register::<Player>();
     gs.ecs.register::<Pools>();
     gs.ecs.register::<Position>();
+    gs.ecs.register::<ProvidesCover>();
     gs.ecs.register::<ProvidesFood>();
     gs.ecs.register::<ProvidesHealing>();
     gs.ecs.register::<ProvidesIdentification>();
     gs.ecs.register::<ProvidesMana>();
     gs.ecs.register::<ProvidesRemoveCurse>();
     gs.ecs.register::<Quips>();
+    gs.ecs.register::<ReleasesGas>();
     gs.ecs.register::<Ranged>();
     gs.ecs.register::<Renderable>();
+    gs.ecs.register::<SecretDoor>();
     gs.ecs.register::<Skills>();
     gs.ecs.register::<Slow>();
     gs.ecs.register::<SingleActivation>();
@@ -113,6 +107,7 @@ fn main() -> rltk::BError {
     gs.ecs.register::<TeleportTo>();
     gs.ecs.register::<TileSize>();
     gs.ecs.register::<TownPortal>();
+    gs.ecs.register::<UsesItems>();
     gs.ecs.register::<Vendor>();
     gs.ecs.register::<Viewshed>();
     gs.ecs.register::<WantsToApproach>();
@@ -132,18 +127,20 @@ fn main() -> rltk::BError {
     gs.ecs.register::<DMSerializationHelper>();
     gs.ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
 
-    raws::load_raws();
-
     gs.ecs.insert(map::MasterDungeonMap::new());
+    gs.ecs.insert(map::FlowFields::new());
     gs.ecs.insert(Map::new(1, 64, 64, "New Map"));
     gs.ecs.insert(Point::new(0, 0));
     gs.ecs.insert(RunState::MapGeneration {});
     gs.ecs.insert(particle_system::ParticleBuilder::new());
-    gs.ecs.insert(rex_assets::RexAssets::new());
+    gs.ecs.insert(RexAssets::new());
     let player_entity = spawner::player(&mut gs.ecs, 0, 0);
     gs.ecs.insert(player_entity);
 
-    gs.generate_world_map(1, 0);
+    match custom_level {
+        Some(level) => gs.generate_custom_map(level),
+        None => gs.generate_world_map(MAIN_BRANCH, 1, 0),
+    }
 
     rltk::main_loop(context, gs)
 }
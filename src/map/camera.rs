@@ -2,19 +2,34 @@ use super::*;
 
 const SHOW_BOUNDARIES: bool = false;
 
+/// How many tiles across and down the map panel shows, inside its frame.
+pub const VIEW_WIDTH: i32 = 48;
+pub const VIEW_HEIGHT: i32 = 44;
+
+/// The span of map tiles on screen, as (min_x, max_x, min_y, max_y). The camera follows the player, but stops at the
+/// edges of the map rather than showing the void beyond them; a map smaller than the view sits in the middle of it.
 pub fn get_screen_bounds(ecs: &World, _ctx: &mut Rltk) -> (i32, i32, i32, i32) {
     let player_pos = ecs.fetch::<Point>();
-    let (x_chars, y_chars) = (48, 44);
+    let map = ecs.fetch::<Map>();
 
-    let center_x = (x_chars / 2) as i32;
-    let center_y = (y_chars / 2) as i32;
+    let min_x = follow(player_pos.x, map.width, VIEW_WIDTH);
+    let min_y = follow(player_pos.y, map.height, VIEW_HEIGHT);
 
-    let min_x = player_pos.x - center_x;
-    let max_x = min_x + x_chars as i32;
-    let min_y = player_pos.y - center_y;
-    let max_y = min_y + y_chars as i32;
+    (min_x, min_x + VIEW_WIDTH, min_y, min_y + VIEW_HEIGHT)
+}
+
+/// Where the view starts along one axis, keeping `pos` as near the middle as the map's edges allow.
+fn follow(pos: i32, map_size: i32, view_size: i32) -> i32 {
+    if map_size <= view_size {
+        (map_size - view_size) / 2
+    } else {
+        (pos - view_size / 2).clamp(0, map_size - view_size)
+    }
+}
 
-    (min_x, max_x, min_y, max_y)
+/// Whether a spot, counted from the top left corner of the view, falls inside it.
+fn in_view(screen_x: i32, screen_y: i32) -> bool {
+    (0..VIEW_WIDTH).contains(&screen_x) && (0..VIEW_HEIGHT).contains(&screen_y)
 }
 
 pub fn render_camera(ecs: &World, ctx: &mut Rltk) {
@@ -71,11 +86,7 @@ pub fn render_camera(ecs: &World, ctx: &mut Rltk) {
                         let entity_screen_x = (cx + pos.x) - min_x;
                         let entity_screen_y = (cy + pos.y) - min_y;
 
-                        if entity_screen_x > 0
-                            && entity_screen_x < map_width
-                            && entity_screen_y > 0
-                            && entity_screen_y < map_height
-                        {
+                        if in_view(entity_screen_x, entity_screen_y) {
                             draw_batch.set(
                                 Point::new(entity_screen_x + 1, entity_screen_y + 1),
                                 ColorPair::new(render.fg, render.bg),
@@ -91,11 +102,7 @@ pub fn render_camera(ecs: &World, ctx: &mut Rltk) {
                 let entity_screen_x = pos.x - min_x;
                 let entity_screen_y = pos.y - min_y;
 
-                if entity_screen_x > 0
-                    && entity_screen_x < map_width
-                    && entity_screen_y > 0
-                    && entity_screen_y < map_height
-                {
+                if in_view(entity_screen_x, entity_screen_y) {
                     draw_batch.set(
                         Point::new(entity_screen_x + 1, entity_screen_y + 1),
                         ColorPair::new(render.fg, render.bg),
@@ -105,10 +112,8 @@ pub fn render_camera(ecs: &World, ctx: &mut Rltk) {
             }
         }
 
-        if targets.get(*entity).is_some() {
-            let entity_screen_x = pos.x - min_x;
-            let entity_screen_y = pos.y - min_y;
-
+        let (entity_screen_x, entity_screen_y) = (pos.x - min_x, pos.y - min_y);
+        if targets.get(*entity).is_some() && in_view(entity_screen_x, entity_screen_y) {
             draw_batch.set(
                 Point::new(entity_screen_x, entity_screen_y + 1),
                 ColorPair::new(RGB::named(rltk::RED), RGB::named(rltk::YELLOW)),
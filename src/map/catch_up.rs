@@ -0,0 +1,204 @@
+use std::collections::HashSet;
+
+use super::*;
+
+/// Turns of off-screen time that make up one coarse simulation step.
+const TURNS_PER_STEP: i32 = 20;
+/// However long the player was away, a level never simulates more than this many steps.
+const MAX_STEPS: i32 = 100;
+/// Turns it takes a monster to recover one hit point (or mana point) while nobody is watching.
+const TURNS_PER_RECOVERY: i32 = 10;
+/// Turns it takes a vendor to get one sold item back on the shelves.
+const TURNS_PER_RESTOCK: i32 = 100;
+/// How far an item user will go to scavenge something lying around.
+const SCAVENGE_RADIUS: f32 = 6.0;
+
+/// Coarse catch-up for a level the player has just returned to, `turns` after leaving it. Monsters heal, wander off,
+/// scavenge whatever is lying around and forget what they were doing; vendors restock.
+pub fn simulate(ecs: &mut World, turns: i32) {
+    if turns < 1 {
+        return;
+    }
+
+    let steps = i32::min(turns / TURNS_PER_STEP, MAX_STEPS);
+
+    recover(ecs, turns);
+    wander(ecs, steps);
+    scavenge(ecs, steps);
+    settle(ecs, turns);
+    restock(ecs, turns / TURNS_PER_RESTOCK);
+}
+
+/// Called once per player turn: vendors keep restocking while the player is on their level, not just while away.
+pub fn restock_vendors(ecs: &mut World) {
+    if ecs.fetch::<MasterDungeonMap>().turn % TURNS_PER_RESTOCK == 0 {
+        restock(ecs, 1);
+    }
+}
+
+fn recover(ecs: &mut World, turns: i32) {
+    let entities = ecs.entities();
+    let positions = ecs.read_storage::<Position>();
+    let mut pools = ecs.write_storage::<Pools>();
+    let player_entity = ecs.fetch::<Entity>();
+    let amount = turns / TURNS_PER_RECOVERY;
+
+    for (entity, stats, _pos) in (&entities, &mut pools, &positions).join() {
+        if entity != *player_entity && stats.hit_points.current > 0 {
+            stats.hit_points.current = i32::min(stats.hit_points.max, stats.hit_points.current + amount);
+            stats.mana.current = i32::min(stats.mana.max, stats.mana.current + amount);
+        }
+    }
+}
+
+fn wander(ecs: &mut World, steps: i32) {
+    let map = ecs.fetch::<Map>();
+    let entities = ecs.entities();
+    let mut positions = ecs.write_storage::<Position>();
+    let mut move_modes = ecs.write_storage::<MoveMode>();
+    let blockers = ecs.read_storage::<BlocksTile>();
+    let sizes = ecs.read_storage::<TileSize>();
+    let vendors = ecs.read_storage::<Vendor>();
+    let player_entity = ecs.fetch::<Entity>();
+
+    // Tiles that someone is standing on
+    let mut occupied: HashSet<usize> = HashSet::new();
+    for (entity, pos) in (&entities, &positions).join() {
+        if blockers.get(entity).is_some() || entity == *player_entity {
+            occupied.extend(map.footprint(pos.x, pos.y, sizes.get(entity)));
+        }
+    }
+
+    for (entity, pos, mode) in (&entities, &mut positions, &mut move_modes).join() {
+        // Large creatures and shopkeepers stay put
+        if entity == *player_entity || sizes.get(entity).is_some() || vendors.get(entity).is_some() {
+            continue;
+        }
+
+        match &mut mode.mode {
+            Movement::Static => continue,
+            Movement::Random => {},
+            // Any route they were following is stale now
+            Movement::RandomWaypoint { path } => *path = None,
+        }
+
+        let blocks = blockers.get(entity).is_some();
+        for _ in 0..steps {
+            let (x, y) = (pos.x + crate::rng::range(-1, 2), pos.y + crate::rng::range(-1, 2));
+            if x < 1 || x > map.width - 2 || y < 1 || y > map.height - 2 {
+                continue;
+            }
+
+            let dest = map.xy_idx(x, y);
+            if !tile_walkable(map.tiles[dest]) || occupied.contains(&dest) {
+                continue;
+            }
+
+            if blocks {
+                occupied.remove(&map.xy_idx(pos.x, pos.y));
+                occupied.insert(dest);
+            }
+
+            pos.x = x;
+            pos.y = y;
+        }
+    }
+}
+
+fn scavenge(ecs: &mut World, steps: i32) {
+    if steps < 1 {
+        return;
+    }
+
+    let entities = ecs.entities();
+    let mut positions = ecs.write_storage::<Position>();
+    let mut backpack = ecs.write_storage::<InBackpack>();
+    let mut dirty = ecs.write_storage::<EquipmentChanged>();
+    let mut equip_failed = ecs.write_storage::<EquipFailed>();
+    let uses_items = ecs.read_storage::<UsesItems>();
+    let items = ecs.read_storage::<Item>();
+    let healing = ecs.read_storage::<ProvidesHealing>();
+    let equippable = ecs.read_storage::<Equippable>();
+    let ranged = ecs.read_storage::<Ranged>();
+    let player_entity = ecs.fetch::<Entity>();
+
+    // Gear, potions and offensive items lying around
+    let mut loose: Vec<(Entity, Point)> = (&entities, &items, &positions)
+        .join()
+        .filter(|(item, _, _)| {
+            healing.get(*item).is_some() || equippable.get(*item).is_some() || ranged.get(*item).is_some()
+        })
+        .map(|(item, _, pos)| (item, Point::new(pos.x, pos.y)))
+        .collect();
+
+    let scavengers: Vec<(Entity, Point)> = (&entities, &uses_items, &positions)
+        .join()
+        .filter(|(entity, _, _)| *entity != *player_entity)
+        .map(|(entity, _, pos)| (entity, Point::new(pos.x, pos.y)))
+        .collect();
+
+    // The longer we were gone, the more they will have gathered up
+    let max_finds = 1 + steps / 10;
+    for (scavenger, at) in scavengers.iter() {
+        let mut finds = 0;
+        loose.retain(|(item, pos)| {
+            if finds < max_finds && rltk::DistanceAlg::Pythagoras.distance2d(*at, *pos) <= SCAVENGE_RADIUS {
+                positions.remove(*item);
+                equip_failed.remove(*item);
+                backpack
+                    .insert(*item, InBackpack { owner: *scavenger })
+                    .expect("Unable to insert backpack entry");
+                finds += 1;
+                false
+            } else {
+                true
+            }
+        });
+
+        if finds > 0 {
+            dirty.insert(*scavenger, EquipmentChanged {}).expect("Unable to insert");
+        }
+    }
+}
+
+fn settle(ecs: &mut World, turns: i32) {
+    let entities = ecs.entities();
+    let positions = ecs.read_storage::<Position>();
+    let player_entity = ecs.fetch::<Entity>();
+
+    // Whatever they were chasing or running from is long gone
+    let level: Vec<Entity> = (&entities, &positions)
+        .join()
+        .map(|(entity, _)| entity)
+        .filter(|entity| *entity != *player_entity)
+        .collect();
+
+    let mut chasing = ecs.write_storage::<Chasing>();
+    let mut approach = ecs.write_storage::<WantsToApproach>();
+    let mut flee = ecs.write_storage::<WantsToFlee>();
+    for entity in level.iter() {
+        chasing.remove(*entity);
+        approach.remove(*entity);
+        flee.remove(*entity);
+    }
+
+    // Status effects have mostly worn off; the survivors expire on the next tick as usual
+    let mut durations = ecs.write_storage::<Duration>();
+    let statuses = ecs.read_storage::<StatusEffect>();
+    for (duration, status) in (&mut durations, &statuses).join() {
+        if level.contains(&status.target) {
+            duration.turns = i32::max(1, duration.turns - turns);
+        }
+    }
+}
+
+/// Puts `restocked` of each sold item back on the shelves of the vendors on the level.
+fn restock(ecs: &mut World, restocked: i32) {
+    let positions = ecs.read_storage::<Position>();
+    let mut vendors = ecs.write_storage::<Vendor>();
+
+    for (vendor, _pos) in (&mut vendors, &positions).join() {
+        vendor.sold.values_mut().for_each(|sold| *sold -= restocked);
+        vendor.sold.retain(|_, sold| *sold > 0);
+    }
+}
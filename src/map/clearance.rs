@@ -0,0 +1,60 @@
+use super::*;
+
+/// Pathing view for an entity bigger than one tile. A tile is only a valid step if the whole footprint, anchored
+/// at its top-left corner, fits there without overlapping walls or anyone other than the entities being ignored.
+pub struct ClearanceMap<'a> {
+    map: &'a Map,
+    width: i32,
+    height: i32,
+    ignore: Vec<Entity>,
+}
+
+impl<'a> ClearanceMap<'a> {
+    /// `ignore` should hold at least the mover itself, since its own body always overlaps the next step.
+    pub fn new(map: &'a Map, size: &TileSize, ignore: Vec<Entity>) -> ClearanceMap<'a> {
+        ClearanceMap {
+            map,
+            width: size.x,
+            height: size.y,
+            ignore,
+        }
+    }
+
+    /// Does the footprint fit with its top-left corner at (x, y)?
+    pub fn fits(&self, x: i32, y: i32) -> bool {
+        if x < 1 || y < 1 || x + self.width > self.map.width - 1 || y + self.height > self.map.height - 1 {
+            return false;
+        }
+
+        for ty in y..y + self.height {
+            for tx in x..x + self.width {
+                if crate::spatial::is_blocked_ignoring(self.map.xy_idx(tx, ty), &self.ignore) {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+}
+
+impl<'a> BaseMap for ClearanceMap<'a> {
+    fn get_available_exits(&self, idx: usize) -> rltk::SmallVec<[(usize, f32); 10]> {
+        const DIAGONAL_COST: f32 = 1.5;
+        let mut exits = rltk::SmallVec::new();
+        let x = idx as i32 % self.map.width;
+        let y = idx as i32 / self.map.width;
+
+        for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)] {
+            if self.fits(x + dx, y + dy) {
+                let cost = self.map.step_cost(idx, self.map.xy_idx(x + dx, y + dy));
+                let step_cost = if dx != 0 && dy != 0 { cost * DIAGONAL_COST } else { cost };
+                exits.push((self.map.xy_idx(x + dx, y + dy), step_cost));
+            }
+        }
+
+        exits
+    }
+
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 { self.map.get_pathing_distance(idx1, idx2) }
+}
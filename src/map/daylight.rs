@@ -0,0 +1,75 @@
+use rltk::RGB;
+
+use super::Map;
+
+/// Turns in a whole day and night; each turn is a minute.
+const DAY_LENGTH: i32 = 24 * 60;
+/// The adventure begins at eight in the morning.
+const START_MINUTE: i32 = 8 * 60;
+/// When the sky starts to brighten, and when it is full day.
+const DAWN: (i32, i32) = (5 * 60, 7 * 60);
+/// When the sun starts to set, and when it is full night.
+const DUSK: (i32, i32) = (18 * 60, 20 * 60);
+/// How far anything can see in pitch darkness without darkvision.
+const DARK_SIGHT: i32 = 2;
+/// Light at or above this is enough to see by at any distance.
+const LIT: f32 = 0.25;
+
+/// The minute of the day on the world clock.
+pub fn minute_of_day(turn: i32) -> i32 { (turn + START_MINUTE).rem_euclid(DAY_LENGTH) }
+
+/// The world clock as it would be read off a sundial, e.g. "14:05".
+pub fn clock_text(turn: i32) -> String {
+    let minute = minute_of_day(turn);
+    format!("{:02}:{:02}", minute / 60, minute % 60)
+}
+
+/// How bright the open sky is, from 0.0 at midnight to 1.0 at noon.
+pub fn sky_brightness(turn: i32) -> f32 {
+    const NIGHT: f32 = 0.05;
+    let minute = minute_of_day(turn);
+    let ramp = |from: i32, to: i32| (minute - from) as f32 / (to - from) as f32;
+
+    if minute < DAWN.0 || minute >= DUSK.1 {
+        NIGHT
+    } else if minute < DAWN.1 {
+        NIGHT + (1.0 - NIGHT) * ramp(DAWN.0, DAWN.1)
+    } else if minute < DUSK.0 {
+        1.0
+    } else {
+        1.0 - (1.0 - NIGHT) * ramp(DUSK.0, DUSK.1)
+    }
+}
+
+/// The colour of the light the sky casts; a deep blue at night.
+pub fn sky_light(turn: i32) -> RGB {
+    let brightness = sky_brightness(turn);
+    RGB::from_f32(0.1, 0.1, 0.3).lerp(RGB::from_f32(1.0, 1.0, 1.0), brightness) * brightness.max(0.3)
+}
+
+/// How much light a tile gets from all around, before any light sources are added.
+pub fn ambient_light(map: &Map, turn: i32) -> RGB {
+    if map.daylight {
+        sky_light(turn)
+    } else if map.outdoors {
+        RGB::from_f32(1.0, 1.0, 1.0)
+    } else {
+        RGB::from_f32(0.0, 0.0, 0.0)
+    }
+}
+
+/// How bright a light is, judged by its strongest colour.
+pub fn brightness(light: RGB) -> f32 { light.r.max(light.g).max(light.b) }
+
+/// How far something with the given vision range can make out unlit tiles in this ambient light.
+pub fn sight_in_dark(range: i32, ambient: RGB, darkvision: bool) -> i32 {
+    if darkvision { range } else { i32::max(DARK_SIGHT, (range as f32 * brightness(ambient)).round() as i32) }
+}
+
+/// Can a tile be made out from far off, thanks to the light falling on it?
+pub fn is_lit(map: &Map, idx: usize) -> bool { brightness(map.light[idx]) >= LIT }
+
+/// How much harder it is to notice someone standing on the tile: 0 in good light, up to 5 in the dark.
+pub fn stealth_modifier(map: &Map, idx: usize) -> i32 {
+    ((1.0 - brightness(map.light[idx]).min(1.0)) * 6.0).clamp(0.0, 5.0) as i32
+}
@@ -2,14 +2,32 @@ use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
 use super::*;
-use crate::map_builders::level_builder;
+use crate::map_builders::{file_builder, level_builder, named_builder, BuilderChain};
+
+/// The main dungeon, which every game starts in and every side branch leads off.
+pub const MAIN_BRANCH: usize = 0;
 
 #[derive(Default, Serialize, Deserialize, Clone)]
 pub struct MasterDungeonMap {
-    maps: HashMap<i32, Map>,
+    maps: HashMap<String, Map>,
     pub identified_items: HashSet<String>,
     pub scroll_mappings: HashMap<String, String>,
     pub potion_mappings: HashMap<String, String>,
+    /// Turns the player has taken since the game began.
+    #[serde(default)]
+    pub turn: i32,
+    /// The turn on which each level was last left behind.
+    #[serde(default)]
+    frozen_at: HashMap<String, i32>,
+    /// The turn on which the player arrived on the current level.
+    #[serde(default)]
+    pub arrived_at: i32,
+    /// The depth of the main dungeon each side branch is entered from, picked when the game begins.
+    #[serde(default)]
+    branch_entrances: HashMap<usize, i32>,
+    /// The areas of the overworld, laid out when the game begins.
+    #[serde(default)]
+    region: Vec<Area>,
 }
 
 impl MasterDungeonMap {
@@ -19,8 +37,19 @@ impl MasterDungeonMap {
             identified_items: HashSet::new(),
             scroll_mappings: HashMap::new(),
             potion_mappings: HashMap::new(),
+            turn: 0,
+            frozen_at: HashMap::new(),
+            arrived_at: 0,
+            branch_entrances: HashMap::new(),
+            region: make_region(),
         };
 
+        // Where the side branches begin
+        for (branch, info) in crate::raws::get_branches(&crate::raws::RAWS.lock().unwrap()) {
+            let depth = crate::rng::range(info.min_entrance_depth, info.max_entrance_depth + 1);
+            dm.branch_entrances.insert(branch, depth);
+        }
+
         // Scroll Naming
         for scroll_tag in crate::raws::get_scroll_tags().iter() {
             let masked_name = make_scroll_name();
@@ -37,16 +66,159 @@ impl MasterDungeonMap {
         dm
     }
 
-    pub fn store_map(&mut self, map: &Map) { self.maps.insert(map.depth, map.clone()); }
+    pub fn store_map(&mut self, map: &Map) {
+        self.maps
+            .insert(level_key(map.branch, map.depth, map.area), map.clone());
+    }
 
-    pub fn get_map(&self, depth: i32) -> Option<Map> {
-        if self.maps.contains_key(&depth) {
-            let result = self.maps[&depth].clone();
-            Some(result)
-        } else {
-            None
+    /// Records a change to a level's terrain, so that it is still there when the player comes back.
+    pub fn set_tile(&mut self, level: &Map, idx: usize, tt: TileType) {
+        if let Some(map) = self.maps.get_mut(&level_key(level.branch, level.depth, level.area)) {
+            map.tiles[idx] = tt;
+        }
+    }
+
+    /// A level of the dungeon, or the town on the surface above it.
+    pub fn get_map(&self, branch: usize, depth: i32) -> Option<Map> { self.stored(branch, depth, WorldCoord::TOWN) }
+
+    /// An area of the overworld, if anybody has been there yet.
+    pub fn get_area_map(&self, coord: WorldCoord) -> Option<Map> { self.stored(MAIN_BRANCH, 1, coord) }
+
+    fn stored(&self, branch: usize, depth: i32, area: WorldCoord) -> Option<Map> {
+        self.maps.get(&level_key(branch, depth, area)).cloned()
+    }
+
+    /// Every area of the overworld. Games from before there was one have none.
+    pub fn region(&self) -> &[Area] { &self.region }
+
+    pub fn area(&self, coord: WorldCoord) -> Option<&Area> { self.region.iter().find(|area| area.coord == coord) }
+
+    /// Whether the player has been to an area of the overworld yet.
+    pub fn visited(&self, coord: WorldCoord) -> bool { self.maps.contains_key(&level_key(MAIN_BRANCH, 1, coord)) }
+
+    /// Where the stairs down from a level lead, or None at the bottom of a side branch.
+    pub fn stairs_down(&self, branch: usize, depth: i32) -> Option<StairLink> {
+        if let Some(info) = crate::raws::get_branch(&crate::raws::RAWS.lock().unwrap(), branch) {
+            if depth >= self.branch_entrances.get(&branch)? + info.levels {
+                return None;
+            }
+        }
+
+        Some(StairLink {
+            branch,
+            depth: depth + 1,
+            area: WorldCoord::default(),
+            idx: None,
+        })
+    }
+
+    /// Where the stairs up from a level lead. The top of a side branch climbs back to its entrance.
+    pub fn stairs_up(&self, branch: usize, depth: i32) -> StairLink {
+        match self.branch_entrances.get(&branch) {
+            Some(entrance) if depth <= entrance + 1 => StairLink {
+                branch: MAIN_BRANCH,
+                depth: *entrance,
+                area: WorldCoord::default(),
+                idx: None,
+            },
+            _ => StairLink {
+                branch,
+                depth: depth - 1,
+                area: WorldCoord::default(),
+                idx: None,
+            },
+        }
+    }
+
+    /// Points a staircase on a stored level at the exact staircase it comes out on.
+    fn link_stairs(&mut self, from: &StairLink, to: &StairLink) {
+        if let (Some(idx), Some(map)) = (
+            from.idx,
+            self.maps.get_mut(&level_key(from.branch, from.depth, from.area)),
+        ) {
+            map.stairs.insert(idx, to.clone());
         }
     }
+
+    /// Gives every staircase on a newly built level somewhere to go: up and down the branch it is in, or into any
+    /// side branch whose way in is on this level, for which an extra staircase down is added.
+    fn link_new_level(&self, map: &mut Map, start: usize) {
+        let down = self.stairs_down(map.branch, map.depth);
+        let up = self.stairs_up(map.branch, map.depth);
+
+        for idx in 0..map.tiles.len() {
+            if map.tiles[idx] == TileType::UpStairs {
+                map.stairs.insert(idx, up.clone());
+            } else if map.tiles[idx] == TileType::DownStairs {
+                match &down {
+                    Some(link) => {
+                        map.stairs.insert(idx, link.clone());
+                    },
+                    // The bottom of a branch goes no deeper
+                    None => map.tiles[idx] = TileType::Floor,
+                }
+            }
+        }
+
+        if map.branch != MAIN_BRANCH {
+            return;
+        }
+        for (branch, entrance) in self.branch_entrances.iter() {
+            if *entrance == map.depth {
+                if let Some(idx) = map.spot_for_stairs(start) {
+                    map.tiles[idx] = TileType::DownStairs;
+                    map.stairs.insert(
+                        idx,
+                        StairLink {
+                            branch: *branch,
+                            depth: map.depth + 1,
+                            area: WorldCoord::default(),
+                            idx: None,
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    /// Finds the staircase on a stored level that leads back to `from`, for stairs that have never been taken
+    /// before. If the level was built by way of some other staircase there may not be one yet, so one is added.
+    fn arrival_stairs(&mut self, to: &StairLink, from: &StairLink, offset: i32) -> usize {
+        let stair_type = if offset < 0 { TileType::DownStairs } else { TileType::UpStairs };
+        let map = self.maps.get_mut(&level_key(to.branch, to.depth, to.area)).unwrap();
+        let leads_back = |link: &StairLink| {
+            link.branch == from.branch && link.depth == from.depth && link.area == from.area && link.idx.is_none()
+        };
+
+        let existing = map
+            .stairs
+            .iter()
+            .filter(|(idx, link)| map.tiles[**idx] == stair_type && leads_back(link))
+            .map(|(idx, _)| *idx)
+            .min();
+
+        let idx = existing
+            .or_else(|| {
+                let centre = map.xy_idx(map.width / 2, map.height / 2);
+                map.spot_for_stairs(centre)
+            })
+            .unwrap_or_else(|| map.xy_idx(map.width / 2, map.height / 2));
+        map.tiles[idx] = stair_type;
+        map.stairs.insert(idx, from.clone());
+        idx
+    }
+}
+
+/// Levels of the main dungeon are stored under their depth alone, as they were before there were branches, and
+/// the town along with them. The rest of the overworld is stored under where each area lies.
+fn level_key(branch: usize, depth: i32, area: WorldCoord) -> String {
+    if area != WorldCoord::TOWN {
+        format!("@{},{}", area.x, area.y)
+    } else if branch == MAIN_BRANCH {
+        depth.to_string()
+    } else {
+        format!("{}:{}", branch, depth)
+    }
 }
 
 fn make_scroll_name() -> String {
@@ -118,23 +290,48 @@ fn make_potion_name(used_names: &mut HashSet<String>) -> String {
     }
 }
 
-fn transition_to_new_map(ecs: &mut World, new_depth: i32) -> Vec<Map> {
-    let mut builder = level_builder(new_depth, 80, 50);
+/// Builds a level nobody has been to yet. `from` is the staircase the player took to get there, if they came by
+/// stairs; the staircase they arrive on leads straight back to it.
+fn transition_to_new_map(
+    ecs: &mut World,
+    branch: usize,
+    new_depth: i32,
+    from: Option<StairLink>,
+    offset: i32,
+) -> Vec<Map> {
+    rltk::console::log(format!("Branch: {}, Depth: {}", branch, new_depth));
+    let mut builder = level_builder(branch, new_depth, None);
     builder.build_map();
+
+    let map = &mut builder.build_data.map;
+    let start = builder.build_data.starting_position.as_ref().unwrap();
+    let start_idx = map.xy_idx(start.x, start.y);
     if new_depth > 1 {
-        if let Some(pos) = &builder.build_data.starting_position {
-            let up_idx = builder.build_data.map.xy_idx(pos.x, pos.y);
-            builder.build_data.map.tiles[up_idx] = TileType::UpStairs;
-        }
+        map.tiles[start_idx] = if offset < 0 { TileType::DownStairs } else { TileType::UpStairs };
     }
-    let mapgen_history = builder.build_data.history.clone();
-    let player_start;
-    {
-        let mut worldmap_resource = ecs.write_resource::<Map>();
-        *worldmap_resource = builder.build_data.map.clone();
-        player_start = builder.build_data.starting_position.as_mut().unwrap().clone();
+
+    ecs.fetch::<MasterDungeonMap>().link_new_level(map, start_idx);
+    if let Some(from) = from {
+        map.stairs.insert(start_idx, from.clone());
+        ecs.write_resource::<MasterDungeonMap>().link_stairs(
+            &from,
+            &StairLink {
+                branch,
+                depth: new_depth,
+                area: WorldCoord::default(),
+                idx: Some(start_idx),
+            },
+        );
     }
 
+    enter_built_map(ecs, builder)
+}
+
+fn enter_built_map(ecs: &mut World, mut builder: BuilderChain) -> Vec<Map> {
+    let mapgen_history = builder.build_data.history.clone();
+    make_current(ecs, builder.build_data.map.clone());
+    let player_start = builder.build_data.starting_position.as_mut().unwrap().clone();
+
     // Spawn bad guys
     builder.spawn_entities(ecs);
 
@@ -164,30 +361,24 @@ fn transition_to_new_map(ecs: &mut World, new_depth: i32) -> Vec<Map> {
     mapgen_history
 }
 
-fn transition_to_existing_map(ecs: &mut World, new_depth: i32, offset: i32) {
-    let dungeon_master = ecs.read_resource::<MasterDungeonMap>();
-    let map = dungeon_master.get_map(new_depth).unwrap();
-    let mut worldmap_resource = ecs.write_resource::<Map>();
+/// Returns to a level that has been visited before, placing the player on the `arrival` tile if there is one.
+fn transition_to_existing_map(ecs: &mut World, map: Map, arrival: Option<usize>) {
+    let w = map.width;
+    make_current(ecs, map);
     let player_entity = ecs.fetch::<Entity>();
 
-    // Find the down stairs and place the player
-    let w = map.width;
-    let stair_type = if offset < 0 { TileType::DownStairs } else { TileType::UpStairs };
-    for (idx, tt) in map.tiles.iter().enumerate() {
-        if *tt == stair_type {
-            let mut player_position = ecs.write_resource::<Point>();
-            *player_position = Point::new(idx as i32 % w, idx as i32 / w);
-            let mut position_components = ecs.write_storage::<Position>();
-            let player_pos_comp = position_components.get_mut(*player_entity);
-            if let Some(player_pos_comp) = player_pos_comp {
-                player_pos_comp.x = idx as i32 % w;
-                player_pos_comp.y = idx as i32 / w;
-            }
+    // Place the player
+    if let Some(idx) = arrival {
+        let mut player_position = ecs.write_resource::<Point>();
+        *player_position = Point::new(idx as i32 % w, idx as i32 / w);
+        let mut position_components = ecs.write_storage::<Position>();
+        let player_pos_comp = position_components.get_mut(*player_entity);
+        if let Some(player_pos_comp) = player_pos_comp {
+            player_pos_comp.x = idx as i32 % w;
+            player_pos_comp.y = idx as i32 / w;
         }
     }
 
-    *worldmap_resource = map;
-
     // Mark the player's visibility as dirty
     let mut viewshed_components = ecs.write_storage::<Viewshed>();
     let vs = viewshed_components.get_mut(*player_entity);
@@ -196,13 +387,26 @@ fn transition_to_existing_map(ecs: &mut World, new_depth: i32, offset: i32) {
     }
 }
 
+/// Makes `map` the level being played on. Whatever it last showed as seen or lit is out of date, and the spatial
+/// index has to be fitted to it.
+fn make_current(ecs: &mut World, mut map: Map) {
+    map.visible_tiles.iter_mut().for_each(|t| *t = false);
+    map.lamplit.clear();
+    map.ambient = None;
+    crate::spatial::set_size(map.tiles.len());
+    *ecs.write_resource::<Map>() = map;
+}
+
 pub fn freeze_level_entities(ecs: &mut World) {
     // Obtain ECS access
     let entities = ecs.entities();
     let mut positions = ecs.write_storage::<Position>();
     let mut other_level_positions = ecs.write_storage::<OtherLevelPosition>();
     let player_entity = ecs.fetch::<Entity>();
-    let map_depth = ecs.fetch::<Map>().depth;
+    let (map_branch, map_depth, map_area) = {
+        let map = ecs.fetch::<Map>();
+        (map.branch, map.depth, map.area)
+    };
 
     // Find positions and make OtherLevelPosition
     let mut pos_to_delete: Vec<Entity> = Vec::new();
@@ -215,6 +419,8 @@ pub fn freeze_level_entities(ecs: &mut World) {
                         x: pos.x,
                         y: pos.y,
                         depth: map_depth,
+                        branch: map_branch,
+                        area: map_area,
                     },
                 )
                 .expect("Insert fail");
@@ -226,44 +432,207 @@ pub fn freeze_level_entities(ecs: &mut World) {
     for p in pos_to_delete.iter() {
         positions.remove(*p);
     }
+
+    // Remember when we left, so the level can catch up when we return
+    let mut dm = ecs.write_resource::<MasterDungeonMap>();
+    let turn = dm.turn;
+    dm.frozen_at.insert(level_key(map_branch, map_depth, map_area), turn);
 }
 
 pub fn thaw_level_entities(ecs: &mut World) {
-    // Obtain ECS access
-    let entities = ecs.entities();
-    let mut positions = ecs.write_storage::<Position>();
-    let mut other_level_positions = ecs.write_storage::<OtherLevelPosition>();
-    let player_entity = ecs.fetch::<Entity>();
-    let map_depth = ecs.fetch::<Map>().depth;
+    let (map_branch, map_depth, map_area) = {
+        let map = ecs.fetch::<Map>();
+        (map.branch, map.depth, map.area)
+    };
 
-    // Find OtherLevelPosition
-    let mut pos_to_delete: Vec<Entity> = Vec::new();
-    for (entity, pos) in (&entities, &other_level_positions).join() {
-        if entity != *player_entity && pos.depth == map_depth {
-            positions
-                .insert(entity, Position { x: pos.x, y: pos.y })
-                .expect("Insert fail");
-            pos_to_delete.push(entity);
+    {
+        // Obtain ECS access
+        let entities = ecs.entities();
+        let mut positions = ecs.write_storage::<Position>();
+        let mut other_level_positions = ecs.write_storage::<OtherLevelPosition>();
+        let player_entity = ecs.fetch::<Entity>();
+        let resistances = ecs.read_storage::<HazardResistance>();
+        let map = ecs.fetch::<Map>();
+
+        // Find OtherLevelPosition
+        let mut pos_to_delete: Vec<Entity> = Vec::new();
+        for (entity, pos) in (&entities, &other_level_positions).join() {
+            if entity != *player_entity && pos.depth == map_depth && pos.branch == map_branch && pos.area == map_area {
+                // Anything that fell in from above lands wherever there is room for it
+                let tt = map.tiles[map.xy_idx(pos.x, pos.y)];
+                let (x, y) = if tile_walkable(tt) && !endangers(tt, resistances.get(entity)) {
+                    (pos.x, pos.y)
+                } else {
+                    map.nearest_safe_tile(pos.x, pos.y, resistances.get(entity))
+                        .unwrap_or((pos.x, pos.y))
+                };
+
+                positions.insert(entity, Position { x, y }).expect("Insert fail");
+                pos_to_delete.push(entity);
+            }
+        }
+
+        // Remove positions
+        for p in pos_to_delete.iter() {
+            other_level_positions.remove(*p);
         }
     }
 
-    // Remove positions
-    for p in pos_to_delete.iter() {
-        other_level_positions.remove(*p);
+    // Life went on while we were away
+    let elapsed = {
+        let mut dm = ecs.write_resource::<MasterDungeonMap>();
+        let now = dm.turn;
+        dm.frozen_at
+            .remove(&level_key(map_branch, map_depth, map_area))
+            .map(|then| now - then)
+    };
+
+    if let Some(elapsed) = elapsed {
+        super::catch_up::simulate(ecs, elapsed);
+        crate::spawner::returning_wanderers(ecs, elapsed);
     }
 }
 
-pub fn level_transition(ecs: &mut World, new_depth: i32, offset: i32) -> Option<Vec<Map>> {
+/// Moves the player to a level other than by stairs, arriving on its first staircase the way they were heading.
+pub fn level_transition(ecs: &mut World, branch: usize, new_depth: i32, offset: i32) -> Option<Vec<Map>> {
+    // Start counting the time spent on this level
+    {
+        let mut dungeon_master = ecs.write_resource::<MasterDungeonMap>();
+        dungeon_master.arrived_at = dungeon_master.turn;
+    }
+
     // Obtain the master dungeon map
     let dungeon_master = ecs.read_resource::<MasterDungeonMap>();
 
     // Do we already have a map?
-    if dungeon_master.get_map(new_depth).is_some() {
+    if let Some(map) = dungeon_master.get_map(branch, new_depth) {
         std::mem::drop(dungeon_master);
-        transition_to_existing_map(ecs, new_depth, offset);
+        let stair_type = if offset < 0 { TileType::DownStairs } else { TileType::UpStairs };
+        let arrival = map.tiles.iter().position(|tt| *tt == stair_type);
+        transition_to_existing_map(ecs, map, arrival);
         None
     } else {
         std::mem::drop(dungeon_master);
-        Some(transition_to_new_map(ecs, new_depth))
+        Some(transition_to_new_map(ecs, branch, new_depth, None, offset))
+    }
+}
+
+/// Takes the player down (`offset` 1) or up (`offset` -1) the staircase they stand on, to the staircase at the
+/// other end - building the level there first, if nobody has been to it yet.
+pub fn stairs_transition(ecs: &mut World, offset: i32) -> Option<Vec<Map>> {
+    let (from, to) = {
+        let map = ecs.fetch::<Map>();
+        let player_pos = ecs.fetch::<Point>();
+        let mut dungeon_master = ecs.write_resource::<MasterDungeonMap>();
+        dungeon_master.arrived_at = dungeon_master.turn;
+
+        let idx = map.xy_idx(player_pos.x, player_pos.y);
+        let from = StairLink {
+            branch: map.branch,
+            depth: map.depth,
+            area: map.area,
+            idx: Some(idx),
+        };
+
+        // Levels from before stairs were linked just lead on up or down the branch
+        let to = map.stairs.get(&idx).cloned().unwrap_or_else(|| {
+            let fallback = StairLink {
+                branch: map.branch,
+                depth: map.depth + offset,
+                area: WorldCoord::default(),
+                idx: None,
+            };
+            if offset < 0 {
+                dungeon_master.stairs_up(map.branch, map.depth)
+            } else {
+                dungeon_master.stairs_down(map.branch, map.depth).unwrap_or(fallback)
+            }
+        });
+        (from, to)
+    };
+
+    let stored = ecs.fetch::<MasterDungeonMap>().stored(to.branch, to.depth, to.area);
+    if stored.is_none() {
+        return Some(transition_to_new_map(ecs, to.branch, to.depth, Some(from), offset));
     }
+
+    // Stairs taken for the first time find, or make, their other end
+    let arrival = match to.idx {
+        Some(idx) => idx,
+        None => {
+            let mut dungeon_master = ecs.write_resource::<MasterDungeonMap>();
+            let idx = dungeon_master.arrival_stairs(&to, &from, offset);
+            dungeon_master.link_stairs(
+                &from,
+                &StairLink {
+                    idx: Some(idx),
+                    ..to.clone()
+                },
+            );
+            idx
+        },
+    };
+
+    // Finding the other end may have added a staircase to the level
+    let map = ecs
+        .fetch::<MasterDungeonMap>()
+        .stored(to.branch, to.depth, to.area)
+        .unwrap();
+    transition_to_existing_map(ecs, map, Some(arrival));
+    None
+}
+
+/// Travels overland to an area of the overworld, building it first if nobody has been there yet. The player comes
+/// in on the side they were `heading` from: going east, they arrive on the western edge.
+pub fn area_transition(ecs: &mut World, coord: WorldCoord, heading: (i32, i32)) -> Option<Vec<Map>> {
+    let (area, stored) = {
+        let mut dungeon_master = ecs.write_resource::<MasterDungeonMap>();
+        dungeon_master.arrived_at = dungeon_master.turn;
+        (dungeon_master.area(coord).cloned()?, dungeon_master.get_area_map(coord))
+    };
+
+    let history = match stored {
+        Some(map) => {
+            transition_to_existing_map(ecs, map, None);
+            None
+        },
+        None => {
+            rltk::console::log(format!("Area: {},{} ({})", coord.x, coord.y, area.chain));
+            let mut builder = named_builder(&area.chain, 1, None)
+                .unwrap_or_else(|| panic!("No level chain is called {}", area.chain));
+            builder.build_map();
+
+            let map = &mut builder.build_data.map;
+            let start = builder.build_data.starting_position.as_ref().unwrap();
+            let start_idx = map.xy_idx(start.x, start.y);
+            map.area = coord;
+            map.settle_entrance(start_idx, area.entrance);
+            ecs.fetch::<MasterDungeonMap>().link_new_level(map, start_idx);
+
+            Some(enter_built_map(ecs, builder))
+        },
+    };
+
+    // Come in from the edge of the area, wherever the builder meant the level to start
+    let (x, y) = ecs.fetch::<Map>().arrival_point(heading);
+    let player_entity = *ecs.fetch::<Entity>();
+    if let Some(pos) = ecs.write_storage::<Position>().get_mut(player_entity) {
+        pos.x = x;
+        pos.y = y;
+    }
+    *ecs.write_resource::<Point>() = Point::new(x, y);
+
+    history
+}
+
+/// Starts the player on a hand-made level instead of a generated one.
+pub fn custom_level_transition(ecs: &mut World, level: LevelFile) -> Vec<Map> {
+    {
+        let mut dungeon_master = ecs.write_resource::<MasterDungeonMap>();
+        dungeon_master.arrived_at = dungeon_master.turn;
+    }
+
+    let mut builder = file_builder(level);
+    builder.build_map();
+    enter_built_map(ecs, builder)
 }
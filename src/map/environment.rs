@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+/// Smoke a burning tile gives off each turn.
+const SMOKE_FROM_FIRE: i32 = 3;
+
+/// What hangs over a tile, or burns on it, each measured in turns or density.
+#[derive(Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct Environment {
+    pub fire: i32,
+    pub smoke: i32,
+    pub gas: i32,
+}
+
+impl Environment {
+    pub fn is_empty(&self) -> bool { self.fire < 1 && self.smoke < 1 && self.gas < 1 }
+
+    /// How the tile looks with this drifting over it.
+    pub fn look(&self, glyph: rltk::FontCharType, fg: RGB, bg: RGB) -> (rltk::FontCharType, RGB, RGB) {
+        if self.fire > 0 {
+            let flicker = if crate::rng::roll_dice(1, 2) == 1 { rltk::ORANGE } else { rltk::YELLOW };
+            (rltk::to_cp437('▲'), RGB::named(flicker), RGB::from_f32(0.5, 0.1, 0.))
+        } else if self.gas > 0 {
+            (
+                rltk::to_cp437('░'),
+                RGB::from_f32(0.4, 0.8, 0.2),
+                bg.lerp(RGB::from_f32(0.1, 0.3, 0.), 0.5),
+            )
+        } else if self.smoke > 0 {
+            (
+                rltk::to_cp437('░'),
+                RGB::from_f32(0.6, 0.6, 0.6),
+                bg.lerp(RGB::from_f32(0.2, 0.2, 0.2), 0.5),
+            )
+        } else {
+            (glyph, fg, bg)
+        }
+    }
+}
+
+/// Will the tile keep a fire going? That takes flammable ground, or something flammable standing on it.
+pub fn has_fuel(map: &Map, idx: usize, flammable: &ReadStorage<Flammable>) -> bool {
+    tile_flammable(map.tiles[idx])
+        || crate::spatial::get_tile_content_clone(idx)
+            .iter()
+            .any(|entity| flammable.get(*entity).is_some())
+}
+
+impl Map {
+    /// Sets the tile burning. Without fuel the flames die down straight away.
+    pub fn ignite(&mut self, idx: usize, turns: i32, fuel: bool) {
+        if !tile_walkable(self.tiles[idx]) {
+            return;
+        }
+
+        let turns = if fuel { turns } else { 1 };
+        let env = self.environment.entry(idx).or_default();
+        env.fire = i32::max(env.fire, turns);
+    }
+
+    /// Releases poison gas over the tile, to drift from there.
+    pub fn add_gas(&mut self, idx: usize, density: i32) {
+        if !tile_walkable(self.tiles[idx]) {
+            return;
+        }
+
+        self.environment.entry(idx).or_default().gas += density;
+    }
+
+    /// Neighbouring tiles that fire, smoke and gas can spread into.
+    pub fn open_neighbours(&self, idx: usize, diagonals: bool) -> Vec<usize> {
+        let (x, y) = (idx as i32 % self.width, idx as i32 / self.width);
+        let mut neighbours = Vec::new();
+        for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)] {
+            if !diagonals && dx != 0 && dy != 0 {
+                continue;
+            }
+            let (nx, ny) = (x + dx, y + dy);
+            if nx > 0 && ny > 0 && nx < self.width - 1 && ny < self.height - 1 {
+                let n = self.xy_idx(nx, ny);
+                if tile_walkable(self.tiles[n]) {
+                    neighbours.push(n);
+                }
+            }
+        }
+        neighbours
+    }
+
+    /// Moves the smoke and gas on by a turn: both spread into open tiles around them and thin out, and fires
+    /// give off fresh smoke. Fire itself is left to the caller, since it needs to know what will burn.
+    pub fn drift_environment(&mut self) {
+        let before = self.environment.clone();
+        let mut after: HashMap<usize, Environment> = HashMap::new();
+
+        for (idx, env) in before.iter() {
+            let here = after.entry(*idx).or_default();
+            here.fire = env.fire;
+            let smoke = if env.fire > 0 { i32::max(env.smoke, SMOKE_FROM_FIRE) } else { env.smoke };
+            here.smoke = i32::max(here.smoke, smoke);
+
+            // Smoke billows outward, thinning as it goes
+            if smoke > 1 {
+                for n in self.open_neighbours(*idx, true) {
+                    let there = after.entry(n).or_default();
+                    there.smoke = i32::max(there.smoke, smoke - 1);
+                }
+            }
+
+            // Gas shares itself out evenly with the open tiles around it
+            let neighbours = self.open_neighbours(*idx, false);
+            let share = env.gas / (neighbours.len() as i32 + 1);
+            after.entry(*idx).or_default().gas += env.gas - share * neighbours.len() as i32;
+            for n in neighbours {
+                after.entry(n).or_default().gas += share;
+            }
+        }
+
+        for env in after.values_mut() {
+            env.smoke -= 1;
+            // Gas lingers, thinning out a little at a time
+            if crate::rng::roll_dice(1, 2) == 1 {
+                env.gas -= 1;
+            }
+            env.smoke = i32::max(env.smoke, 0);
+            env.gas = i32::max(env.gas, 0);
+        }
+        after.retain(|_, env| !env.is_empty());
+
+        self.environment = after;
+    }
+}
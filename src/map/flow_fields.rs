@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use super::*;
+
+/// How far a field reaches, as a multiple of the map's width plus height - enough to cover the winding paths of
+/// any level, whatever its size.
+const FLOW_DEPTH_SCALE: f32 = 3.0;
+const MAX_CACHED_FIELDS: usize = 64;
+
+/// Terrain-only view of the map. Flow fields ignore mobile blockers so they stay valid while monsters shuffle
+/// around; whoever follows a field checks the step itself against the spatial index.
+struct TerrainView<'a> {
+    map: &'a Map,
+}
+
+impl<'a> TerrainView<'a> {
+    fn is_exit_valid(&self, x: i32, y: i32) -> bool {
+        if x < 1 || x > self.map.width - 1 || y < 1 || y > self.map.height - 1 {
+            return false;
+        }
+
+        tile_walkable(self.map.tiles[self.map.xy_idx(x, y)])
+    }
+}
+
+impl<'a> BaseMap for TerrainView<'a> {
+    fn get_available_exits(&self, idx: usize) -> rltk::SmallVec<[(usize, f32); 10]> {
+        const DIAGONAL_COST: f32 = 1.5;
+        let mut exits = rltk::SmallVec::new();
+        let w = self.map.width as usize;
+        let x = idx as i32 % self.map.width;
+        let y = idx as i32 / self.map.width;
+
+        for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)] {
+            if self.is_exit_valid(x + dx, y + dy) {
+                let dest = ((idx as i32) + dx + (dy * w as i32)) as usize;
+                let cost = self.map.step_cost(idx, dest);
+                let step_cost = if dx != 0 && dy != 0 { cost * DIAGONAL_COST } else { cost };
+                exits.push((dest, step_cost));
+            }
+        }
+
+        exits
+    }
+}
+
+/// Shared cache of Dijkstra flow fields, so that AI systems don't each run their own search for every monster.
+///
+/// Fields are keyed by the tiles or the creature they flow from and are only rebuilt when those sources move or the
+/// map itself changes (a new level, or terrain being altered).
+#[derive(Default)]
+pub struct FlowFields {
+    terrain_revision: Option<u64>,
+    toward: HashMap<Vec<usize>, rltk::DijkstraMap>,
+    away: HashMap<Vec<usize>, rltk::DijkstraMap>,
+    creatures: HashMap<Entity, (usize, rltk::DijkstraMap)>,
+}
+
+impl FlowFields {
+    pub fn new() -> FlowFields { FlowFields::default() }
+
+    /// Throws away every cached field.
+    pub fn invalidate(&mut self) {
+        self.terrain_revision = None;
+        self.toward.clear();
+        self.away.clear();
+        self.creatures.clear();
+    }
+
+    /// Makes sure the cache belongs to the current map; call once at the start of each system run. The spatial index
+    /// hears about every new map and every change to the terrain, so there is no need to look over the tiles.
+    pub fn sync(&mut self) {
+        let revision = crate::spatial::terrain_revision();
+        if self.terrain_revision != Some(revision) {
+            self.invalidate();
+            self.terrain_revision = Some(revision);
+        }
+    }
+
+    /// A field flowing toward the given tiles (for example, the player's position).
+    pub fn toward(&mut self, map: &Map, targets: &[usize]) -> &rltk::DijkstraMap {
+        let key = sorted_key(targets);
+        if self.toward.len() > MAX_CACHED_FIELDS {
+            self.toward.clear();
+        }
+
+        self.toward.entry(key).or_insert_with_key(|key| build_field(map, key))
+    }
+
+    /// A field flowing toward a set of threats; follow its highest exit to run away from them.
+    pub fn away_from(&mut self, map: &Map, threats: &[usize]) -> &rltk::DijkstraMap {
+        let key = sorted_key(threats);
+        if self.away.len() > MAX_CACHED_FIELDS {
+            self.away.clear();
+        }
+
+        self.away.entry(key).or_insert_with_key(|key| build_field(map, key))
+    }
+
+    /// A field flowing toward one creature, standing at `idx`, shared by everyone after it and rebuilt only when it
+    /// has moved.
+    pub fn toward_creature(&mut self, map: &Map, creature: Entity, idx: usize) -> &rltk::DijkstraMap {
+        let stale = match self.creatures.get(&creature) {
+            Some((at, _)) => *at != idx,
+            None => true,
+        };
+
+        if stale {
+            if self.creatures.len() > MAX_CACHED_FIELDS {
+                self.creatures.clear();
+            }
+            self.creatures.insert(creature, (idx, build_field(map, &[idx])));
+        }
+
+        &self.creatures[&creature].1
+    }
+
+    /// Distance from `idx` to the nearest source of `field`, if it can be reached at all.
+    pub fn distance(field: &rltk::DijkstraMap, idx: usize) -> Option<f32> {
+        let d = field.map[idx];
+        if d < f32::MAX { Some(d) } else { None }
+    }
+
+    /// The best free step from `idx` down the field, toward its sources, among those the mover counts as `safe`.
+    pub fn step_toward(
+        field: &rltk::DijkstraMap,
+        idx: usize,
+        map: &dyn BaseMap,
+        safe: impl Fn(usize) -> bool,
+    ) -> Option<usize> {
+        map.get_available_exits(idx)
+            .iter()
+            .map(|(exit, _)| *exit)
+            .filter(|exit| safe(*exit) && field.map[*exit] < field.map[idx])
+            .min_by(|a, b| field.map[*a].total_cmp(&field.map[*b]))
+    }
+
+    /// The best free step from `idx` up the field, away from its sources, among those the mover counts as `safe`.
+    pub fn step_away(
+        field: &rltk::DijkstraMap,
+        idx: usize,
+        map: &dyn BaseMap,
+        safe: impl Fn(usize) -> bool,
+    ) -> Option<usize> {
+        map.get_available_exits(idx)
+            .iter()
+            .map(|(exit, _)| *exit)
+            .filter(|exit| safe(*exit))
+            .min_by(|a, b| field.map[*b].total_cmp(&field.map[*a]))
+    }
+}
+
+fn sorted_key(indices: &[usize]) -> Vec<usize> {
+    let mut key = indices.to_vec();
+    key.sort_unstable();
+    key.dedup();
+    key
+}
+
+fn build_field(map: &Map, sources: &[usize]) -> rltk::DijkstraMap {
+    let view = TerrainView { map };
+    let max_depth = (map.width + map.height) as f32 * FLOW_DEPTH_SCALE;
+    rltk::DijkstraMap::new(map.width as usize, map.height as usize, sources, &view, max_depth)
+}
@@ -0,0 +1,78 @@
+use super::*;
+
+/// Furthest anything can slide across slippery tiles in one move.
+const MAX_SLIDE: i32 = 20;
+
+/// Is the tile a hazard that these resistances cover?
+pub fn resists(tt: TileType, resistance: Option<&HazardResistance>) -> bool {
+    match (tile_hazard(tt), resistance) {
+        (Some(hazard), Some(resistance)) => resistance.hazards.contains(&hazard),
+        _ => false,
+    }
+}
+
+/// Would stepping onto the tile hurt something with these resistances?
+pub fn endangers(tt: TileType, resistance: Option<&HazardResistance>) -> bool {
+    tile_hazard(tt).is_some() && !resists(tt, resistance)
+}
+
+impl Map {
+    /// What pathing charges for a step from `from` onto `to`. Stepping into a hazard costs as much again as getting
+    /// back out of it, so that routes only cross hazards when there is no reasonable way round.
+    pub fn step_cost(&self, from: usize, to: usize) -> f32 {
+        let hazard = if tile_hazard(self.tiles[to]).is_some() { tile_cost(self.tiles[to]) } else { 0.0 };
+        tile_cost(self.tiles[from]) + hazard
+    }
+
+    /// Where a step from `from` onto `to` really ends: a slippery tile carries the mover on in the same direction
+    /// until it runs out of slippery ground or bumps into something.
+    pub fn slide(&self, from: usize, to: usize) -> usize {
+        let (dx, dy) = (
+            (to as i32 % self.width) - (from as i32 % self.width),
+            (to as i32 / self.width) - (from as i32 / self.width),
+        );
+        let (mut x, mut y) = (to as i32 % self.width, to as i32 / self.width);
+        let mut idx = to;
+
+        for _ in 0..MAX_SLIDE {
+            if !tile_slippery(self.tiles[idx]) || (dx == 0 && dy == 0) {
+                break;
+            }
+
+            let (nx, ny) = (x + dx, y + dy);
+            if nx < 1 || ny < 1 || nx > self.width - 2 || ny > self.height - 2 {
+                break;
+            }
+
+            let next = self.xy_idx(nx, ny);
+            if crate::spatial::is_blocked(next) {
+                break;
+            }
+
+            (x, y, idx) = (nx, ny, next);
+        }
+
+        idx
+    }
+
+    /// The closest tile to (x, y) that can be stood on safely, for anything arriving where it can't stay.
+    pub fn nearest_safe_tile(&self, x: i32, y: i32, resistance: Option<&HazardResistance>) -> Option<(i32, i32)> {
+        let safe = |x: i32, y: i32| {
+            let tt = self.tiles[self.xy_idx(x, y)];
+            tile_walkable(tt) && !endangers(tt, resistance)
+        };
+
+        for radius in 0..i32::max(self.width, self.height) {
+            for ty in y - radius..=y + radius {
+                for tx in x - radius..=x + radius {
+                    let on_ring = (tx - x).abs() == radius || (ty - y).abs() == radius;
+                    if on_ring && tx > 0 && ty > 0 && tx < self.width - 1 && ty < self.height - 1 && safe(tx, ty) {
+                        return Some((tx, ty));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+}
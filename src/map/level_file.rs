@@ -0,0 +1,307 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::*;
+
+/// Something to spawn when a level file is loaded, by its raw name.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LevelSpawn {
+    pub x: i32,
+    pub y: i32,
+    pub name: String,
+}
+
+/// A hand-editable level: the tiles drawn as rows of glyphs, plus everything that makes it playable. Reads and
+/// writes either as JSON or as a plain text file with `[legend]`, `[map]` and `[spawns]` sections.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LevelFile {
+    pub name: String,
+    #[serde(default = "default_depth")]
+    pub depth: i32,
+    #[serde(default)]
+    pub outdoors: bool,
+    /// Whether the level follows the world clock from day into night.
+    #[serde(default)]
+    pub daylight: bool,
+    #[serde(default)]
+    pub start: Option<(i32, i32)>,
+    /// Extra or replacement glyphs, on top of each tile's `ascii` glyph from `tiles.json`.
+    #[serde(default)]
+    pub legend: BTreeMap<String, TileType>,
+    pub map: Vec<String>,
+    #[serde(default)]
+    pub spawns: Vec<LevelSpawn>,
+}
+
+fn default_depth() -> i32 { 1 }
+
+impl LevelFile {
+    /// Captures a map, along with where the player starts and what should spawn on it.
+    pub fn from_map(map: &Map, start: Option<(i32, i32)>, spawns: &[(usize, String)]) -> LevelFile {
+        let mut legend = BTreeMap::new();
+        let mut rows = Vec::new();
+        for y in 0..map.height {
+            let mut row = String::new();
+            for x in 0..map.width {
+                let tt = map.tiles[map.xy_idx(x, y)];
+                let glyph = tile_ascii(tt);
+                legend.insert(glyph.to_string(), tt);
+                row.push(glyph);
+            }
+            rows.push(row);
+        }
+
+        LevelFile {
+            name: map.name.clone(),
+            depth: map.depth,
+            outdoors: map.outdoors,
+            daylight: map.daylight,
+            start,
+            legend,
+            map: rows,
+            spawns: spawns
+                .iter()
+                .map(|(idx, name)| LevelSpawn {
+                    x: *idx as i32 % map.width,
+                    y: *idx as i32 / map.width,
+                    name: name.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Captures the level the player is currently on, including everything lying or standing around on it.
+    pub fn from_world(ecs: &World) -> LevelFile {
+        let map = ecs.fetch::<Map>();
+        let player_pos = ecs.fetch::<Point>();
+        let player_entity = ecs.fetch::<Entity>();
+        let entities = ecs.entities();
+        let positions = ecs.read_storage::<Position>();
+        let names = ecs.read_storage::<Name>();
+
+        let spawns: Vec<(usize, String)> = (&entities, &positions, &names)
+            .join()
+            .filter(|(entity, _, _)| *entity != *player_entity)
+            .map(|(_, pos, name)| (map.xy_idx(pos.x, pos.y), name.name.clone()))
+            .collect();
+
+        LevelFile::from_map(&map, Some((player_pos.x, player_pos.y)), &spawns)
+    }
+
+    pub fn width(&self) -> i32 { self.map.iter().map(|row| row.chars().count()).max().unwrap_or(0) as i32 }
+
+    pub fn height(&self) -> i32 { self.map.len() as i32 }
+
+    /// Turns the glyph rows back into a map. The outer edge is always made solid, whatever the file says.
+    pub fn to_map(&self) -> Result<Map, String> {
+        let (width, height) = (self.width(), self.height());
+        if width < 3 || height < 3 {
+            return Err("A level must be at least 3x3 tiles".to_string());
+        }
+
+        let mut map = Map::new(self.depth, width, height, &self.name);
+        map.outdoors = self.outdoors;
+        map.daylight = self.daylight;
+        for (y, row) in self.map.iter().enumerate() {
+            if row.chars().count() as i32 != width {
+                return Err(format!(
+                    "Map row {} is {} tiles wide, expected {}",
+                    y + 1,
+                    row.chars().count(),
+                    width
+                ));
+            }
+
+            for (x, glyph) in row.chars().enumerate() {
+                let tt = self
+                    .tile_for(glyph)
+                    .ok_or(format!("Unknown glyph '{}' on map row {}", glyph, y + 1))?;
+                let idx = map.xy_idx(x as i32, y as i32);
+                let edge = x == 0 || y == 0 || x as i32 == width - 1 || y as i32 == height - 1;
+                map.tiles[idx] = if edge { TileType::Wall } else { tt };
+            }
+        }
+
+        if let Some((x, y)) = self.start {
+            if x < 1 || y < 1 || x > width - 2 || y > height - 2 || !tile_walkable(map.tiles[map.xy_idx(x, y)]) {
+                return Err(format!("Start ({}, {}) is not on an open tile", x, y));
+            }
+        }
+
+        for spawn in self.spawns.iter() {
+            if spawn.x < 1 || spawn.y < 1 || spawn.x > width - 2 || spawn.y > height - 2 {
+                return Err(format!("{} at ({}, {}) is off the map", spawn.name, spawn.x, spawn.y));
+            }
+        }
+
+        Ok(map)
+    }
+
+    fn tile_for(&self, glyph: char) -> Option<TileType> {
+        if let Some(tt) = self.legend.get(&glyph.to_string()) {
+            return Some(*tt);
+        }
+
+        TileType::from_ascii(glyph)
+    }
+
+    pub fn to_json(&self) -> String { serde_json::to_string_pretty(self).expect("Unable to serialize level") }
+
+    pub fn from_json(data: &str) -> Result<LevelFile, String> { serde_json::from_str(data).map_err(|e| e.to_string()) }
+
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        text += &format!("name: {}\n", self.name);
+        text += &format!("depth: {}\n", self.depth);
+        text += &format!("outdoors: {}\n", self.outdoors);
+        text += &format!("daylight: {}\n", self.daylight);
+        if let Some((x, y)) = self.start {
+            text += &format!("start: {},{}\n", x, y);
+        }
+
+        text += "\n[legend]\n";
+        for (glyph, tt) in self.legend.iter() {
+            text += &format!("{} {}\n", glyph, tt.name());
+        }
+
+        text += "\n[map]\n";
+        for row in self.map.iter() {
+            text += row;
+            text += "\n";
+        }
+
+        text += "\n[spawns]\n";
+        for spawn in self.spawns.iter() {
+            text += &format!("{},{} {}\n", spawn.x, spawn.y, spawn.name);
+        }
+
+        text
+    }
+
+    /// Reads the text format. Blank lines and lines starting with `;` are ignored outside the `[map]` section. Inside
+    /// it every line is a row, blank or not, so the rows below a blank one stay where they are; only the blank lines
+    /// before the first row and after the last are left out.
+    pub fn from_text(data: &str) -> Result<LevelFile, String> {
+        let mut level = LevelFile {
+            name: "Custom Level".to_string(),
+            depth: default_depth(),
+            outdoors: false,
+            daylight: false,
+            start: None,
+            legend: BTreeMap::new(),
+            map: Vec::new(),
+            spawns: Vec::new(),
+        };
+
+        let mut section = "";
+        for (n, line) in data.lines().enumerate() {
+            let line = line.trim_end_matches('\r');
+            let fail = |what: &str| format!("Line {}: {}", n + 1, what);
+
+            if line.trim().is_empty() {
+                if section == "[map]" && !level.map.is_empty() {
+                    level.map.push(line.to_string());
+                }
+                continue;
+            }
+
+            if section != "[map]" && line.starts_with(';') {
+                continue;
+            }
+
+            if let heading @ ("[legend]" | "[map]" | "[spawns]") = line.trim() {
+                trim_blank_rows(&mut level.map);
+                section = heading;
+                continue;
+            }
+
+            match section {
+                "[legend]" => {
+                    let mut chars = line.chars();
+                    let glyph = chars.next().unwrap();
+                    let tt = TileType::named(chars.as_str().trim()).ok_or(fail("unknown tile type"))?;
+                    level.legend.insert(glyph.to_string(), tt);
+                },
+                "[map]" => level.map.push(line.to_string()),
+                "[spawns]" => {
+                    let (at, name) = line.split_once(' ').ok_or(fail("expected 'x,y Name'"))?;
+                    let (x, y) = parse_xy(at).ok_or(fail("expected 'x,y Name'"))?;
+                    level.spawns.push(LevelSpawn {
+                        x,
+                        y,
+                        name: name.trim().to_string(),
+                    });
+                },
+                _ => {
+                    let (key, value) = line.split_once(':').ok_or(fail("expected 'key: value'"))?;
+                    let value = value.trim();
+                    match key.trim() {
+                        "name" => level.name = value.to_string(),
+                        "depth" => level.depth = value.parse().map_err(|_| fail("depth must be a number"))?,
+                        "outdoors" => {
+                            level.outdoors = value.parse().map_err(|_| fail("outdoors must be true or false"))?
+                        },
+                        "daylight" => {
+                            level.daylight = value.parse().map_err(|_| fail("daylight must be true or false"))?
+                        },
+                        "start" => level.start = Some(parse_xy(value).ok_or(fail("expected 'start: x,y'"))?),
+                        other => return Err(fail(&format!("unknown setting '{}'", other))),
+                    }
+                },
+            }
+        }
+
+        trim_blank_rows(&mut level.map);
+        if level.map.is_empty() {
+            return Err("No [map] section found".to_string());
+        }
+
+        Ok(level)
+    }
+
+    /// Checks that everything the level spawns is in the raws.
+    pub fn check_spawns(&self) -> Result<(), String> {
+        let raws = crate::raws::RAWS.lock().unwrap();
+        match self.spawns.iter().find(|spawn| !crate::raws::is_spawnable(&raws, &spawn.name)) {
+            Some(spawn) => Err(format!("Unknown spawn '{}' at ({}, {})", spawn.name, spawn.x, spawn.y)),
+            None => Ok(()),
+        }
+    }
+
+    /// Loads a level, as JSON if the file ends in `.json` and as text otherwise.
+    pub fn load(path: &str) -> Result<LevelFile, String> {
+        let data = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let level = if is_json(path) { LevelFile::from_json(&data) } else { LevelFile::from_text(&data) };
+        let level = level.map_err(|e| format!("{}: {}", path, e))?;
+
+        // Catch mistakes now, rather than when the level is built
+        level.to_map().map_err(|e| format!("{}: {}", path, e))?;
+        level.check_spawns().map_err(|e| format!("{}: {}", path, e))?;
+        Ok(level)
+    }
+
+    /// Saves a level, as JSON if the file ends in `.json` and as text otherwise.
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let data = if is_json(path) { self.to_json() } else { self.to_text() };
+        std::fs::write(path, data).map_err(|e| format!("{}: {}", path, e))
+    }
+}
+
+fn is_json(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("json"))
+}
+
+/// Drops the blank lines after the last row of the map.
+fn trim_blank_rows(rows: &mut Vec<String>) {
+    while rows.last().is_some_and(|row| row.trim().is_empty()) {
+        rows.pop();
+    }
+}
+
+fn parse_xy(s: &str) -> Option<(i32, i32)> {
+    let (x, y) = s.split_once(',')?;
+    Some((x.trim().parse().ok()?, y.trim().parse().ok()?))
+}
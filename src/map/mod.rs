@@ -1,15 +1,51 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use crate::prelude::*;
 
 pub mod camera;
 
 mod dungeon;
-pub use dungeon::{freeze_level_entities, level_transition, thaw_level_entities, MasterDungeonMap};
+pub use dungeon::{
+    area_transition, custom_level_transition, freeze_level_entities, level_transition, stairs_transition,
+    thaw_level_entities, MasterDungeonMap, MAIN_BRANCH,
+};
+
+mod catch_up;
+pub use catch_up::restock_vendors;
+
+mod clearance;
+pub use clearance::ClearanceMap;
+
+mod flow_fields;
+pub use flow_fields::FlowFields;
+
+mod level_file;
+pub use level_file::{LevelFile, LevelSpawn};
 
 mod tiletype;
-pub use tiletype::{tile_cost, tile_opaque, tile_walkable, TileType};
+pub use tiletype::{
+    register_tiles, tile_ascii, tile_burns_into, tile_cost, tile_diggable, tile_entry_effect, tile_flammable,
+    tile_hazard, tile_look, tile_opaque, tile_slippery, tile_walkable, TileLook, TileType,
+};
+
+mod hazards;
+pub use hazards::{endangers, resists};
+
+mod environment;
+pub use environment::{has_fuel, Environment};
+
+mod stairs;
+pub use stairs::StairLink;
+
+mod overworld;
+pub use overworld::{make_region, roll_encounter, Area, WorldCoord, AREA_CHAINS, REGION_REACH, REGION_WIDTH, TRAVEL_TURNS};
+
+mod report;
+pub use report::{LevelCheck, LevelReport};
+
+mod daylight;
+pub use daylight::{ambient_light, clock_text, is_lit, sight_in_dark, stealth_modifier};
 
 mod themes;
 pub use themes::*;
@@ -22,11 +58,41 @@ pub struct Map {
     pub revealed_tiles: Vec<bool>,
     pub visible_tiles: Vec<bool>,
     pub depth: i32,
+    /// Which branch of the dungeon the level is in; see `MAIN_BRANCH`.
+    #[serde(default)]
+    pub branch: usize,
+    /// Which area of the overworld the level is, for those on the surface; see `WorldCoord`.
+    #[serde(default)]
+    pub area: WorldCoord,
     pub bloodstains: HashSet<usize>,
+    /// Fire, smoke and gas over the tiles that have any, by tile index.
+    #[serde(default)]
+    pub environment: HashMap<usize, Environment>,
     pub view_blocked: HashSet<usize>,
+    /// Tiles where a secret door nobody has found yet passes for a wall.
+    #[serde(default)]
+    pub disguised: HashSet<usize>,
     pub name: String,
     pub outdoors: bool,
+    /// Open to the sky, so the light follows the world clock from day into night.
+    #[serde(default)]
+    pub daylight: bool,
+    /// The theme the level is drawn with, when its chain picks one rather than going by depth.
+    #[serde(default)]
+    pub theme: Option<String>,
     pub light: Vec<rltk::RGB>,
+    /// The light the whole level was last filled with, before any lamps; None if it hasn't been lit yet.
+    #[serde(default)]
+    pub ambient: Option<rltk::RGB>,
+    /// The tiles lamps lit last time, so that only they need putting back to the ambient light.
+    #[serde(default)]
+    pub lamplit: Vec<usize>,
+    /// Where each staircase on the level leads, by tile index.
+    #[serde(default)]
+    pub stairs: HashMap<usize, StairLink>,
+    /// The checks the level passed when it was built.
+    #[serde(default)]
+    pub report: LevelReport,
 }
 
 impl Map {
@@ -42,11 +108,21 @@ impl Map {
             revealed_tiles: vec![false; map_tile_count],
             visible_tiles: vec![false; map_tile_count],
             depth: new_depth,
+            branch: MAIN_BRANCH,
+            area: WorldCoord::default(),
             bloodstains: HashSet::new(),
+            environment: HashMap::new(),
             view_blocked: HashSet::new(),
+            disguised: HashSet::new(),
             name: name.to_string(),
             outdoors: true,
+            daylight: false,
+            theme: None,
             light: vec![rltk::RGB::from_f32(0.0, 0.0, 0.0); map_tile_count],
+            ambient: None,
+            lamplit: Vec::new(),
+            stairs: HashMap::new(),
+            report: LevelReport::default(),
         }
     }
 
@@ -65,30 +141,27 @@ impl Map {
 
     pub fn populate_blocked(&mut self) { crate::spatial::populate_blocked_from_map(self); }
 
-    pub fn populate_blocked_multi(&mut self, width: i32, height: i32) {
-        self.populate_blocked();
-
-        for y in 1..self.height - 1 {
-            for x in 1..self.width - 1 {
-                let idx = self.xy_idx(x, y);
-
-                if !crate::spatial::is_blocked(idx) {
-                    for cy in 0..height {
-                        for cx in 0..width {
-                            let tx = x + cx;
-                            let ty = y + cy;
-                            if tx < self.width - 1 && ty < self.height - 1 {
-                                let tidx = self.xy_idx(tx, ty);
-                                if crate::spatial::is_blocked(tidx) {
-                                    crate::spatial::set_blocked(idx, true);
-                                }
-                            } else {
-                                crate::spatial::set_blocked(idx, true);
-                            }
+    /// Can the tile be dug out? Never on the outer edge, so that the level stays closed.
+    pub fn can_dig(&self, idx: usize) -> bool {
+        let (x, y) = (idx as i32 % self.width, idx as i32 / self.width);
+        x > 0 && y > 0 && x < self.width - 1 && y < self.height - 1 && tile_diggable(self.tiles[idx])
+    }
+
+    /// Every tile an entity at (x, y) covers - just the one, unless it has a `TileSize`.
+    pub fn footprint(&self, x: i32, y: i32, size: Option<&TileSize>) -> Vec<usize> {
+        match size {
+            None => vec![self.xy_idx(x, y)],
+            Some(size) => {
+                let mut tiles = Vec::new();
+                for ty in y..y + size.y {
+                    for tx in x..x + size.x {
+                        if tx > 0 && tx < self.width - 1 && ty > 0 && ty < self.height - 1 {
+                            tiles.push(self.xy_idx(tx, ty));
                         }
                     }
                 }
-            }
+                tiles
+            },
         }
     }
 }
@@ -107,35 +180,34 @@ impl BaseMap for Map {
         let mut exits = rltk::SmallVec::new();
         let x = idx as i32 % self.width;
         let y = idx as i32 / self.width;
-        let tt = self.tiles[idx as usize];
         let w = self.width as usize;
 
         // Cardinal directions
         if self.is_exit_valid(x - 1, y) {
-            exits.push((idx - 1, tile_cost(tt)))
+            exits.push((idx - 1, self.step_cost(idx, idx - 1)))
         };
         if self.is_exit_valid(x + 1, y) {
-            exits.push((idx + 1, tile_cost(tt)))
+            exits.push((idx + 1, self.step_cost(idx, idx + 1)))
         };
         if self.is_exit_valid(x, y - 1) {
-            exits.push((idx - w, tile_cost(tt)))
+            exits.push((idx - w, self.step_cost(idx, idx - w)))
         };
         if self.is_exit_valid(x, y + 1) {
-            exits.push((idx + w, tile_cost(tt)))
+            exits.push((idx + w, self.step_cost(idx, idx + w)))
         };
 
         // Diagonals
         if self.is_exit_valid(x - 1, y - 1) {
-            exits.push(((idx - w) - 1, tile_cost(tt) * DIAGONAL_COST));
+            exits.push(((idx - w) - 1, self.step_cost(idx, (idx - w) - 1) * DIAGONAL_COST));
         }
         if self.is_exit_valid(x + 1, y - 1) {
-            exits.push(((idx - w) + 1, tile_cost(tt) * DIAGONAL_COST));
+            exits.push(((idx - w) + 1, self.step_cost(idx, (idx - w) + 1) * DIAGONAL_COST));
         }
         if self.is_exit_valid(x - 1, y + 1) {
-            exits.push(((idx + w) - 1, tile_cost(tt) * DIAGONAL_COST));
+            exits.push(((idx + w) - 1, self.step_cost(idx, (idx + w) - 1) * DIAGONAL_COST));
         }
         if self.is_exit_valid(x + 1, y + 1) {
-            exits.push(((idx + w) + 1, tile_cost(tt) * DIAGONAL_COST));
+            exits.push(((idx + w) + 1, self.step_cost(idx, (idx + w) + 1) * DIAGONAL_COST));
         }
 
         exits
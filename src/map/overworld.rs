@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+/// How many areas across the overworld is. The town is on its western edge, with the wilds spreading out east.
+pub const REGION_WIDTH: i32 = 5;
+/// How many areas the overworld reaches north and south of the town.
+pub const REGION_REACH: i32 = 1;
+/// How many turns it takes to travel from one area of the overworld to the next.
+pub const TRAVEL_TURNS: i32 = 100;
+/// How many areas of the wilds, besides the ruined watchtower, have a way down into the dungeon.
+const WILD_ENTRANCES: usize = 2;
+/// The level chains the areas of the overworld are built from.
+pub const AREA_CHAINS: [&str; 4] = ["The Town of Bracketon", "Forest Road", "Farmland", "Ruined Watchtower"];
+
+/// Where an area of the overworld lies: how many areas east (`x`) and south (`y`) of the town it is. Everywhere
+/// else - the town and the whole dungeon beneath it - is at the origin.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct WorldCoord {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl WorldCoord {
+    pub const TOWN: WorldCoord = WorldCoord { x: 0, y: 0 };
+
+    pub fn new(x: i32, y: i32) -> WorldCoord { WorldCoord { x, y } }
+
+    /// How many areas there are to cross between here and the town.
+    pub fn distance_from_town(&self) -> i32 { i32::max(self.x.abs(), self.y.abs()) }
+}
+
+/// One area of the overworld, and what is to be found there.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Area {
+    pub coord: WorldCoord,
+    /// The level chain the area is built from, by name.
+    pub chain: String,
+    /// Whether the area has a way down into the dungeon. Every area chain makes one; the others are grassed over.
+    pub entrance: bool,
+}
+
+impl Area {
+    /// How the area shows on the map of the overworld.
+    pub fn glyph(&self) -> (rltk::FontCharType, rltk::RGB) {
+        let (glyph, colour) = match self.chain.as_str() {
+            "The Town of Bracketon" => ('■', rltk::WHITE),
+            "Forest Road" => ('♣', rltk::GREEN),
+            "Farmland" => ('"', rltk::YELLOW),
+            "Ruined Watchtower" => ('^', rltk::GREY),
+            _ => ('?', rltk::MAGENTA),
+        };
+        (rltk::to_cp437(glyph), rltk::RGB::named(colour))
+    }
+}
+
+/// Lays out the overworld for a new game: the town, forest roads and farmland, and a ruined watchtower out on the
+/// far side with a way down beneath it. A few other areas have a way down too.
+pub fn make_region() -> Vec<Area> {
+    let tower = WorldCoord::new(REGION_WIDTH - 1, crate::rng::range(-REGION_REACH, REGION_REACH + 1));
+    let mut region: Vec<Area> = Vec::new();
+    for x in 0..REGION_WIDTH {
+        for y in -REGION_REACH..=REGION_REACH {
+            let coord = WorldCoord::new(x, y);
+            let chain = if coord == WorldCoord::TOWN {
+                "The Town of Bracketon"
+            } else if coord == tower {
+                "Ruined Watchtower"
+            } else if crate::rng::roll_dice(1, 5) <= 3 {
+                "Forest Road"
+            } else {
+                "Farmland"
+            };
+
+            region.push(Area {
+                coord,
+                chain: chain.to_string(),
+                entrance: coord == WorldCoord::TOWN || coord == tower,
+            });
+        }
+    }
+
+    let mut wilds: Vec<usize> = (0..region.len()).filter(|i| !region[*i].entrance).collect();
+    for _ in 0..WILD_ENTRANCES {
+        if wilds.is_empty() {
+            break;
+        }
+        let i = wilds.remove((crate::rng::roll_dice(1, wilds.len() as i32) - 1) as usize);
+        region[i].entrance = true;
+    }
+
+    region
+}
+
+/// Whether something finds the player on the way into an area. The further out from the town, the likelier - and
+/// nothing lies in wait right outside the town gates.
+pub fn roll_encounter(to: WorldCoord) -> bool {
+    to != WorldCoord::TOWN && crate::rng::roll_dice(1, 100) <= 10 + 5 * to.distance_from_town()
+}
+
+impl Map {
+    /// Whether the level is one of the areas of the overworld, rather than somewhere down in the dungeon.
+    pub fn on_surface(&self) -> bool { self.branch == MAIN_BRANCH && self.depth == 1 }
+
+    /// Keeps a single way down on a freshly built area that has a dungeon entrance, and none on one that doesn't.
+    pub(super) fn settle_entrance(&mut self, start: usize, entrance: bool) {
+        let mut ways_down = (0..self.tiles.len()).filter(|idx| self.tiles[*idx] == TileType::DownStairs);
+        let keep = if entrance { ways_down.next().or_else(|| self.spot_for_stairs(start)) } else { None };
+
+        for idx in ways_down.collect::<Vec<usize>>() {
+            // Roads ran right up to the stairs, so they carry on where the stairs were
+            let on_road = self
+                .open_neighbours(idx, false)
+                .iter()
+                .any(|n| self.tiles[*n] == TileType::Road);
+            self.tiles[idx] = if on_road { TileType::Road } else { TileType::Grass };
+        }
+        if let Some(idx) = keep {
+            self.tiles[idx] = TileType::DownStairs;
+        }
+    }
+
+    /// Where someone travelling overland comes into the area: the middle of the edge they are coming from, or as
+    /// near to it as there is safe ground.
+    pub fn arrival_point(&self, heading: (i32, i32)) -> (i32, i32) {
+        let edge = |step: i32, size: i32| match step {
+            s if s > 0 => 1,
+            s if s < 0 => size - 2,
+            _ => size / 2,
+        };
+        let (x, y) = (edge(heading.0, self.width), edge(heading.1, self.height));
+        self.nearest_safe_tile(x, y, None).unwrap_or((x, y))
+    }
+}
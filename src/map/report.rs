@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+/// One of the checks every generated level has to pass before anyone is let onto it.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LevelCheck {
+    /// There is a starting position, on a safe walkable tile.
+    Start,
+    /// A way down can be walked to from the start without crossing any hazards.
+    ExitReachable,
+    /// Enough of the level is open ground to be worth exploring.
+    FloorArea,
+    /// Nothing was spawned inside a wall.
+    SpawnsWalkable,
+    /// Every locked door and its key are still in place, and each key can be reached with all the doors locked.
+    KeysReachable,
+}
+
+impl std::fmt::Display for LevelCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let text = match self {
+            LevelCheck::Start => "start",
+            LevelCheck::ExitReachable => "exit reachable",
+            LevelCheck::FloorArea => "floor area",
+            LevelCheck::SpawnsWalkable => "spawns walkable",
+            LevelCheck::KeysReachable => "keys reachable",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// How a level fared against the checks when it was built.
+#[derive(Default, Serialize, Deserialize, Clone, Debug)]
+pub struct LevelReport {
+    /// How many times the level was built, counting the first.
+    pub attempts: i32,
+    /// Every attempt failed, so the level is the plain fallback instead.
+    pub fallback: bool,
+    pub passed: Vec<LevelCheck>,
+    pub failed: Vec<LevelCheck>,
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use super::*;
+
+/// Where a staircase leads: a level, and the staircase on it that it comes out on. That is only known once the
+/// level at the other end has been built, or the stairs have been taken.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StairLink {
+    pub branch: usize,
+    pub depth: i32,
+    /// The area of the overworld, for stairs that lead up out of the dungeon.
+    #[serde(default)]
+    pub area: WorldCoord,
+    pub idx: Option<usize>,
+}
+
+impl Map {
+    pub fn is_stairs(&self, idx: usize) -> bool { matches!(self.tiles[idx], TileType::UpStairs | TileType::DownStairs) }
+
+    /// A safe spot for a new staircase, as far as can be walked from `start` and from every staircase already on
+    /// the level. Picked at random from the furthest third, so stairs don't always end up in the same corner.
+    pub fn spot_for_stairs(&self, start: usize) -> Option<usize> {
+        let mut distance = vec![i32::MAX; self.tiles.len()];
+        let mut open: VecDeque<usize> = VecDeque::new();
+        for idx in (0..self.tiles.len()).filter(|idx| *idx == start || self.is_stairs(*idx)) {
+            distance[idx] = 0;
+            open.push_back(idx);
+        }
+
+        while let Some(idx) = open.pop_front() {
+            let (x, y) = (idx as i32 % self.width, idx as i32 / self.width);
+            for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)] {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 1 || ny < 1 || nx > self.width - 2 || ny > self.height - 2 {
+                    continue;
+                }
+                let n = self.xy_idx(nx, ny);
+                if distance[n] == i32::MAX && tile_walkable(self.tiles[n]) && !endangers(self.tiles[n], None) {
+                    distance[n] = distance[idx] + 1;
+                    open.push_back(n);
+                }
+            }
+        }
+
+        let furthest = distance.iter().filter(|d| **d != i32::MAX).max().copied().unwrap_or(0);
+        if furthest < 1 {
+            return None;
+        }
+
+        let candidates: Vec<usize> = (0..self.tiles.len())
+            .filter(|idx| distance[*idx] != i32::MAX && distance[*idx] * 3 >= furthest * 2)
+            .collect();
+        Some(candidates[crate::rng::roll_dice(1, candidates.len() as i32) as usize - 1])
+    }
+}
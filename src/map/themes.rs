@@ -1,21 +1,11 @@
-use super::{Map, TileType};
+use super::{tile_look, Map, TileType};
 use rltk::RGB;
 
 pub fn tile_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, RGB, RGB) {
-    let (glyph, mut fg, mut bg) = match map.depth {
-        7 => {
-            let x = idx as i32 % map.width;
-            if x > map.width - 16 { get_tile_glyph_default(idx, map) } else { get_mushroom_glyph(idx, map) }
-        },
-        5 => {
-            let x = idx as i32 % map.width;
-            if x < map.width / 2 { get_limestone_cavern_glyph(idx, map) } else { get_tile_glyph_default(idx, map) }
-        },
-        4 => get_limestone_cavern_glyph(idx, map),
-        3 => get_limestone_cavern_glyph(idx, map),
-        2 => get_forest_glyph(idx, map),
-        _ => get_tile_glyph_default(idx, map),
-    };
+    let look = tile_look(shown_tile(idx, map), tile_theme(idx, map));
+    let glyph =
+        if look.connected { wall_glyph(map, idx as i32 % map.width, idx as i32 / map.width) } else { look.glyph };
+    let (mut fg, mut bg) = (look.fg, look.bg);
 
     if map.bloodstains.contains(&idx) {
         bg = RGB::from_f32(0.75, 0., 0.);
@@ -24,95 +14,62 @@ pub fn tile_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, RGB, RGB) {
     if !map.visible_tiles[idx] {
         fg = fg.to_greyscale();
         bg = RGB::from_f32(0., 0., 0.); // Don't show stains out of visual range
-    } else if !map.outdoors {
+    } else if !map.outdoors || map.daylight {
         fg = fg * map.light[idx];
         bg = bg * map.light[idx];
     }
 
-    (glyph, fg, bg)
+    // Fire, smoke and gas can only be seen while in view
+    match map.environment.get(&idx) {
+        Some(env) if map.visible_tiles[idx] => env.look(glyph, fg, bg),
+        _ => (glyph, fg, bg),
+    }
 }
 
-fn get_tile_glyph_default(idx: usize, map: &Map) -> (rltk::FontCharType, RGB, RGB) {
-    let glyph;
-    let fg;
-    let bg = RGB::from_f32(0., 0., 0.);
+/// Which of the themes in `tiles.json` a tile is drawn with. An empty name means the tiles' usual looks.
+pub fn tile_theme(idx: usize, map: &Map) -> &str {
+    if let Some(theme) = &map.theme {
+        return theme;
+    }
 
-    match map.tiles[idx] {
-        TileType::Floor => {
-            glyph = rltk::to_cp437('.');
-            fg = RGB::from_f32(0.0, 0.5, 0.5);
-        },
-        TileType::WoodFloor => {
-            glyph = rltk::to_cp437('░');
-            fg = RGB::named(rltk::CHOCOLATE);
-        },
-        TileType::Wall => {
+    // Side branches are dug out of the same rock as the caverns they lead off
+    if map.branch != super::MAIN_BRANCH {
+        return "limestone";
+    }
+
+    match map.depth {
+        7 => {
             let x = idx as i32 % map.width;
-            let y = idx as i32 / map.width;
-            glyph = wall_glyph(&*map, x, y);
-            fg = RGB::from_f32(0., 1.0, 0.);
-        },
-        TileType::DownStairs => {
-            glyph = rltk::to_cp437('>');
-            fg = RGB::from_f32(0., 1.0, 1.0);
-        },
-        TileType::UpStairs => {
-            glyph = rltk::to_cp437('<');
-            fg = RGB::from_f32(0., 1.0, 1.0);
-        },
-        TileType::Bridge => {
-            glyph = rltk::to_cp437('.');
-            fg = RGB::named(rltk::CHOCOLATE);
-        },
-        TileType::Road => {
-            glyph = rltk::to_cp437('≡');
-            fg = RGB::named(rltk::GRAY);
-        },
-        TileType::Grass => {
-            glyph = rltk::to_cp437('"');
-            fg = RGB::named(rltk::GREEN);
-        },
-        TileType::ShallowWater => {
-            glyph = rltk::to_cp437('~');
-            fg = RGB::named(rltk::CYAN);
+            if x > map.width - 16 { "" } else { "mushroom" }
         },
-        TileType::DeepWater => {
-            glyph = rltk::to_cp437('~');
-            fg = RGB::named(rltk::BLUE);
-        },
-        TileType::Gravel => {
-            glyph = rltk::to_cp437(';');
-            fg = RGB::from_f32(0.5, 0.5, 0.5);
-        },
-        TileType::Stalactite => {
-            glyph = rltk::to_cp437('╨');
-            fg = RGB::from_f32(0.5, 0.5, 0.5);
-        },
-        TileType::Stalagmite => {
-            glyph = rltk::to_cp437('╥');
-            fg = RGB::from_f32(0.5, 0.5, 0.5);
+        5 => {
+            let x = idx as i32 % map.width;
+            if x < map.width / 2 { "limestone" } else { "" }
         },
+        4 => "limestone",
+        3 => "limestone",
+        2 => "forest",
+        _ => "",
     }
-
-    (glyph, fg, bg)
 }
 
 fn wall_glyph(map: &Map, x: i32, y: i32) -> rltk::FontCharType {
     if x < 1 || x > map.width - 2 || y < 1 || y > map.height - 2_i32 {
         return 35;
     }
+    let tt = shown_tile(map.xy_idx(x, y), map);
     let mut mask: u8 = 0;
 
-    if is_revealed_and_wall(map, x, y - 1) {
+    if is_revealed_and_same(map, x, y - 1, tt) {
         mask += 1;
     }
-    if is_revealed_and_wall(map, x, y + 1) {
+    if is_revealed_and_same(map, x, y + 1, tt) {
         mask += 2;
     }
-    if is_revealed_and_wall(map, x - 1, y) {
+    if is_revealed_and_same(map, x - 1, y, tt) {
         mask += 4;
     }
-    if is_revealed_and_wall(map, x + 1, y) {
+    if is_revealed_and_same(map, x + 1, y, tt) {
         mask += 8;
     }
 
@@ -137,169 +94,12 @@ fn wall_glyph(map: &Map, x: i32, y: i32) -> rltk::FontCharType {
     }
 }
 
-fn get_forest_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, RGB, RGB) {
-    let glyph;
-    let fg;
-    let bg = RGB::from_f32(0., 0., 0.);
-
-    match map.tiles[idx] {
-        TileType::Wall => {
-            glyph = rltk::to_cp437('♣');
-            fg = RGB::from_f32(0.0, 0.6, 0.0);
-        },
-        TileType::Bridge => {
-            glyph = rltk::to_cp437('.');
-            fg = RGB::named(rltk::CHOCOLATE);
-        },
-        TileType::Road => {
-            glyph = rltk::to_cp437('≡');
-            fg = RGB::named(rltk::YELLOW);
-        },
-        TileType::Grass => {
-            glyph = rltk::to_cp437('"');
-            fg = RGB::named(rltk::GREEN);
-        },
-        TileType::ShallowWater => {
-            glyph = rltk::to_cp437('~');
-            fg = RGB::named(rltk::CYAN);
-        },
-        TileType::DeepWater => {
-            glyph = rltk::to_cp437('~');
-            fg = RGB::named(rltk::BLUE);
-        },
-        TileType::Gravel => {
-            glyph = rltk::to_cp437(';');
-            fg = RGB::from_f32(0.5, 0.5, 0.5);
-        },
-        TileType::DownStairs => {
-            glyph = rltk::to_cp437('>');
-            fg = RGB::from_f32(0., 1.0, 1.0);
-        },
-        TileType::UpStairs => {
-            glyph = rltk::to_cp437('<');
-            fg = RGB::from_f32(0., 1.0, 1.0);
-        },
-        _ => {
-            glyph = rltk::to_cp437('"');
-            fg = RGB::from_f32(0.0, 0.6, 0.0);
-        },
-    }
-
-    (glyph, fg, bg)
-}
-
-fn get_limestone_cavern_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, RGB, RGB) {
-    let glyph;
-    let fg;
-    let bg = RGB::from_f32(0., 0., 0.);
-
-    match map.tiles[idx] {
-        TileType::Wall => {
-            glyph = rltk::to_cp437('▒');
-            fg = RGB::from_f32(0.7, 0.7, 0.7);
-        },
-        TileType::Bridge => {
-            glyph = rltk::to_cp437('.');
-            fg = RGB::named(rltk::CHOCOLATE);
-        },
-        TileType::Road => {
-            glyph = rltk::to_cp437('≡');
-            fg = RGB::named(rltk::YELLOW);
-        },
-        TileType::Grass => {
-            glyph = rltk::to_cp437('"');
-            fg = RGB::named(rltk::GREEN);
-        },
-        TileType::ShallowWater => {
-            glyph = rltk::to_cp437('░');
-            fg = RGB::named(rltk::CYAN);
-        },
-        TileType::DeepWater => {
-            glyph = rltk::to_cp437('▓');
-            fg = RGB::from_f32(0.2, 0.2, 1.0);
-        },
-
-        TileType::Gravel => {
-            glyph = rltk::to_cp437(';');
-            fg = RGB::from_f32(0.5, 0.5, 0.5);
-        },
-        TileType::DownStairs => {
-            glyph = rltk::to_cp437('>');
-            fg = RGB::from_f32(0., 1.0, 1.0);
-        },
-        TileType::UpStairs => {
-            glyph = rltk::to_cp437('<');
-            fg = RGB::from_f32(0., 1.0, 1.0);
-        },
-        TileType::Stalactite => {
-            glyph = rltk::to_cp437('╨');
-            fg = RGB::from_f32(0.7, 0.7, 0.7);
-        },
-        TileType::Stalagmite => {
-            glyph = rltk::to_cp437('╥');
-            fg = RGB::from_f32(0.7, 0.7, 0.7);
-        },
-        _ => {
-            glyph = rltk::to_cp437('░');
-            fg = RGB::from_f32(0.4, 0.4, 0.4);
-        },
-    }
-
-    (glyph, fg, bg)
-}
-
-fn get_mushroom_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, RGB, RGB) {
-    let glyph;
-    let fg;
-    let bg = RGB::from_f32(0., 0., 0.);
-
-    match map.tiles[idx] {
-        TileType::Wall => {
-            glyph = rltk::to_cp437('♠');
-            fg = RGB::from_f32(1.0, 0.0, 1.0);
-        },
-        TileType::Bridge => {
-            glyph = rltk::to_cp437('.');
-            fg = RGB::named(rltk::GREEN);
-        },
-        TileType::Road => {
-            glyph = rltk::to_cp437('≡');
-            fg = RGB::named(rltk::CHOCOLATE);
-        },
-        TileType::Grass => {
-            glyph = rltk::to_cp437('"');
-            fg = RGB::named(rltk::GREEN);
-        },
-        TileType::ShallowWater => {
-            glyph = rltk::to_cp437('~');
-            fg = RGB::named(rltk::CYAN);
-        },
-        TileType::DeepWater => {
-            glyph = rltk::to_cp437('~');
-            fg = RGB::named(rltk::BLUE);
-        },
-        TileType::Gravel => {
-            glyph = rltk::to_cp437(';');
-            fg = RGB::from_f32(0.5, 0.5, 0.5);
-        },
-        TileType::DownStairs => {
-            glyph = rltk::to_cp437('>');
-            fg = RGB::from_f32(0., 1.0, 1.0);
-        },
-        TileType::UpStairs => {
-            glyph = rltk::to_cp437('<');
-            fg = RGB::from_f32(0., 1.0, 1.0);
-        },
-        _ => {
-            glyph = rltk::to_cp437('"');
-            fg = RGB::from_f32(0.0, 0.6, 0.0);
-        },
-    }
-
-    (glyph, fg, bg)
+fn is_revealed_and_same(map: &Map, x: i32, y: i32, tt: TileType) -> bool {
+    let idx = map.xy_idx(x, y);
+    shown_tile(idx, map) == tt && map.revealed_tiles[idx]
 }
 
-fn is_revealed_and_wall(map: &Map, x: i32, y: i32) -> bool {
-    let idx = map.xy_idx(x, y);
-    map.tiles[idx] == TileType::Wall && map.revealed_tiles[idx]
+/// The tile as the player sees it, with secret doors they haven't found drawn as the wall they pass for.
+fn shown_tile(idx: usize, map: &Map) -> TileType {
+    if map.disguised.contains(&idx) { TileType::Wall } else { map.tiles[idx] }
 }
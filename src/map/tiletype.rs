@@ -1,44 +1,219 @@
-use serde::{Deserialize, Serialize};
-
-#[derive(PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
-pub enum TileType {
-    Wall,
-    Stalactite,
-    Stalagmite,
-    Floor,
-    DownStairs,
-    Road,
-    Grass,
-    ShallowWater,
-    DeepWater,
-    WoodFloor,
-    Bridge,
-    Gravel,
-    UpStairs,
-}
-
-pub fn tile_walkable(tt: TileType) -> bool {
-    matches!(
-        tt,
-        TileType::Floor
-            | TileType::DownStairs
-            | TileType::Road
-            | TileType::Grass
-            | TileType::ShallowWater
-            | TileType::WoodFloor
-            | TileType::Bridge
-            | TileType::Gravel
-            | TileType::UpStairs
-    )
-}
-
-pub fn tile_opaque(tt: TileType) -> bool { matches!(tt, TileType::Wall | TileType::Stalactite | TileType::Stalagmite) }
-
-pub fn tile_cost(tt: TileType) -> f32 {
-    match tt {
-        TileType::Road => 0.8,
-        TileType::Grass => 1.1,
-        TileType::ShallowWater => 1.2,
-        _ => 1.0,
+use rltk::RGB;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::raws::structs::{TileDefinition, TileEntryEffect};
+
+/// A kind of terrain, as an index into the tile definitions loaded from `tiles.json`. The tiles the map builders
+/// rely on are always present, at fixed indices; anything else in the file is only known by name. Saves and level
+/// files store the name, so adding tiles doesn't invalidate them.
+#[derive(PartialEq, Eq, Hash, Copy, Clone)]
+pub struct TileType(u16);
+
+#[allow(non_upper_case_globals)]
+impl TileType {
+    pub const Wall: TileType = TileType(0);
+    pub const Stalactite: TileType = TileType(1);
+    pub const Stalagmite: TileType = TileType(2);
+    pub const Floor: TileType = TileType(3);
+    pub const DownStairs: TileType = TileType(4);
+    pub const Road: TileType = TileType(5);
+    pub const Grass: TileType = TileType(6);
+    pub const ShallowWater: TileType = TileType(7);
+    pub const DeepWater: TileType = TileType(8);
+    pub const WoodFloor: TileType = TileType(9);
+    pub const Bridge: TileType = TileType(10);
+    pub const Gravel: TileType = TileType(11);
+    pub const UpStairs: TileType = TileType(12);
+}
+
+/// Names of the tiles with a constant above, in index order.
+const BUILT_IN: [&str; 13] = [
+    "Wall",
+    "Stalactite",
+    "Stalagmite",
+    "Floor",
+    "DownStairs",
+    "Road",
+    "Grass",
+    "ShallowWater",
+    "DeepWater",
+    "WoodFloor",
+    "Bridge",
+    "Gravel",
+    "UpStairs",
+];
+
+/// How a tile is drawn, with its colours already parsed.
+#[derive(Copy, Clone)]
+pub struct TileLook {
+    pub glyph: rltk::FontCharType,
+    pub fg: RGB,
+    pub bg: RGB,
+    pub connected: bool,
+}
+
+struct TileDef {
+    name: String,
+    ascii: char,
+    walkable: bool,
+    opaque: bool,
+    cost: f32,
+    flammable: bool,
+    burns_into: Option<String>,
+    hazard: Option<String>,
+    slippery: bool,
+    diggable: bool,
+    look: TileLook,
+    themes: HashMap<String, TileLook>,
+    on_enter: Option<TileEntryEffect>,
+}
+
+/// Set once when the raws load, and only read after that, so looking a tile up never has to take a lock.
+static TILES: OnceLock<Vec<TileDef>> = OnceLock::new();
+
+fn tiles() -> &'static [TileDef] { TILES.get().expect("Tile definitions haven't been loaded") }
+
+/// Takes the tile definitions from the raws; they can only be set once. Every built-in tile must be defined.
+pub fn register_tiles(definitions: &[TileDefinition]) {
+    let mut ordered: Vec<&TileDefinition> = BUILT_IN
+        .iter()
+        .map(|name| {
+            definitions
+                .iter()
+                .find(|def| def.name == *name)
+                .unwrap_or_else(|| panic!("tiles.json must define {}", name))
+        })
+        .collect();
+    ordered.extend(definitions.iter().filter(|def| !BUILT_IN.contains(&def.name.as_str())));
+
+    let mut tiles = Vec::new();
+    for def in ordered {
+        if tiles.iter().any(|t: &TileDef| t.name == def.name) {
+            panic!("Tile {} is defined twice", def.name);
+        }
+        if let Some(on_enter) = &def.on_enter {
+            for dice in on_enter.damage.iter().chain(on_enter.drown.iter()) {
+                if rltk::parse_dice_string(dice).is_err() {
+                    panic!("Tile {} has invalid damage dice {}", def.name, dice);
+                }
+            }
+        }
+
+        tiles.push(TileDef {
+            name: def.name.clone(),
+            ascii: def.ascii,
+            walkable: def.walkable,
+            opaque: def.opaque,
+            cost: def.cost,
+            flammable: def.flammable,
+            burns_into: def.burns_into.clone(),
+            hazard: def.hazard.clone(),
+            slippery: def.slippery,
+            diggable: def.diggable,
+            look: parse_look(&def.name, &def.look),
+            themes: def
+                .themes
+                .iter()
+                .map(|(theme, look)| (theme.clone(), parse_look(&def.name, look)))
+                .collect(),
+            on_enter: def.on_enter.clone(),
+        });
+    }
+
+    for def in tiles.iter() {
+        if let Some(into) = &def.burns_into {
+            if !tiles.iter().any(|t| t.name == *into) {
+                panic!("Tile {} burns into unknown tile {}", def.name, into);
+            }
+        }
+    }
+
+    if TILES.set(tiles).is_err() {
+        panic!("Tile definitions are already loaded");
+    }
+}
+
+fn parse_look(name: &str, look: &crate::raws::structs::TileLook) -> TileLook {
+    let colour =
+        |hex: &str| RGB::from_hex(hex).unwrap_or_else(|_| panic!("Tile {} has an invalid colour {}", name, hex));
+    TileLook {
+        glyph: rltk::to_cp437(look.glyph),
+        fg: colour(&look.fg),
+        bg: look.bg.as_ref().map_or(RGB::from_f32(0., 0., 0.), |bg| colour(bg)),
+        connected: look.connected,
+    }
+}
+
+fn with_def<R>(tt: TileType, f: impl FnOnce(&TileDef) -> R) -> R { f(&tiles()[tt.0 as usize]) }
+
+impl TileType {
+    /// Looks a tile up by its name in `tiles.json`, ignoring case.
+    pub fn named(name: &str) -> Option<TileType> {
+        tiles()
+            .iter()
+            .position(|def| def.name.eq_ignore_ascii_case(name))
+            .map(|idx| TileType(idx as u16))
     }
+
+    /// Looks a tile up by the glyph it is written as in level files.
+    pub fn from_ascii(glyph: char) -> Option<TileType> {
+        tiles()
+            .iter()
+            .position(|def| def.ascii == glyph)
+            .map(|idx| TileType(idx as u16))
+    }
+
+    /// Every defined tile, built-in ones first.
+    pub fn all() -> Vec<TileType> { (0..tiles().len()).map(|idx| TileType(idx as u16)).collect() }
+
+    pub fn name(&self) -> String { with_def(*self, |def| def.name.clone()) }
 }
+
+impl std::fmt::Debug for TileType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.write_str(&self.name()) }
+}
+
+impl Serialize for TileType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        with_def(*self, |def| serializer.serialize_str(&def.name))
+    }
+}
+
+impl<'de> Deserialize<'de> for TileType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<TileType, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        TileType::named(&name).ok_or_else(|| serde::de::Error::custom(format!("unknown tile type {}", name)))
+    }
+}
+
+pub fn tile_walkable(tt: TileType) -> bool { with_def(tt, |def| def.walkable) }
+
+pub fn tile_opaque(tt: TileType) -> bool { with_def(tt, |def| def.opaque) }
+
+pub fn tile_cost(tt: TileType) -> f32 { with_def(tt, |def| def.cost) }
+
+pub fn tile_flammable(tt: TileType) -> bool { with_def(tt, |def| def.flammable) }
+
+/// What a flammable tile becomes once it has burnt out, if anything.
+pub fn tile_burns_into(tt: TileType) -> Option<TileType> {
+    with_def(tt, |def| def.burns_into.clone()).and_then(|name| TileType::named(&name))
+}
+
+pub fn tile_hazard(tt: TileType) -> Option<String> { with_def(tt, |def| def.hazard.clone()) }
+
+pub fn tile_slippery(tt: TileType) -> bool { with_def(tt, |def| def.slippery) }
+
+pub fn tile_diggable(tt: TileType) -> bool { with_def(tt, |def| def.diggable) }
+
+/// The glyph a tile is written as in level files.
+pub fn tile_ascii(tt: TileType) -> char { with_def(tt, |def| def.ascii) }
+
+/// How a tile is drawn in a theme, falling back to its usual look if the theme doesn't change it.
+pub fn tile_look(tt: TileType, theme: &str) -> TileLook {
+    with_def(tt, |def| *def.themes.get(theme).unwrap_or(&def.look))
+}
+
+/// What stepping onto a tile does, if anything.
+pub fn tile_entry_effect(tt: TileType) -> Option<TileEntryEffect> { with_def(tt, |def| def.on_enter.clone()) }
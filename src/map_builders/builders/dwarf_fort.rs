@@ -1,28 +1,4 @@
-use super::{
-    AreaEndingPosition, AreaStartingPosition, BspCorridors, BspDungeonBuilder, BuilderChain, BuilderMap,
-    CorridorSpawner, CullUnreachable, DLABuilder, DistantExit, MetaMapBuilder, RoomDrawer, RoomSort, RoomSorter,
-    TileType, VoronoiSpawning, XEnd, XStart, YEnd, YStart,
-};
-
-pub fn dwarf_fort_builder(new_depth: i32, width: i32, height: i32) -> BuilderChain {
-    let mut chain = BuilderChain::new(new_depth, width, height, "Dwarven Fortress");
-
-    chain.start_with(BspDungeonBuilder::new());
-    chain.with(RoomSorter::new(RoomSort::Central));
-    chain.with(RoomDrawer::new());
-    chain.with(BspCorridors::new());
-    chain.with(CorridorSpawner::new());
-    chain.with(DragonsLair::new());
-
-    chain.with(AreaStartingPosition::new(XStart::Left, YStart::Top));
-    chain.with(CullUnreachable::new());
-    chain.with(AreaEndingPosition::new(XEnd::Right, YEnd::Bottom));
-    chain.with(VoronoiSpawning::new());
-    chain.with(DistantExit::new());
-    chain.with(DragonSpawner::new());
-
-    chain
-}
+use super::{BuilderChain, BuilderMap, DLABuilder, MetaMapBuilder, TileType};
 
 pub struct DragonsLair {}
 
@@ -74,7 +50,7 @@ impl DragonSpawner {
         let seed_y = build_data.map.height / 2;
         let mut available_floors: Vec<(usize, f32)> = Vec::new();
         for (idx, tiletype) in build_data.map.tiles.iter().enumerate() {
-            if crate::map::tile_walkable(*tiletype) {
+            if crate::map::tile_walkable(*tiletype) && !crate::map::endangers(*tiletype, None) {
                 available_floors.push((
                     idx,
                     rltk::DistanceAlg::PythagorasSquared.distance2d(
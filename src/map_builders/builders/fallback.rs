@@ -0,0 +1,28 @@
+use super::{BuilderMap, InitialMapBuilder, Position, TileType};
+
+/// A single open hall with the start at one end and the way down at the other. Nothing about it can go wrong,
+/// which makes it the level of last resort when a chain keeps failing its checks.
+pub struct FallbackBuilder {}
+
+impl InitialMapBuilder for FallbackBuilder {
+    fn build_map(&mut self, build_data: &mut BuilderMap) { self.build(build_data); }
+}
+
+impl FallbackBuilder {
+    pub fn new() -> Box<FallbackBuilder> { Box::new(FallbackBuilder {}) }
+
+    fn build(&mut self, build_data: &mut BuilderMap) {
+        let (width, height) = (build_data.map.width, build_data.map.height);
+        for y in 1..height - 1 {
+            for x in 1..width - 1 {
+                let idx = build_data.map.xy_idx(x, y);
+                build_data.map.tiles[idx] = TileType::Floor;
+            }
+        }
+
+        build_data.starting_position = Some(Position { x: 2, y: height / 2 });
+        let exit = build_data.map.xy_idx(width - 3, height / 2);
+        build_data.map.tiles[exit] = TileType::DownStairs;
+        build_data.take_snapshot();
+    }
+}
@@ -0,0 +1,44 @@
+use super::{AreaStartingPosition, BuilderMap, InitialMapBuilder, MetaMapBuilder, Position, TileType, XStart, YStart};
+use crate::map::LevelFile;
+
+/// Starts the chain from a hand-made level file rather than generating anything.
+pub struct FileMapBuilder {
+    level: LevelFile,
+}
+
+impl InitialMapBuilder for FileMapBuilder {
+    fn build_map(&mut self, build_data: &mut BuilderMap) { self.build(build_data); }
+}
+
+impl FileMapBuilder {
+    pub fn new(level: LevelFile) -> Box<FileMapBuilder> { Box::new(FileMapBuilder { level }) }
+
+    fn build(&mut self, build_data: &mut BuilderMap) {
+        build_data.map = self.level.to_map().expect("Invalid level file");
+        build_data.width = build_data.map.width;
+        build_data.height = build_data.map.height;
+        build_data.take_snapshot();
+
+        // Start where the file says, or failing that on the up stairs, or failing that in the middle
+        build_data.starting_position = match self.level.start {
+            Some((x, y)) => Some(Position { x, y }),
+            None => build_data
+                .map
+                .tiles
+                .iter()
+                .position(|tt| *tt == TileType::UpStairs)
+                .map(|idx| Position {
+                    x: idx as i32 % build_data.map.width,
+                    y: idx as i32 / build_data.map.width,
+                }),
+        };
+        if build_data.starting_position.is_none() {
+            AreaStartingPosition::new(XStart::Center, YStart::Center).build_map(build_data);
+        }
+
+        for spawn in self.level.spawns.iter() {
+            let idx = build_data.map.xy_idx(spawn.x, spawn.y);
+            build_data.spawn_list.push((idx, spawn.name.clone()));
+        }
+    }
+}
@@ -1,23 +1,7 @@
-use super::{
-    AreaStartingPosition, BuilderChain, BuilderMap, CellularAutomataBuilder, CullUnreachable, MetaMapBuilder, TileType,
-    VoronoiSpawning, XStart, YStart,
-};
+use super::{BuilderMap, MetaMapBuilder, TileType};
 
 use crate::map;
 
-pub fn forest_builder(new_depth: i32, width: i32, height: i32) -> BuilderChain {
-    let mut chain = BuilderChain::new(new_depth, width, height, "Into the Woods");
-
-    chain.start_with(CellularAutomataBuilder::new());
-    chain.with(AreaStartingPosition::new(XStart::Center, YStart::Center));
-    chain.with(CullUnreachable::new());
-    chain.with(AreaStartingPosition::new(XStart::Left, YStart::Center));
-    chain.with(VoronoiSpawning::new());
-    chain.with(YellowBrickRoad::new());
-
-    chain
-}
-
 pub struct YellowBrickRoad {}
 
 impl MetaMapBuilder for YellowBrickRoad {
@@ -31,7 +15,7 @@ impl YellowBrickRoad {
     fn find_exit(&self, build_data: &mut BuilderMap, seed_x: i32, seed_y: i32) -> (i32, i32) {
         let mut available_floors: Vec<(usize, f32)> = Vec::new();
         for (idx, tiletype) in build_data.map.tiles.iter().enumerate() {
-            if map::tile_walkable(*tiletype) {
+            if map::tile_walkable(*tiletype) && !map::endangers(*tiletype, None) {
                 available_floors.push((
                     idx,
                     rltk::DistanceAlg::PythagorasSquared.distance2d(
@@ -70,11 +54,11 @@ impl YellowBrickRoad {
         let end_idx = build_data.map.xy_idx(end_x, end_y);
 
         build_data.map.populate_blocked();
+        // With no way through there's no road either; the stairs still go in, and the level fails its checks for
+        // being unable to reach them
         let path = rltk::a_star_search(start_idx, end_idx, &build_data.map);
-        if !path.success {
-            panic!("No valid path for the road");
-        }
-        for idx in path.steps.iter() {
+        let road = if path.success { path.steps } else { Vec::new() };
+        for idx in road.iter() {
             let x = *idx as i32 % build_data.map.width;
             let y = *idx as i32 / build_data.map.width;
             self.paint_road(build_data, x, y);
@@ -104,11 +88,11 @@ impl YellowBrickRoad {
         build_data.take_snapshot();
 
         let (stream_x, stream_y) = self.find_exit(build_data, stream_startx, stream_starty);
-        let stream_idx = build_data.map.xy_idx(stream_x, stream_y) as usize;
+        let stream_idx = build_data.map.xy_idx(stream_x, stream_y);
         let stream = rltk::a_star_search(stairs_idx, stream_idx, &build_data.map);
         for tile in stream.steps.iter() {
-            if build_data.map.tiles[*tile as usize] == TileType::Floor {
-                build_data.map.tiles[*tile as usize] = TileType::ShallowWater;
+            if build_data.map.tiles[*tile] == TileType::Floor {
+                build_data.map.tiles[*tile] = TileType::ShallowWater;
             }
         }
         build_data.map.tiles[stairs_idx] = TileType::DownStairs;
@@ -1,89 +1,17 @@
 use super::{
-    AreaEndingPosition,
-    AreaStartingPosition,
     BspDungeonBuilder,
     BuilderChain,
     BuilderMap,
-    CellularAutomataBuilder,
-    CullUnreachable,
-    DLABuilder,
-    DistantExit,
-    DrunkardsWalkBuilder,
     MetaMapBuilder,
     NearestCorridors,
-    PrefabBuilder,
     RoomBasedSpawner,
     RoomDrawer,
     RoomExploder,
     RoomSort,
     RoomSorter,
     TileType,
-    VoronoiSpawning,
-    XEnd,
-    XStart,
-    YEnd,
-    YStart,
 };
 
-
-pub fn limestone_cavern_builder(
-    new_depth: i32,
-    width: i32,
-    height: i32,
-) -> BuilderChain {
-    let mut chain = BuilderChain::new(new_depth, width, height, "Limestone Caverns");
-
-    chain.start_with(DrunkardsWalkBuilder::winding_passages());
-    chain.with(AreaStartingPosition::new(XStart::Center, YStart::Center));
-    chain.with(CullUnreachable::new());
-    chain.with(AreaStartingPosition::new(XStart::Left, YStart::Center));
-    chain.with(VoronoiSpawning::new());
-    chain.with(DistantExit::new());
-    chain.with(CaveDecorator::new());
-
-    chain
-}
-
-pub fn limestone_deep_cavern_builder(
-    new_depth: i32,
-    width: i32,
-    height: i32,
-) -> BuilderChain {
-    let mut chain = BuilderChain::new(new_depth, width, height, "Deep Limestone Caverns");
-
-    chain.start_with(DLABuilder::central_attractor());
-    chain.with(AreaStartingPosition::new(XStart::Left, YStart::Top));
-    chain.with(VoronoiSpawning::new());
-    chain.with(DistantExit::new());
-    chain.with(CaveDecorator::new());
-    chain.with(PrefabBuilder::sectional(
-        super::prefab_builder::prefab_sections::ORC_CAMP,
-    ));
-
-    chain
-}
-
-pub fn limestone_transition_builder(
-    new_depth: i32,
-    width: i32,
-    height: i32,
-) -> BuilderChain {
-    let mut chain = BuilderChain::new(new_depth, width, height, "Dwarf Fort - Upper Reaches");
-
-    chain.start_with(CellularAutomataBuilder::new());
-    chain.with(AreaStartingPosition::new(XStart::Center, YStart::Center));
-    chain.with(CullUnreachable::new());
-    chain.with(AreaStartingPosition::new(XStart::Left, YStart::Center));
-    chain.with(VoronoiSpawning::new());
-    chain.with(CaveDecorator::new());
-    chain.with(CaveTransition::new());
-    chain.with(AreaStartingPosition::new(XStart::Left, YStart::Center));
-    chain.with(CullUnreachable::new());
-    chain.with(AreaEndingPosition::new(XEnd::Right, YEnd::Center));
-
-    chain
-}
-
 pub struct CaveDecorator {}
 
 impl MetaMapBuilder for CaveDecorator {
@@ -180,13 +108,13 @@ impl CaveTransition {
         // Keep Voronoi spawn data from the left half of the map
         let w = build_data.map.width;
         build_data.spawn_list.retain(|s| {
-            let x = s.0 as i32 / w;
+            let x = s.0 as i32 % w;
             x < w / 2
         });
 
         // Keep room spawn data from the right half of the map
         for s in builder.build_data.spawn_list.iter() {
-            let x = s.0 as i32 / w;
+            let x = s.0 as i32 % w;
             if x > w / 2 {
                 build_data.spawn_list.push(s.clone());
             }
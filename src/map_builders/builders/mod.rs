@@ -3,14 +3,14 @@ use crate::map_builders::*;
 mod bsp_dungeon;
 mod bsp_interior;
 mod cellular_automata;
-mod dark_elves;
 mod dla;
 mod drunkard;
 mod dwarf_fort;
+mod fallback;
+mod file_map;
 mod forest;
 mod limestone_cavern;
 mod maze;
-mod mushroom_forest;
 mod simple_map;
 mod voronoi;
 mod waveform_collapse;
@@ -23,15 +23,15 @@ pub use bsp_interior::BspInteriorBuilder;
 pub use cellular_automata::CellularAutomataBuilder;
 pub use dla::DLABuilder;
 pub use drunkard::DrunkardsWalkBuilder;
+pub use fallback::FallbackBuilder;
+pub use file_map::FileMapBuilder;
 pub use maze::MazeBuilder;
 pub use prefab_builder::PrefabBuilder;
 pub use simple_map::SimpleMapBuilder;
 pub use voronoi::VoronoiCellBuilder;
-pub use waveform_collapse::WaveformCollapseBuilder;
+pub use waveform_collapse::{WaveformCollapseBuilder, WfcSample};
 
-pub use dark_elves::*;
 pub use dwarf_fort::*;
 pub use forest::*;
 pub use limestone_cavern::*;
-pub use mushroom_forest::*;
 pub use town::*;
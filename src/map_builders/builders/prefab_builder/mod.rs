@@ -1,20 +1,15 @@
-use std::collections::HashSet;
-
-#[rustfmt::skip]
-pub mod prefab_levels;
-#[rustfmt::skip]
-pub mod prefab_rooms;
-#[rustfmt::skip]
-pub mod prefab_sections;
+use std::collections::{BTreeMap, HashSet};
 
 use super::{BuilderMap, InitialMapBuilder, MetaMapBuilder, Position, TileType};
+use crate::raws::structs::{HorizontalPlacement, Prefab, VerticalPlacement};
+use crate::raws::{self, RAWS};
 
-#[derive(PartialEq, Copy, Clone)]
+#[derive(Clone)]
 #[allow(dead_code)]
 pub enum PrefabMode {
-    RexLevel { template: &'static str },
-    Constant { level: prefab_levels::PrefabLevel },
-    Sectional { section: prefab_sections::PrefabSection },
+    RexLevel { template: String },
+    Constant { level: Prefab },
+    Sectional { section: Prefab },
     RoomVaults,
 }
 
@@ -41,26 +36,29 @@ impl PrefabBuilder {
     }
 
     #[allow(dead_code)]
-    pub fn rex_level(template: &'static str) -> Box<PrefabBuilder> {
+    pub fn rex_level(template: &str) -> Box<PrefabBuilder> {
         Box::new(PrefabBuilder {
-            mode: PrefabMode::RexLevel { template },
+            mode: PrefabMode::RexLevel {
+                template: template.to_string(),
+            },
         })
     }
 
     #[allow(dead_code)]
-    pub fn constant(level: prefab_levels::PrefabLevel) -> Box<PrefabBuilder> {
+    pub fn constant(level: Prefab) -> Box<PrefabBuilder> {
         Box::new(PrefabBuilder {
             mode: PrefabMode::Constant { level },
         })
     }
 
     #[allow(dead_code)]
-    pub fn sectional(section: prefab_sections::PrefabSection) -> Box<PrefabBuilder> {
+    pub fn sectional(section: Prefab) -> Box<PrefabBuilder> {
         Box::new(PrefabBuilder {
             mode: PrefabMode::Sectional { section },
         })
     }
 
+    /// Drops a few vaults from the prefab library into open floor, if any suit the level.
     #[allow(dead_code)]
     pub fn vaults() -> Box<PrefabBuilder> {
         Box::new(PrefabBuilder {
@@ -69,8 +67,8 @@ impl PrefabBuilder {
     }
 
     fn build(&mut self, build_data: &mut BuilderMap) {
-        match self.mode {
-            PrefabMode::RexLevel { template } => self.load_rex_map(template, build_data),
+        match self.mode.clone() {
+            PrefabMode::RexLevel { template } => self.load_rex_map(&template, build_data),
             PrefabMode::Constant { level } => self.load_ascii_map(&level, build_data),
             PrefabMode::Sectional { section } => self.apply_sectional(&section, build_data),
             PrefabMode::RoomVaults => self.apply_room_vaults(build_data),
@@ -78,55 +76,29 @@ impl PrefabBuilder {
         build_data.take_snapshot();
     }
 
-    fn char_to_map(&mut self, ch: char, idx: usize, build_data: &mut BuilderMap) {
+    /// Puts down whatever a glyph stands for in the legend. Glyphs the legend leaves out are tiles, by their `ascii`
+    /// glyph, except for `@`, where the player starts, and a blank, which is floor.
+    fn char_to_map(&mut self, ch: char, legend: &BTreeMap<char, String>, idx: usize, build_data: &mut BuilderMap) {
+        if let Some(entry) = legend.get(&ch) {
+            let (spawn, tile) = raws::legend_entry(entry);
+            build_data.map.tiles[idx] = tile.and_then(TileType::named).unwrap_or(TileType::Floor);
+            if let Some(name) = spawn {
+                build_data.spawn_list.push((idx, name.to_string()));
+            }
+            return;
+        }
+
         match ch {
             ' ' => build_data.map.tiles[idx] = TileType::Floor,
-            '#' => build_data.map.tiles[idx] = TileType::Wall,
             '@' => {
                 let x = idx as i32 % build_data.map.width;
                 let y = idx as i32 / build_data.map.width;
                 build_data.map.tiles[idx] = TileType::Floor;
-                build_data.starting_position = Some(Position {
-                    x: x as i32,
-                    y: y as i32,
-                });
-            },
-            '>' => build_data.map.tiles[idx] = TileType::DownStairs,
-            'e' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Dark Elf".to_string()));
-            },
-            'g' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Goblin".to_string()));
-            },
-            'o' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Orc".to_string()));
-            },
-            '^' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Bear Trap".to_string()));
+                build_data.starting_position = Some(Position { x, y });
             },
-            '%' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Rations".to_string()));
-            },
-            '!' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Health Potion".to_string()));
-            },
-            '≈' => build_data.map.tiles[idx] = TileType::DeepWater,
-            'O' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Orc Leader".to_string()));
-            },
-            '☼' => {
-                build_data.map.tiles[idx] = TileType::Floor;
-                build_data.spawn_list.push((idx, "Watch Fire".to_string()));
-            },
-            _ => {
-                rltk::console::log(format!("Unknown glyph loading map: {}", (ch as u8) as char));
+            _ => match TileType::from_ascii(ch) {
+                Some(tt) => build_data.map.tiles[idx] = tt,
+                None => rltk::console::log(format!("Unknown glyph loading map: {}", ch)),
             },
         }
     }
@@ -135,6 +107,20 @@ impl PrefabBuilder {
     fn load_rex_map(&mut self, path: &str, build_data: &mut BuilderMap) {
         let xp_file = rltk::rex::XpFile::from_resource(path).unwrap();
 
+        // The maps in `resources` mark out who lives there with letters and symbols, rather than a legend
+        let legend: BTreeMap<char, String> = [
+            ('e', "Dark Elf"),
+            ('g', "Goblin"),
+            ('o', "Orc"),
+            ('O', "Orc Leader"),
+            ('^', "Bear Trap"),
+            ('%', "Rations"),
+            ('!', "Health Potion"),
+        ]
+        .iter()
+        .map(|(ch, name)| (*ch, name.to_string()))
+        .collect();
+
         for layer in &xp_file.layers {
             for y in 0..layer.height {
                 for x in 0..layer.width {
@@ -142,37 +128,21 @@ impl PrefabBuilder {
                     if x < build_data.map.width as usize && y < build_data.map.height as usize {
                         let idx = build_data.map.xy_idx(x as i32, y as i32);
                         // We're doing some nasty casting to make it easier to type things like '#' in the match
-                        self.char_to_map(cell.ch as u8 as char, idx, build_data);
+                        self.char_to_map(cell.ch as u8 as char, &legend, idx, build_data);
                     }
                 }
             }
         }
     }
 
-    fn read_ascii_to_vec(template: &str) -> Vec<char> {
-        let mut string_vec: Vec<char> = template.chars().filter(|a| *a != '\r' && *a != '\n').collect();
-        for c in string_vec.iter_mut() {
-            if *c as u8 == 160u8 {
-                *c = ' ';
-            }
-        }
-        string_vec
-    }
-
     #[allow(dead_code)]
-    fn load_ascii_map(&mut self, level: &prefab_levels::PrefabLevel, build_data: &mut BuilderMap) {
-        let string_vec = PrefabBuilder::read_ascii_to_vec(level.template);
-
-        let mut i = 0;
-        for ty in 0..level.height {
-            for tx in 0..level.width {
+    fn load_ascii_map(&mut self, level: &Prefab, build_data: &mut BuilderMap) {
+        for ty in 0..level.height() {
+            for tx in 0..level.width() {
                 if tx < build_data.map.width as usize && ty < build_data.map.height as usize {
                     let idx = build_data.map.xy_idx(tx as i32, ty as i32);
-                    if i < string_vec.len() {
-                        self.char_to_map(string_vec[i], idx, build_data);
-                    }
+                    self.char_to_map(level.glyph(tx, ty), &level.legend, idx, build_data);
                 }
-                i += 1;
             }
         }
     }
@@ -189,54 +159,52 @@ impl PrefabBuilder {
     }
 
     #[allow(dead_code)]
-    fn apply_sectional(&mut self, section: &prefab_sections::PrefabSection, build_data: &mut BuilderMap) {
-        use prefab_sections::*;
-
-        let string_vec = PrefabBuilder::read_ascii_to_vec(section.template);
+    fn apply_sectional(&mut self, section: &Prefab, build_data: &mut BuilderMap) {
+        // Turn or flip it, if it may be, as long as it still fits on the map
+        let layouts: Vec<Prefab> = section
+            .orientations()
+            .iter()
+            .map(|o| section.oriented(*o))
+            .filter(|s| s.width() as i32 <= build_data.map.width && s.height() as i32 <= build_data.map.height)
+            .collect();
+        let section = match layouts.len() {
+            0 => section,
+            n => &layouts[(crate::rng::roll_dice(1, n as i32) - 1) as usize],
+        };
+        let (width, height) = (section.width(), section.height());
 
         // Place the new section
         let chunk_x = match section.placement.0 {
             HorizontalPlacement::Left => 0,
-            HorizontalPlacement::Center => (build_data.map.width / 2) - (section.width as i32 / 2),
-            HorizontalPlacement::Right => (build_data.map.width - 1) - section.width as i32,
+            HorizontalPlacement::Center => (build_data.map.width / 2) - (width as i32 / 2),
+            HorizontalPlacement::Right => (build_data.map.width - 1) - width as i32,
         };
 
         let chunk_y = match section.placement.1 {
             VerticalPlacement::Top => 0,
-            VerticalPlacement::Center => (build_data.map.height / 2) - (section.height as i32 / 2),
-            VerticalPlacement::Bottom => (build_data.map.height - 1) - section.height as i32,
+            VerticalPlacement::Center => (build_data.map.height / 2) - (height as i32 / 2),
+            VerticalPlacement::Bottom => (build_data.map.height - 1) - height as i32,
         };
 
         // Build the map
         self.apply_previous_iteration(
-            |x, y| {
-                x < chunk_x
-                    || x > (chunk_x + section.width as i32)
-                    || y < chunk_y
-                    || y > (chunk_y + section.height as i32)
-            },
+            |x, y| x < chunk_x || x > (chunk_x + width as i32) || y < chunk_y || y > (chunk_y + height as i32),
             build_data,
         );
 
-        let mut i = 0;
-        for ty in 0..section.height {
-            for tx in 0..section.width {
+        for ty in 0..height {
+            for tx in 0..width {
                 if tx > 0 && tx < build_data.map.width as usize - 1 && ty < build_data.map.height as usize - 1 && ty > 0
                 {
                     let idx = build_data.map.xy_idx(tx as i32 + chunk_x, ty as i32 + chunk_y);
-                    if i < string_vec.len() {
-                        self.char_to_map(string_vec[i], idx, build_data);
-                    }
+                    self.char_to_map(section.glyph(tx, ty), &section.legend, idx, build_data);
                 }
-                i += 1;
             }
         }
         build_data.take_snapshot();
     }
 
     fn apply_room_vaults(&mut self, build_data: &mut BuilderMap) {
-        use prefab_rooms::*;
-
         // Apply the previous builder, and keep all entities it spawns (for now)
         self.apply_previous_iteration(|_x, _y| true, build_data);
 
@@ -246,15 +214,8 @@ impl PrefabBuilder {
             return;
         }
 
-        // Note that this is a place-holder and will be moved out of this function
-        let master_vault_list = vec![TOTALLY_NOT_A_TRAP, CHECKERBOARD, SILLY_SMILE];
-
-        // Filter the vault list down to ones that are applicable to the current depth
-        let mut possible_vaults: Vec<&PrefabRoom> = master_vault_list
-            .iter()
-            .filter(|v| build_data.map.depth >= v.first_depth && build_data.map.depth <= v.last_depth)
-            .collect();
-
+        // Only the vaults that can be found this deep
+        let mut possible_vaults = raws::get_vaults_for_depth(&RAWS.lock().unwrap(), build_data.map.depth);
         if possible_vaults.is_empty() {
             return;
         } // Bail out if there's nothing to build
@@ -263,50 +224,32 @@ impl PrefabBuilder {
         let mut used_tiles: HashSet<usize> = HashSet::new();
 
         for _i in 0..n_vaults {
-            let vault_index = if possible_vaults.len() == 1 {
-                0
-            } else {
-                (crate::rng::roll_dice(1, possible_vaults.len() as i32) - 1) as usize
-            };
-            let vault = possible_vaults[vault_index];
-
-            // We'll make a list of places in which the vault could fit
-            let mut vault_positions: Vec<Position> = Vec::new();
-
-            let mut idx = 0usize;
-            loop {
-                let x = (idx % build_data.map.width as usize) as i32;
-                let y = (idx / build_data.map.width as usize) as i32;
-
-                // Check that we won't overflow the map
-                if x > 1
-                    && (x + vault.width as i32) < build_data.map.width - 2
-                    && y > 1
-                    && (y + vault.height as i32) < build_data.map.height - 2
-                {
-                    let mut possible = true;
-                    for ty in 0..vault.height as i32 {
-                        for tx in 0..vault.width as i32 {
-                            let idx = build_data.map.xy_idx(tx + x, ty + y);
-                            if build_data.map.tiles[idx] != TileType::Floor {
-                                possible = false;
-                            }
-                            if used_tiles.contains(&idx) {
-                                possible = false;
-                            }
-                        }
-                    }
+            if possible_vaults.is_empty() {
+                break;
+            }
 
-                    if possible {
-                        vault_positions.push(Position { x, y });
-                        break;
+            // Rarer vaults are less likely to be picked
+            let total: i32 = possible_vaults.iter().map(|v| v.rarity.weight()).sum();
+            let mut roll = crate::rng::roll_dice(1, total);
+            let mut vault_index = 0;
+            while roll > possible_vaults[vault_index].rarity.weight() {
+                roll -= possible_vaults[vault_index].rarity.weight();
+                vault_index += 1;
+            }
+            let vault = &possible_vaults[vault_index];
+
+            // We'll make a list of places in which the vault could fit, whichever way round it is allowed to go
+            let layouts: Vec<Prefab> = vault.orientations().iter().map(|o| vault.oriented(*o)).collect();
+            let mut vault_positions: Vec<(Position, usize)> = Vec::new();
+
+            for (layout, oriented) in layouts.iter().enumerate() {
+                for idx in 0..build_data.map.tiles.len() {
+                    let x = (idx % build_data.map.width as usize) as i32;
+                    let y = (idx / build_data.map.width as usize) as i32;
+                    if PrefabBuilder::vault_fits(oriented, x, y, &used_tiles, build_data) {
+                        vault_positions.push((Position { x, y }, layout));
                     }
                 }
-
-                idx += 1;
-                if idx >= build_data.map.tiles.len() - 1 {
-                    break;
-                }
             }
 
             if !vault_positions.is_empty() {
@@ -315,36 +258,69 @@ impl PrefabBuilder {
                 } else {
                     (crate::rng::roll_dice(1, vault_positions.len() as i32) - 1) as usize
                 };
-                let pos = &vault_positions[pos_idx];
+                let (pos, layout) = &vault_positions[pos_idx];
+                let oriented = &layouts[*layout];
+                let (vault_width, vault_height) = (oriented.width() as i32, oriented.height() as i32);
 
                 let chunk_x = pos.x;
                 let chunk_y = pos.y;
 
                 let width = build_data.map.width; // The borrow checker really doesn't like it
-                let height = build_data.map.height; // when we access `self` inside the `retain`
                 build_data.spawn_list.retain(|e| {
                     let idx = e.0 as i32;
                     let x = idx % width;
-                    let y = idx / height;
-                    x < chunk_x || x > chunk_x + vault.width as i32 || y < chunk_y || y > chunk_y + vault.height as i32
+                    let y = idx / width;
+                    x < chunk_x || x > chunk_x + vault_width || y < chunk_y || y > chunk_y + vault_height
                 });
 
-                let string_vec = PrefabBuilder::read_ascii_to_vec(vault.template);
-                let mut i = 0;
-                for ty in 0..vault.height {
-                    for tx in 0..vault.width {
+                for ty in 0..oriented.height() {
+                    for tx in 0..oriented.width() {
                         let idx = build_data.map.xy_idx(tx as i32 + chunk_x, ty as i32 + chunk_y);
-                        if i < string_vec.len() {
-                            self.char_to_map(string_vec[i], idx, build_data);
-                        }
+                        self.char_to_map(oriented.glyph(tx, ty), &oriented.legend, idx, build_data);
                         used_tiles.insert(idx);
-                        i += 1;
                     }
                 }
                 build_data.take_snapshot();
+            }
 
+            // One that didn't fit won't fit next time either, and a unique one can't be used again
+            if vault_positions.is_empty() || possible_vaults[vault_index].unique {
                 possible_vaults.remove(vault_index);
             }
         }
     }
+
+    /// Whether a vault can go with its top left corner here: on open floor that no other vault has taken, clear of
+    /// the edges of the map and of where the player starts, and somewhere drawn in a theme it belongs in.
+    fn vault_fits(vault: &Prefab, x: i32, y: i32, used_tiles: &HashSet<usize>, build_data: &BuilderMap) -> bool {
+        let (vault_width, vault_height) = (vault.width() as i32, vault.height() as i32);
+
+        // Check that we won't overflow the map
+        if x <= 1
+            || x + vault_width >= build_data.map.width - 2
+            || y <= 1
+            || y + vault_height >= build_data.map.height - 2
+        {
+            return false;
+        }
+
+        if !vault.suits(crate::map::tile_theme(build_data.map.xy_idx(x, y), &build_data.map)) {
+            return false;
+        }
+
+        for ty in 0..vault_height {
+            for tx in 0..vault_width {
+                let idx = build_data.map.xy_idx(tx + x, ty + y);
+                if build_data.map.tiles[idx] != TileType::Floor || used_tiles.contains(&idx) {
+                    return false;
+                }
+            }
+        }
+
+        // Don't drop the player into the middle of it
+        match &build_data.starting_position {
+            Some(start) => start.x < x || start.x >= x + vault_width || start.y < y || start.y >= y + vault_height,
+            None => true,
+        }
+    }
 }
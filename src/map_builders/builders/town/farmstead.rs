@@ -0,0 +1,101 @@
+use std::collections::HashSet;
+
+use super::{BuilderMap, Position, TileType, TownBuilder};
+
+impl TownBuilder {
+    /// A farm out in the countryside: a road running east to west, a farmhouse and a barn or two beside it, and
+    /// fields of crops round about. There's a cellar under the first barn, for anyone wanting a way down.
+    pub fn build_farmstead(&mut self, build_data: &mut BuilderMap) {
+        self.grass_layer(build_data);
+        let road_y = self.country_road(build_data);
+
+        // Leave the verges of the road clear
+        let mut available_building_tiles: HashSet<usize> = HashSet::new();
+        for y in 3..build_data.height - 3 {
+            for x in 3..build_data.width - 3 {
+                if (y - 3..=y + 3).all(|near| build_data.map.tiles[build_data.map.xy_idx(x, near)] != TileType::Road) {
+                    available_building_tiles.insert(build_data.map.xy_idx(x, y));
+                }
+            }
+        }
+
+        let mut buildings = self.buildings(
+            build_data,
+            &mut available_building_tiles,
+            crate::rng::roll_dice(1, 2) + 1,
+            2,
+        );
+        let doors = self.add_doors(build_data, &mut buildings, road_y);
+        self.add_paths(build_data, &doors);
+
+        // The largest building is the farmhouse, and whatever's left are barns
+        let mut by_size: Vec<&(i32, i32, i32, i32)> = buildings.iter().collect();
+        by_size.sort_by_key(|b| std::cmp::Reverse(b.2 * b.3));
+        self.build_hovel(by_size[0], build_data);
+        for barn in by_size.iter().skip(1) {
+            self.build_abandoned_house(barn, build_data);
+        }
+        if let Some(barn) = by_size.get(1) {
+            let cellar = build_data.map.xy_idx(barn.0 + barn.2 / 2, barn.1 + barn.3 / 2);
+            build_data.map.tiles[cellar] = TileType::DownStairs;
+            build_data.spawn_list.retain(|(idx, _)| *idx != cellar);
+        }
+
+        self.fields(build_data, &mut available_building_tiles);
+
+        build_data.starting_position = Some(Position { x: 1, y: road_y });
+        build_data.take_snapshot();
+    }
+
+    /// A road across the map from west to east, wandering a little on the way. Returns the row it starts on.
+    fn country_road(&mut self, build_data: &mut BuilderMap) -> i32 {
+        let mid = build_data.height / 2;
+        let mut n = (crate::rng::roll_dice(1, 65535) as f32) / 65535f32;
+        let mut start = mid;
+
+        for x in 0..build_data.width {
+            let y = mid + (f32::sin(n) * 4.0) as i32;
+            if x == 1 {
+                start = y;
+            }
+            for road_y in y..y + 2 {
+                let idx = build_data.map.xy_idx(x, road_y);
+                build_data.map.tiles[idx] = TileType::Road;
+            }
+            n += 0.08;
+        }
+        build_data.take_snapshot();
+
+        start
+    }
+
+    /// Ploughs fields of crops wherever there is room left, with a farmhand or two working them.
+    fn fields(&mut self, build_data: &mut BuilderMap, available_building_tiles: &mut HashSet<usize>) {
+        let crops = TileType::named("Crops").unwrap_or(TileType::Grass);
+
+        for _i in 0..crate::rng::roll_dice(1, 3) + 3 {
+            let fw = crate::rng::roll_dice(1, 10) + 6;
+            let fh = crate::rng::roll_dice(1, 4) + 3;
+            let fx = crate::rng::roll_dice(1, build_data.width - fw - 4) + 1;
+            let fy = crate::rng::roll_dice(1, build_data.height - fh - 4) + 1;
+
+            let tiles: Vec<usize> = (fy..fy + fh)
+                .flat_map(|y| (fx..fx + fw).map(move |x| (x, y)))
+                .map(|(x, y)| build_data.map.xy_idx(x, y))
+                .collect();
+            if !tiles.iter().all(|idx| available_building_tiles.contains(idx)) {
+                continue;
+            }
+
+            for idx in tiles.iter() {
+                build_data.map.tiles[*idx] = crops;
+                available_building_tiles.remove(idx);
+            }
+            if crate::rng::roll_dice(1, 2) == 1 {
+                let idx = tiles[(crate::rng::roll_dice(1, tiles.len() as i32) - 1) as usize];
+                build_data.spawn_list.push((idx, "Peasant".to_string()));
+            }
+            build_data.take_snapshot();
+        }
+    }
+}
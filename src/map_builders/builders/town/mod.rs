@@ -1,21 +1,25 @@
 use std::collections::HashSet;
 
-use super::{BuilderChain, BuilderMap, InitialMapBuilder, Position, TileType};
+use super::{BuilderMap, InitialMapBuilder, Position, TileType};
 
+mod farmstead;
 mod town_buildings;
 mod town_people;
 
-pub fn town_builder(new_depth: i32, width: i32, height: i32) -> BuilderChain {
-    let mut chain = BuilderChain::new(new_depth, width, height, "The Town of Bracketon");
-    chain.start_with(TownBuilder::new());
-    chain
+pub struct TownBuilder {
+    /// Build a lone farm out in the countryside, rather than the town itself.
+    farmstead: bool,
 }
 
-pub struct TownBuilder {}
-
 impl InitialMapBuilder for TownBuilder {
     #[allow(dead_code)]
-    fn build_map(&mut self, build_data: &mut BuilderMap) { self.build_rooms(build_data); }
+    fn build_map(&mut self, build_data: &mut BuilderMap) {
+        if self.farmstead {
+            self.build_farmstead(build_data);
+        } else {
+            self.build_rooms(build_data);
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -32,7 +36,9 @@ enum BuildingTag {
 }
 
 impl TownBuilder {
-    pub fn new() -> Box<TownBuilder> { Box::new(TownBuilder {}) }
+    pub fn new() -> Box<TownBuilder> { Box::new(TownBuilder { farmstead: false }) }
+
+    pub fn farmstead() -> Box<TownBuilder> { Box::new(TownBuilder { farmstead: true }) }
 
     pub fn build_rooms(&mut self, build_data: &mut BuilderMap) {
         self.grass_layer(build_data);
@@ -40,7 +46,7 @@ impl TownBuilder {
 
         // Create the town!
         let (mut available_building_tiles, wall_gap_y) = self.town_walls(build_data);
-        let mut buildings = self.buildings(build_data, &mut available_building_tiles);
+        let mut buildings = self.buildings(build_data, &mut available_building_tiles, 12, 30);
         let doors = self.add_doors(build_data, &mut buildings, wall_gap_y);
 
         // Connect gravel to doors!
@@ -151,17 +157,20 @@ impl TownBuilder {
         (available_building_tiles, wall_gap_y)
     }
 
+    /// Puts up `count` buildings on the available tiles east of `west`.
     fn buildings(
         &mut self,
 
         build_data: &mut BuilderMap,
         available_building_tiles: &mut HashSet<usize>,
+        count: i32,
+        west: i32,
     ) -> Vec<(i32, i32, i32, i32)> {
         let mut buildings: Vec<(i32, i32, i32, i32)> = Vec::new();
         let mut n_buildings = 0;
 
-        while n_buildings < 12 {
-            let bx = crate::rng::roll_dice(1, build_data.map.width - 32) + 30;
+        while n_buildings < count {
+            let bx = crate::rng::roll_dice(1, build_data.map.width - west - 2) + west;
             let by = crate::rng::roll_dice(1, build_data.map.height) - 2;
             let bw = crate::rng::roll_dice(1, 8) + 4;
             let bh = crate::rng::roll_dice(1, 8) + 4;
@@ -203,7 +212,7 @@ impl TownBuilder {
         // Outline buildings
         let mut mapclone = build_data.map.clone();
         for y in 2..build_data.height - 2 {
-            for x in 32..build_data.width - 2 {
+            for x in west + 2..build_data.width - 2 {
                 let idx = build_data.map.xy_idx(x, y);
                 if build_data.map.tiles[idx] == TileType::WoodFloor {
                     let mut neighbors = 0;
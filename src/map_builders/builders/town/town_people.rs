@@ -18,7 +18,10 @@ impl TownBuilder {
     }
 
     pub fn spawn_townsfolk(&mut self, build_data: &mut BuilderMap, available_building_tiles: &mut HashSet<usize>) {
-        for idx in available_building_tiles.iter() {
+        // In order, so the same seed always makes the same town
+        let mut tiles: Vec<usize> = available_building_tiles.iter().copied().collect();
+        tiles.sort_unstable();
+        for idx in tiles.iter() {
             if crate::rng::roll_dice(1, 10) == 1 {
                 let roll = crate::rng::roll_dice(1, 4);
                 match roll {
@@ -6,6 +6,9 @@ pub struct MapChunk {
     pub exits: [Vec<bool>; 4],
     pub has_exits: bool,
     pub compatible_with: [Vec<usize>; 4],
+    /// How often the pattern turned up in the sample; the solver favours common ones. Patterns with no weight are
+    /// only ever placed as fixed cells.
+    pub weight: i32,
 }
 
 pub fn tile_idx_in_chunk(chunk_size: i32, x: i32, y: i32) -> usize { ((y * chunk_size) + x) as usize }
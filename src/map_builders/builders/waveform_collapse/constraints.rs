@@ -1,13 +1,16 @@
 use super::{tile_idx_in_chunk, Map, MapChunk, TileType};
-use std::collections::HashSet;
+use std::collections::HashMap;
 
-pub fn build_patterns(map: &Map, chunk_size: i32, include_flipping: bool, dedupe: bool) -> Vec<Vec<TileType>> {
+/// Cuts the map up into chunk-sized patterns, each with the number of times it was seen.
+pub fn build_patterns(map: &Map, chunk_size: i32, include_flipping: bool, dedupe: bool) -> Vec<(Vec<TileType>, i32)> {
     let chunks_x = map.width / chunk_size;
     let chunks_y = map.height / chunk_size;
     let mut patterns = Vec::new();
 
     for cy in 0..chunks_y {
         for cx in 0..chunks_x {
+            let first = patterns.len();
+
             // Normal orientation
             let mut pattern: Vec<TileType> = Vec::new();
             let start_x = cx * chunk_size;
@@ -54,18 +57,39 @@ pub fn build_patterns(map: &Map, chunk_size: i32, include_flipping: bool, dedupe
                 }
                 patterns.push(pattern);
             }
+
+            // A symmetrical chunk flips into copies of itself, which shouldn't add to its weight
+            if dedupe {
+                for variant in patterns.split_off(first) {
+                    if !patterns[first..].contains(&variant) {
+                        patterns.push(variant);
+                    }
+                }
+            }
         }
     }
 
-    // Dedupe
-    if dedupe {
-        rltk::console::log(format!("Pre de-duplication, there are {} patterns", patterns.len()));
-        let set: HashSet<Vec<TileType>> = patterns.drain(..).collect(); // dedup
-        patterns.extend(set.into_iter());
-        rltk::console::log(format!("There are {} patterns", patterns.len()));
+    // Dedupe, counting the copies so common patterns can be favoured. Kept in the order first seen, so the same
+    // seed always gives the same level.
+    if !dedupe {
+        return patterns.into_iter().map(|pattern| (pattern, 1)).collect();
+    }
+
+    rltk::console::log(format!("Pre de-duplication, there are {} patterns", patterns.len()));
+    let mut counted: Vec<(Vec<TileType>, i32)> = Vec::new();
+    let mut seen: HashMap<Vec<TileType>, usize> = HashMap::new();
+    for pattern in patterns {
+        match seen.get(&pattern) {
+            Some(i) => counted[*i].1 += 1,
+            None => {
+                seen.insert(pattern.clone(), counted.len());
+                counted.push((pattern, 1));
+            },
+        }
     }
+    rltk::console::log(format!("There are {} patterns", counted.len()));
 
-    patterns
+    counted
 }
 
 pub fn render_pattern_to_map(map: &mut Map, chunk: &MapChunk, chunk_size: i32, start_x: i32, start_y: i32) {
@@ -105,15 +129,16 @@ pub fn render_pattern_to_map(map: &mut Map, chunk: &MapChunk, chunk_size: i32, s
     }
 }
 
-pub fn patterns_to_constraints(patterns: Vec<Vec<TileType>>, chunk_size: i32) -> Vec<MapChunk> {
+pub fn patterns_to_constraints(patterns: Vec<(Vec<TileType>, i32)>, chunk_size: i32) -> Vec<MapChunk> {
     // Move into the new constraints object
     let mut constraints: Vec<MapChunk> = Vec::new();
-    for p in patterns {
+    for (p, weight) in patterns {
         let mut new_chunk = MapChunk {
             pattern: p,
             exits: [Vec::new(), Vec::new(), Vec::new(), Vec::new()],
             has_exits: true,
             compatible_with: [Vec::new(), Vec::new(), Vec::new(), Vec::new()],
+            weight,
         };
         for exit in new_chunk.exits.iter_mut() {
             for _i in 0..chunk_size {
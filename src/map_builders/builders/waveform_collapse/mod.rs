@@ -7,10 +7,29 @@ use constraints::*;
 mod solver;
 use solver::*;
 
-use super::{BuilderMap, Map, MetaMapBuilder, TileType};
+use crate::raws::structs::Prefab;
+use super::{BuilderChain, BuilderMap, Map, MetaMapBuilder, PrefabBuilder, TileType};
+
+/// How many times the solver is set going before the builder gives up and leaves the map as it found it.
+const MAX_ATTEMPTS: i32 = 10;
+
+/// Where the builder takes the patterns it tiles the level with.
+#[derive(Clone)]
+pub enum WfcSample {
+    /// The map built so far, as it stands.
+    CurrentMap,
+    /// A whole level from the prefab library.
+    Prefab(Prefab),
+    /// A REX Paint `.xp` file, by resource path.
+    Rex(String),
+}
 
 /// Provides a map builder using the Wave Function Collapse algorithm.
-pub struct WaveformCollapseBuilder {}
+pub struct WaveformCollapseBuilder {
+    sample: WfcSample,
+    /// Chunks of the map holding any of these tiles are left as they are, and the rest is made to fit around them.
+    keep: Vec<TileType>,
+}
 
 impl MetaMapBuilder for WaveformCollapseBuilder {
     fn build_map(&mut self, build_data: &mut BuilderMap) { self.build(build_data); }
@@ -19,33 +38,116 @@ impl MetaMapBuilder for WaveformCollapseBuilder {
 impl WaveformCollapseBuilder {
     /// Constructor for waveform collapse.
     #[allow(dead_code)]
-    pub fn new() -> Box<WaveformCollapseBuilder> { Box::new(WaveformCollapseBuilder {}) }
+    pub fn new() -> Box<WaveformCollapseBuilder> { WaveformCollapseBuilder::sampling(WfcSample::CurrentMap) }
+
+    /// Tiles the level with patterns from somewhere other than the map built so far.
+    pub fn sampling(sample: WfcSample) -> Box<WaveformCollapseBuilder> {
+        Box::new(WaveformCollapseBuilder {
+            sample,
+            keep: vec![TileType::DownStairs, TileType::UpStairs],
+        })
+    }
+
+    /// Leaves the chunks holding any of these tiles in place, instead of just the stairs.
+    pub fn keeping(mut self: Box<Self>, keep: Vec<TileType>) -> Box<WaveformCollapseBuilder> {
+        self.keep = keep;
+        self
+    }
 
     fn build(&mut self, build_data: &mut BuilderMap) {
         const CHUNK_SIZE: i32 = 8;
         build_data.take_snapshot();
 
-        let patterns = build_patterns(&build_data.map, CHUNK_SIZE, true, true);
+        let canvas = build_data.map.clone();
+        let mut patterns = build_patterns(&self.sample_map(build_data), CHUNK_SIZE, true, true);
+
+        // The chunks to keep join the patterns without any weight, so they are never picked anywhere else
+        let fixed = self.fixed_chunks(&canvas, CHUNK_SIZE);
+        let mut pinned = Vec::new();
+        for (chunk_x, chunk_y, pattern) in fixed {
+            let constraint = match patterns.iter().position(|(p, _)| *p == pattern) {
+                Some(i) => i,
+                None => {
+                    patterns.push((pattern, 0));
+                    patterns.len() - 1
+                },
+            };
+            pinned.push((chunk_x, chunk_y, constraint));
+        }
+
         let constraints = patterns_to_constraints(patterns, CHUNK_SIZE);
         self.render_tile_gallery(&constraints, CHUNK_SIZE, build_data);
 
-        build_data.map = Map::new(
-            build_data.map.depth,
-            build_data.map.width,
-            build_data.map.height,
-            &build_data.map.name,
-        );
-        loop {
+        for _ in 0..MAX_ATTEMPTS {
+            // The tile gallery leaves a blank map behind, which needs to know where it is again
+            build_data.map = Map::new(canvas.depth, canvas.width, canvas.height, &canvas.name);
+            build_data.map.branch = canvas.branch;
+
             let mut solver = Solver::new(constraints.clone(), CHUNK_SIZE, &build_data.map);
+            solver.cancel_with(build_data.cancel.clone());
+            for (chunk_x, chunk_y, constraint) in pinned.iter() {
+                solver.fix(*chunk_x, *chunk_y, *constraint, &mut build_data.map);
+            }
+
             while !solver.iteration(&mut build_data.map) {
                 build_data.take_snapshot();
             }
             build_data.take_snapshot();
-            if solver.possible {
-                break;
-            } // If it has hit an impossible condition, try again
+
+            match solver.state {
+                SolverState::Solved => {
+                    // Only what was spawned in the chunks that stayed put is still where it should be
+                    let w = canvas.width;
+                    build_data.spawn_list.retain(|(idx, _)| {
+                        let (chunk_x, chunk_y) = ((*idx as i32 % w) / CHUNK_SIZE, (*idx as i32 / w) / CHUNK_SIZE);
+                        pinned
+                            .iter()
+                            .any(|(x, y, _)| *x as i32 == chunk_x && *y as i32 == chunk_y)
+                    });
+                    return;
+                },
+                SolverState::Cancelled => break,
+                _ => {}, // If it has hit an impossible condition, try again
+            }
+        }
+
+        rltk::console::log("Wave function collapse gave up, keeping the map it started with");
+        build_data.map = canvas;
+        build_data.take_snapshot();
+    }
+
+    /// The map the patterns are cut from.
+    fn sample_map(&self, build_data: &BuilderMap) -> Map {
+        let prefab = match &self.sample {
+            WfcSample::CurrentMap => return build_data.map.clone(),
+            WfcSample::Prefab(level) => PrefabBuilder::constant(level.clone()),
+            WfcSample::Rex(path) => PrefabBuilder::rex_level(path),
+        };
+
+        let mut builder = BuilderChain::new(build_data.map.depth, build_data.width, build_data.height, "Sample");
+        builder.start_with(prefab);
+        builder.build_map();
+        builder.build_data.map
+    }
+
+    /// Every whole chunk of the map holding a tile to keep, with what is in it.
+    fn fixed_chunks(&self, map: &Map, chunk_size: i32) -> Vec<(usize, usize, Vec<TileType>)> {
+        let mut fixed = Vec::new();
+        for chunk_y in 0..map.height / chunk_size {
+            for chunk_x in 0..map.width / chunk_size {
+                let mut pattern = Vec::new();
+                for y in chunk_y * chunk_size..(chunk_y + 1) * chunk_size {
+                    for x in chunk_x * chunk_size..(chunk_x + 1) * chunk_size {
+                        pattern.push(map.tiles[map.xy_idx(x, y)]);
+                    }
+                }
+                if pattern.iter().any(|tt| self.keep.contains(tt)) {
+                    fixed.push((chunk_x as usize, chunk_y as usize, pattern));
+                }
+            }
         }
-        build_data.spawn_list.clear();
+
+        fixed
     }
 
     fn render_tile_gallery(&mut self, constraints: &[MapChunk], chunk_size: i32, build_data: &mut BuilderMap) {
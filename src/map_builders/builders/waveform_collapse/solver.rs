@@ -1,5 +1,27 @@
-use super::{Map, MapChunk};
-use std::collections::HashSet;
+use super::{Map, MapChunk, TileType};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Most steps a solver may take for each chunk of the map, counting placements and backtracks alike.
+const STEPS_PER_CHUNK: usize = 50;
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum SolverState {
+    Working,
+    Solved,
+    /// Backtracked all the way and still couldn't fit the chunks together.
+    Impossible,
+    /// Ran out of steps before finishing.
+    Exhausted,
+    Cancelled,
+}
+
+/// A choice the solver made, and what it can fall back on if that leads nowhere.
+struct Decision {
+    chunk: usize,
+    untried: Vec<usize>,
+    remaining: Vec<(usize, i32)>,
+}
 
 pub struct Solver {
     constraints: Vec<MapChunk>,
@@ -8,7 +30,11 @@ pub struct Solver {
     chunks_x: usize,
     chunks_y: usize,
     remaining: Vec<(usize, i32)>, // (index, # neighbors)
-    pub possible: bool,
+    decisions: Vec<Decision>,
+    steps: usize,
+    max_steps: usize,
+    cancel: Option<Arc<AtomicBool>>,
+    pub state: SolverState,
 }
 
 impl Solver {
@@ -27,59 +53,74 @@ impl Solver {
             chunks_x,
             chunks_y,
             remaining,
-            possible: true,
+            decisions: Vec::new(),
+            steps: 0,
+            max_steps: chunks_x * chunks_y * STEPS_PER_CHUNK,
+            cancel: None,
+            state: SolverState::Working,
         }
     }
 
-    fn chunk_idx(&self, x: usize, y: usize) -> usize { ((y * self.chunks_x) + x) as usize }
+    /// Stops the solver at its next step once the flag is raised.
+    pub fn cancel_with(&mut self, flag: Arc<AtomicBool>) { self.cancel = Some(flag); }
+
+    /// Pins a chunk to a pattern before solving starts. The solver fits everything else around it, and never
+    /// backtracks over it.
+    pub fn fix(&mut self, chunk_x: usize, chunk_y: usize, constraint: usize, map: &mut Map) {
+        let chunk_index = self.chunk_idx(chunk_x, chunk_y);
+        self.remaining.retain(|r| r.0 != chunk_index);
+        self.place(chunk_index, constraint, map);
+    }
 
-    fn count_neighbors(&self, chunk_x: usize, chunk_y: usize) -> i32 {
-        let mut neighbors = 0;
+    fn chunk_idx(&self, x: usize, y: usize) -> usize { (y * self.chunks_x) + x }
+
+    /// The chunks beside this one that have a pattern, each with the side of that pattern that faces this chunk.
+    fn decided_neighbors(&self, chunk_x: usize, chunk_y: usize) -> Vec<(usize, usize)> {
+        let mut neighbors = Vec::new();
 
         if chunk_x > 0 {
-            let left_idx = self.chunk_idx(chunk_x - 1, chunk_y);
-            match self.chunks[left_idx] {
-                None => {},
-                Some(_) => {
-                    neighbors += 1;
-                },
+            if let Some(nt) = self.chunks[self.chunk_idx(chunk_x - 1, chunk_y)] {
+                neighbors.push((nt, 3));
             }
         }
 
         if chunk_x < self.chunks_x - 1 {
-            let right_idx = self.chunk_idx(chunk_x + 1, chunk_y);
-            match self.chunks[right_idx] {
-                None => {},
-                Some(_) => {
-                    neighbors += 1;
-                },
+            if let Some(nt) = self.chunks[self.chunk_idx(chunk_x + 1, chunk_y)] {
+                neighbors.push((nt, 2));
             }
         }
 
         if chunk_y > 0 {
-            let up_idx = self.chunk_idx(chunk_x, chunk_y - 1);
-            match self.chunks[up_idx] {
-                None => {},
-                Some(_) => {
-                    neighbors += 1;
-                },
+            if let Some(nt) = self.chunks[self.chunk_idx(chunk_x, chunk_y - 1)] {
+                neighbors.push((nt, 1));
             }
         }
 
         if chunk_y < self.chunks_y - 1 {
-            let down_idx = self.chunk_idx(chunk_x, chunk_y + 1);
-            match self.chunks[down_idx] {
-                None => {},
-                Some(_) => {
-                    neighbors += 1;
-                },
+            if let Some(nt) = self.chunks[self.chunk_idx(chunk_x, chunk_y + 1)] {
+                neighbors.push((nt, 0));
             }
         }
+
         neighbors
     }
 
     pub fn iteration(&mut self, map: &mut Map) -> bool {
+        if self.state != SolverState::Working {
+            return true;
+        }
+        if self.cancel.as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed)) {
+            self.state = SolverState::Cancelled;
+            return true;
+        }
         if self.remaining.is_empty() {
+            self.state = SolverState::Solved;
+            return true;
+        }
+        self.steps += 1;
+        if self.steps > self.max_steps {
+            rltk::console::log("Wave function collapse ran out of steps");
+            self.state = SolverState::Exhausted;
             return true;
         }
 
@@ -90,13 +131,13 @@ impl Solver {
             let idx = r.0;
             let chunk_x = idx % self.chunks_x;
             let chunk_y = idx / self.chunks_x;
-            let neighbor_count = self.count_neighbors(chunk_x, chunk_y);
+            let neighbor_count = self.decided_neighbors(chunk_x, chunk_y).len() as i32;
             if neighbor_count > 0 {
                 neighbors_exist = true;
             }
             *r = (r.0, neighbor_count);
         }
-        remain_copy.sort_by(|a, b| b.1.cmp(&a.1));
+        remain_copy.sort_by_key(|r| std::cmp::Reverse(r.1));
         self.remaining = remain_copy;
 
         // Pick a random chunk we haven't dealt with yet and get its index, remove from remaining list
@@ -108,125 +149,93 @@ impl Solver {
         let chunk_index = self.remaining[remaining_index].0;
         self.remaining.remove(remaining_index);
 
-        let chunk_x = chunk_index % self.chunks_x;
-        let chunk_y = chunk_index / self.chunks_x;
+        // Anything can go where there is nothing nearby; otherwise it has to fit every neighbour
+        let neighbors = self.decided_neighbors(chunk_index % self.chunks_x, chunk_index / self.chunks_x);
+        let options: Vec<usize> = (0..self.constraints.len())
+            .filter(|c| self.constraints[*c].weight > 0)
+            .filter(|c| {
+                neighbors
+                    .iter()
+                    .all(|(nt, side)| self.constraints[*nt].compatible_with[*side].contains(c))
+            })
+            .collect();
+
+        if options.is_empty() {
+            self.backtrack(map);
+        } else {
+            self.decide(chunk_index, options, map);
+        }
 
-        let mut neighbors = 0;
-        let mut options: Vec<Vec<usize>> = Vec::new();
+        self.state != SolverState::Working
+    }
 
-        if chunk_x > 0 {
-            let left_idx = self.chunk_idx(chunk_x - 1, chunk_y);
-            match self.chunks[left_idx] {
-                None => {},
-                Some(nt) => {
-                    neighbors += 1;
-                    options.push(self.constraints[nt].compatible_with[3].clone());
-                },
-            }
-        }
+    fn decide(&mut self, chunk_index: usize, mut untried: Vec<usize>, map: &mut Map) {
+        let choice = untried.remove(self.pick_weighted(&untried));
+        self.decisions.push(Decision {
+            chunk: chunk_index,
+            untried,
+            remaining: self.remaining.clone(),
+        });
+        self.place(chunk_index, choice, map);
+    }
 
-        if chunk_x < self.chunks_x - 1 {
-            let right_idx = self.chunk_idx(chunk_x + 1, chunk_y);
-            match self.chunks[right_idx] {
-                None => {},
-                Some(nt) => {
-                    neighbors += 1;
-                    options.push(self.constraints[nt].compatible_with[2].clone());
-                },
+    /// Undoes choices, latest first, until one can be made differently.
+    fn backtrack(&mut self, map: &mut Map) {
+        while let Some(mut decision) = self.decisions.pop() {
+            self.clear(decision.chunk, map);
+            if !decision.untried.is_empty() {
+                let choice = decision.untried.remove(self.pick_weighted(&decision.untried));
+                let chunk_index = decision.chunk;
+                self.remaining = decision.remaining.clone();
+                self.decisions.push(decision);
+                self.place(chunk_index, choice, map);
+                return;
             }
         }
 
-        if chunk_y > 0 {
-            let up_idx = self.chunk_idx(chunk_x, chunk_y - 1);
-            match self.chunks[up_idx] {
-                None => {},
-                Some(nt) => {
-                    neighbors += 1;
-                    options.push(self.constraints[nt].compatible_with[1].clone());
-                },
-            }
-        }
+        rltk::console::log("Oh no! It's not possible!");
+        self.state = SolverState::Impossible;
+    }
 
-        if chunk_y < self.chunks_y - 1 {
-            let down_idx = self.chunk_idx(chunk_x, chunk_y + 1);
-            match self.chunks[down_idx] {
-                None => {},
-                Some(nt) => {
-                    neighbors += 1;
-                    options.push(self.constraints[nt].compatible_with[0].clone());
-                },
+    /// Index into `options` of a pattern picked at random, in proportion to how common it is.
+    fn pick_weighted(&self, options: &[usize]) -> usize {
+        let total: i32 = options.iter().map(|c| self.constraints[*c].weight).sum();
+        let mut roll = crate::rng::roll_dice(1, total);
+        for (i, c) in options.iter().enumerate() {
+            if roll <= self.constraints[*c].weight {
+                return i;
             }
+            roll -= self.constraints[*c].weight;
         }
+        options.len() - 1
+    }
 
-        if neighbors == 0 {
-            // There is nothing nearby, so we can have anything!
-            let new_chunk_idx = (crate::rng::roll_dice(1, self.constraints.len() as i32) - 1) as usize;
-            self.chunks[chunk_index] = Some(new_chunk_idx);
-            let left_x = chunk_x as i32 * self.chunk_size as i32;
-            let right_x = (chunk_x as i32 + 1) * self.chunk_size as i32;
-            let top_y = chunk_y as i32 * self.chunk_size as i32;
-            let bottom_y = (chunk_y as i32 + 1) * self.chunk_size as i32;
-
-            let mut i: usize = 0;
-            for y in top_y..bottom_y {
-                for x in left_x..right_x {
-                    let mapidx = map.xy_idx(x, y);
-                    let tile = self.constraints[new_chunk_idx].pattern[i];
-                    map.tiles[mapidx] = tile;
-                    i += 1;
-                }
-            }
-        } else {
-            // There are neighbors, so we try to be compatible with them
-            let mut options_to_check: HashSet<usize> = HashSet::new();
-            for o in options.iter() {
-                for i in o.iter() {
-                    options_to_check.insert(*i);
-                }
-            }
+    fn place(&mut self, chunk_index: usize, constraint: usize, map: &mut Map) {
+        self.chunks[chunk_index] = Some(constraint);
+        let pattern = self.constraints[constraint].pattern.clone();
+        self.paint(chunk_index, |i| pattern[i], map);
+    }
 
-            let mut possible_options: Vec<usize> = Vec::new();
-            for new_chunk_idx in options_to_check.iter() {
-                let mut possible = true;
-                for o in options.iter() {
-                    if !o.contains(new_chunk_idx) {
-                        possible = false;
-                    }
-                }
-                if possible {
-                    possible_options.push(*new_chunk_idx);
-                }
-            }
+    fn clear(&mut self, chunk_index: usize, map: &mut Map) {
+        self.chunks[chunk_index] = None;
+        self.paint(chunk_index, |_| TileType::Wall, map);
+    }
 
-            if possible_options.is_empty() {
-                rltk::console::log("Oh no! It's not possible!");
-                self.possible = false;
-                return true;
-            } else {
-                let new_chunk_idx = if possible_options.len() == 1 {
-                    0
-                } else {
-                    crate::rng::roll_dice(1, possible_options.len() as i32) - 1
-                };
-
-                self.chunks[chunk_index] = Some(possible_options[new_chunk_idx as usize]);
-                let left_x = chunk_x as i32 * self.chunk_size as i32;
-                let right_x = (chunk_x as i32 + 1) * self.chunk_size as i32;
-                let top_y = chunk_y as i32 * self.chunk_size as i32;
-                let bottom_y = (chunk_y as i32 + 1) * self.chunk_size as i32;
-
-                let mut i: usize = 0;
-                for y in top_y..bottom_y {
-                    for x in left_x..right_x {
-                        let mapidx = map.xy_idx(x, y);
-                        let tile = self.constraints[possible_options[new_chunk_idx as usize]].pattern[i];
-                        map.tiles[mapidx] = tile;
-                        i += 1;
-                    }
-                }
+    fn paint(&self, chunk_index: usize, tile: impl Fn(usize) -> TileType, map: &mut Map) {
+        let chunk_x = (chunk_index % self.chunks_x) as i32;
+        let chunk_y = (chunk_index / self.chunks_x) as i32;
+        let left_x = chunk_x * self.chunk_size;
+        let right_x = (chunk_x + 1) * self.chunk_size;
+        let top_y = chunk_y * self.chunk_size;
+        let bottom_y = (chunk_y + 1) * self.chunk_size;
+
+        let mut i: usize = 0;
+        for y in top_y..bottom_y {
+            for x in left_x..right_x {
+                let mapidx = map.xy_idx(x, y);
+                map.tiles[mapidx] = tile(i);
+                i += 1;
             }
         }
-
-        false
     }
 }
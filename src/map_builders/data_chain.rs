@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+
+use super::*;
+use crate::raws::structs::{BuilderStep, LevelChain, PrefabKind};
+
+impl BuilderChain {
+    /// Assembles a chain from its description in `level_chains.json`, rolling for any chances and alternatives
+    /// along the way. The first builder reached starts the chain; every one after it is a meta builder.
+    pub fn from_raw(chain: &LevelChain, new_depth: i32, width: i32, height: i32) -> BuilderChain {
+        let mut builder = BuilderChain::new(new_depth, width, height, &chain.name);
+        builder.daylight = chain.daylight;
+        builder.theme = chain.theme.clone();
+        builder.validate = true;
+        builder.recipe = Some(chain.clone());
+        for step in chain.steps.iter() {
+            add_step(&mut builder, step);
+        }
+
+        builder
+    }
+}
+
+/// Picks one of the chains that can appear at a depth of a branch, by weight.
+pub fn pick_level_chain(branch: usize, new_depth: i32) -> Option<LevelChain> {
+    let chains = raws::get_level_chains_for_depth(&RAWS.lock().unwrap(), branch, new_depth);
+    pick_weighted(&chains, |chain| chain.weight).cloned()
+}
+
+/// Whether there is a chain that could be picked for a depth of a branch, so that a level can be built there.
+pub fn covers_depth(branch: usize, depth: i32) -> bool {
+    raws::get_level_chains_for_depth(&RAWS.lock().unwrap(), branch, depth)
+        .iter()
+        .any(|chain| chain.weight > 0)
+}
+
+fn add_step(builder: &mut BuilderChain, step: &BuilderStep) {
+    if let Some(chance) = step.chance {
+        if crate::rng::roll_dice(1, 100) > chance {
+            return;
+        }
+    }
+
+    if let Some(name) = &step.builder {
+        // Every chain was checked over when the raws loaded
+        if builder.starter.is_none() {
+            let starter = initial_builder(name, &step.params).expect("Chain picks a builder it can't start with");
+            builder.start_with(starter);
+        } else {
+            let meta = meta_builder(name, &step.params).expect("Chain picks a builder it can't use");
+            builder.with(meta);
+        }
+    }
+
+    for sub_step in step.steps.iter() {
+        add_step(builder, sub_step);
+    }
+
+    if let Some(alternative) = pick_weighted(&step.one_of, |s| s.weight) {
+        add_step(builder, alternative);
+    }
+}
+
+/// Whether a chain has picked its starting builder by some point along it, which can depend on the rolls it makes.
+#[derive(Clone, Copy, PartialEq)]
+enum Started {
+    No,
+    Maybe,
+    Yes,
+}
+
+/// Makes sure a level can be built wherever one is needed, whatever is rolled along the way: every builder a chain
+/// names exists and can do the job it is put to, and every chain picks a starting builder. Each depth a level can be
+/// made for has a chain that can be picked for it, with one for the main dungeon that carries on all the way down,
+/// and each area of the overworld has the chain it is built from.
+pub fn check_level_chains() -> Result<(), String> {
+    let (chains, branches) = {
+        let raws = RAWS.lock().unwrap();
+        (raws::get_level_chains(&raws), raws::get_branches(&raws))
+    };
+
+    for chain in chains.iter() {
+        if check_steps(&chain.steps, Started::No, &chain.name)? != Started::Yes {
+            return Err(format!(
+                "Level chain {} doesn't always pick a starting builder",
+                chain.name
+            ));
+        }
+    }
+
+    let main = || chains.iter().filter(|chain| chain.branch.is_none() && chain.weight > 0);
+    if !main().any(|chain| chain.max_depth.is_none()) {
+        return Err("No level chain carries on to the bottom of the main dungeon".to_string());
+    }
+
+    let mut depths: Vec<(usize, i32)> = (1..=main().map(|chain| chain.min_depth).max().unwrap_or(1))
+        .map(|depth| (MAIN_BRANCH, depth))
+        .collect();
+    for (branch, info) in branches.iter() {
+        let deepest = info.max_entrance_depth + info.levels;
+        depths.extend((info.min_entrance_depth + 1..=deepest).map(|depth| (*branch, depth)));
+    }
+    if let Some((branch, depth)) = depths
+        .into_iter()
+        .find(|(branch, depth)| !covers_depth(*branch, *depth))
+    {
+        return Err(format!("No level chain covers depth {} of branch {}", depth, branch));
+    }
+
+    match crate::map::AREA_CHAINS
+        .iter()
+        .find(|name| !chains.iter().any(|chain| chain.name == **name))
+    {
+        Some(name) => Err(format!("No level chain is called {}, for the overworld", name)),
+        None => Ok(()),
+    }
+}
+
+fn check_steps(steps: &[BuilderStep], mut started: Started, chain: &str) -> Result<Started, String> {
+    for step in steps.iter() {
+        started = check_step(step, started, chain)?;
+    }
+    Ok(started)
+}
+
+fn check_step(step: &BuilderStep, started: Started, chain: &str) -> Result<Started, String> {
+    let mut after = started;
+
+    if let Some(name) = &step.builder {
+        let (starter, meta) = (
+            initial_builder(name, &step.params).is_some(),
+            meta_builder(name, &step.params).is_some(),
+        );
+        if !starter && !meta {
+            return Err(match step.params.is_empty() {
+                true => format!("Level chain {} uses unknown builder {}", chain, name),
+                false => format!("Level chain {} can't make a {} from {:?}", chain, name, step.params),
+            });
+        }
+        if after != Started::Yes && !starter {
+            return Err(format!("Level chain {} can't start with {}", chain, name));
+        }
+        if after != Started::No && !meta {
+            return Err(format!("Level chain {} can only use {} to start with", chain, name));
+        }
+        after = Started::Yes;
+    }
+
+    after = check_steps(&step.steps, after, chain)?;
+
+    let alternatives: Vec<Started> = step
+        .one_of
+        .iter()
+        .filter(|alternative| alternative.weight > 0)
+        .map(|alternative| check_step(alternative, after, chain))
+        .collect::<Result<_, _>>()?;
+    if let Some(first) = alternatives.first() {
+        after = if alternatives.iter().all(|a| a == first) { *first } else { Started::Maybe };
+    }
+
+    // A step that only happens by chance may leave things as they were
+    if step.chance.is_some() && after != started {
+        after = Started::Maybe;
+    }
+
+    Ok(after)
+}
+
+fn pick_weighted<T>(options: &[T], weight: impl Fn(&T) -> i32) -> Option<&T> {
+    let total: i32 = options.iter().map(&weight).filter(|w| *w > 0).sum();
+    if total < 1 {
+        return None;
+    }
+
+    let mut roll = crate::rng::roll_dice(1, total);
+    for option in options.iter().filter(|o| weight(o) > 0) {
+        if roll <= weight(option) {
+            return Some(option);
+        }
+        roll -= weight(option);
+    }
+
+    None
+}
+
+fn param<'a>(params: &'a HashMap<String, String>, key: &str) -> &'a str {
+    params.get(key).map_or("", |value| value.as_str())
+}
+
+fn initial_builder(name: &str, params: &HashMap<String, String>) -> Option<Box<dyn InitialMapBuilder>> {
+    let builder: Box<dyn InitialMapBuilder> = match name {
+        "SimpleMapBuilder" => SimpleMapBuilder::new(),
+        "BspDungeonBuilder" => BspDungeonBuilder::new(),
+        "BspInteriorBuilder" => BspInteriorBuilder::new(),
+        "MazeBuilder" => MazeBuilder::new(),
+        "TownBuilder" => town(params),
+        "CellularAutomataBuilder" => CellularAutomataBuilder::new(),
+        "DrunkardsWalkBuilder" => drunkards_walk(params),
+        "DLABuilder" => dla(params),
+        "VoronoiCellBuilder" => voronoi(params),
+        "PrefabBuilder" => prefab(params)?,
+        _ => return None,
+    };
+    Some(builder)
+}
+
+fn meta_builder(name: &str, params: &HashMap<String, String>) -> Option<Box<dyn MetaMapBuilder>> {
+    let builder: Box<dyn MetaMapBuilder> = match name {
+        // Shapes that can also reshape an existing map
+        "CellularAutomataBuilder" => CellularAutomataBuilder::new(),
+        "DrunkardsWalkBuilder" => drunkards_walk(params),
+        "DLABuilder" => dla(params),
+        "PrefabBuilder" => prefab(params)?,
+        "WaveformCollapseBuilder" => waveform_collapse(params)?,
+
+        // Rooms and corridors
+        "RoomSorter" => RoomSorter::new(match param(params, "sort") {
+            "Right" => RoomSort::Right,
+            "Top" => RoomSort::Top,
+            "Bottom" => RoomSort::Bottom,
+            "Central" => RoomSort::Central,
+            _ => RoomSort::Left,
+        }),
+        "RoomDrawer" => RoomDrawer::new(),
+        "RoomExploder" => RoomExploder::new(),
+        "RoomCornerRounder" => RoomCornerRounder::new(),
+        "DoglegCorridors" => DoglegCorridors::new(),
+        "NearestCorridors" => NearestCorridors::new(),
+        "StraightLineCorridors" => StraightLineCorridors::new(),
+        "BspCorridors" => BspCorridors::new(),
+        "CorridorSpawner" => CorridorSpawner::new(),
+        "DoorPlacement" => DoorPlacement::with_secrets(param(params, "secret").parse().unwrap_or(0)),
+        "RoomFurnisher" => RoomFurnisher::new(),
+        "LockAndKey" => LockAndKey::new(param(params, "locks").parse().unwrap_or(1)),
+
+        // Start, exit and population
+        "AreaStartingPosition" => AreaStartingPosition::new(x_start(params), y_start(params)),
+        "AreaEndingPosition" => AreaEndingPosition::new(x_end(params), y_end(params)),
+        "RoomBasedStartingPosition" => RoomBasedStartingPosition::new(),
+        "RoomBasedStairs" => RoomBasedStairs::new(),
+        "DistantExit" => DistantExit::new(),
+        "ExtraStairs" => ExtraStairs::new(param(params, "count").parse().unwrap_or(1)),
+        "CullUnreachable" => CullUnreachable::new(),
+        "RoomBasedSpawner" => RoomBasedSpawner::new(),
+        "VoronoiSpawning" => VoronoiSpawning::new(),
+
+        // Themed touches
+        "YellowBrickRoad" => YellowBrickRoad::new(),
+        "CaveDecorator" => CaveDecorator::new(),
+        "CaveTransition" => CaveTransition::new(),
+        "HazardPlacement" => hazards(params)?,
+        "DragonsLair" => DragonsLair::new(),
+        "DragonSpawner" => DragonSpawner::new(),
+        _ => return None,
+    };
+    Some(builder)
+}
+
+fn town(params: &HashMap<String, String>) -> Box<TownBuilder> {
+    match param(params, "variant") {
+        "farmstead" => TownBuilder::farmstead(),
+        _ => TownBuilder::new(),
+    }
+}
+
+/// `tile` names the hazard in `tiles.json`, and `patches` says how many patches of it to scatter.
+fn hazards(params: &HashMap<String, String>) -> Option<Box<HazardPlacement>> {
+    let hazard = TileType::named(param(params, "tile"))?;
+    Some(HazardPlacement::new(
+        hazard,
+        param(params, "patches").parse().unwrap_or(1),
+    ))
+}
+
+fn drunkards_walk(params: &HashMap<String, String>) -> Box<DrunkardsWalkBuilder> {
+    match param(params, "variant") {
+        "open_halls" => DrunkardsWalkBuilder::open_halls(),
+        "winding_passages" => DrunkardsWalkBuilder::winding_passages(),
+        "fat_passages" => DrunkardsWalkBuilder::fat_passages(),
+        "fearful_symmetry" => DrunkardsWalkBuilder::fearful_symmetry(),
+        _ => DrunkardsWalkBuilder::open_area(),
+    }
+}
+
+fn dla(params: &HashMap<String, String>) -> Box<DLABuilder> {
+    match param(params, "variant") {
+        "walk_inwards" => DLABuilder::walk_inwards(),
+        "walk_outwards" => DLABuilder::walk_outwards(),
+        "central_attractor" => DLABuilder::central_attractor(),
+        "insectoid" => DLABuilder::insectoid(),
+        "heavy_erosion" => DLABuilder::heavy_erosion(),
+        _ => DLABuilder::new(),
+    }
+}
+
+fn voronoi(params: &HashMap<String, String>) -> Box<VoronoiCellBuilder> {
+    match param(params, "variant") {
+        "manhattan" => VoronoiCellBuilder::manhattan(),
+        _ => VoronoiCellBuilder::pythagoras(),
+    }
+}
+
+/// Samples the map built so far, unless `sample` names a prefab level or the resource path of an `.xp` file, such as
+/// `../resources/wfc-demo1.xp`. `keep` lists the tiles whose chunks are left in place, separated by commas.
+fn waveform_collapse(params: &HashMap<String, String>) -> Option<Box<WaveformCollapseBuilder>> {
+    let sample = match param(params, "sample") {
+        "" => WfcSample::CurrentMap,
+        path if path.ends_with(".xp") => WfcSample::Rex(path.to_string()),
+        name => WfcSample::Prefab(raws::get_prefab(&RAWS.lock().unwrap(), name, PrefabKind::Level)?),
+    };
+
+    let builder = WaveformCollapseBuilder::sampling(sample);
+    match params.get("keep") {
+        None => Some(builder),
+        Some(keep) => {
+            let tiles: Option<Vec<TileType>> = keep.split(',').map(|name| TileType::named(name.trim())).collect();
+            Some(builder.keeping(tiles?))
+        },
+    }
+}
+
+/// A whole `level` or a `section` from the prefab library, by name; with neither, vaults from the library.
+fn prefab(params: &HashMap<String, String>) -> Option<Box<PrefabBuilder>> {
+    let raws = RAWS.lock().unwrap();
+    if let Some(level) = params.get("level") {
+        return raws::get_prefab(&raws, level, PrefabKind::Level).map(PrefabBuilder::constant);
+    }
+
+    if let Some(section) = params.get("section") {
+        return raws::get_prefab(&raws, section, PrefabKind::Section).map(PrefabBuilder::sectional);
+    }
+
+    Some(PrefabBuilder::vaults())
+}
+
+fn x_start(params: &HashMap<String, String>) -> XStart {
+    match param(params, "x") {
+        "Left" => XStart::Left,
+        "Center" => XStart::Center,
+        "Right" => XStart::Right,
+        _ => match crate::rng::roll_dice(1, 3) {
+            1 => XStart::Left,
+            2 => XStart::Center,
+            _ => XStart::Right,
+        },
+    }
+}
+
+fn y_start(params: &HashMap<String, String>) -> YStart {
+    match param(params, "y") {
+        "Top" => YStart::Top,
+        "Center" => YStart::Center,
+        "Bottom" => YStart::Bottom,
+        _ => match crate::rng::roll_dice(1, 3) {
+            1 => YStart::Bottom,
+            2 => YStart::Center,
+            _ => YStart::Top,
+        },
+    }
+}
+
+fn x_end(params: &HashMap<String, String>) -> XEnd {
+    match param(params, "x") {
+        "Left" => XEnd::Left,
+        "Center" => XEnd::Center,
+        _ => XEnd::Right,
+    }
+}
+
+fn y_end(params: &HashMap<String, String>) -> YEnd {
+    match param(params, "y") {
+        "Top" => YEnd::Top,
+        "Center" => YEnd::Center,
+        _ => YEnd::Bottom,
+    }
+}
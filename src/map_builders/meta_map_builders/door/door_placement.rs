@@ -1,6 +1,11 @@
+use std::collections::{HashSet, VecDeque};
+
 use super::{BuilderMap, MetaMapBuilder, TileType};
 
-pub struct DoorPlacement {}
+pub struct DoorPlacement {
+    /// One door in this many is a secret one, made to pass for a wall; none are if it is 0.
+    secret_one_in: i32,
+}
 
 impl MetaMapBuilder for DoorPlacement {
     #[allow(dead_code)]
@@ -9,9 +14,15 @@ impl MetaMapBuilder for DoorPlacement {
 
 impl DoorPlacement {
     #[allow(dead_code)]
-    pub fn new() -> Box<DoorPlacement> { Box::new(DoorPlacement {}) }
+    pub fn new() -> Box<DoorPlacement> { DoorPlacement::with_secrets(0) }
+
+    /// Places doors, one in `one_in` of them a secret door.
+    #[allow(dead_code)]
+    pub fn with_secrets(one_in: i32) -> Box<DoorPlacement> { Box::new(DoorPlacement { secret_one_in: one_in }) }
 
     fn doors(&mut self, build_data: &mut BuilderMap) {
+        let before = build_data.spawn_list.len();
+
         if let Some(halls_original) = &build_data.corridors {
             let halls = halls_original.clone(); // To avoid nested borrowing
             for hall in halls.iter() {
@@ -32,6 +43,70 @@ impl DoorPlacement {
                 }
             }
         }
+
+        if self.secret_one_in > 0 {
+            self.hide_doors(before, build_data);
+        }
+    }
+
+    /// Turns some of the doors placed from `first` on into secret doors, as long as the way from the start to the
+    /// stairs doesn't rely on them, and leaves scuff marks in front of most of them for the sharp-eyed.
+    fn hide_doors(&mut self, first: usize, build_data: &mut BuilderMap) {
+        let mut hidden: HashSet<usize> = HashSet::new();
+        for i in first..build_data.spawn_list.len() {
+            let idx = build_data.spawn_list[i].0;
+            if crate::rng::roll_dice(1, self.secret_one_in) != 1 {
+                continue;
+            }
+
+            hidden.insert(idx);
+            if !DoorPlacement::stairs_reachable(&hidden, build_data) {
+                hidden.remove(&idx);
+                continue;
+            }
+            build_data.spawn_list[i].1 = "Secret Door".to_string();
+
+            if crate::rng::roll_dice(1, 3) > 1 {
+                let taken: HashSet<usize> = build_data.spawn_list.iter().map(|(idx, _)| *idx).collect();
+                let spots: Vec<usize> = build_data
+                    .map
+                    .open_neighbours(idx, false)
+                    .into_iter()
+                    .filter(|n| !taken.contains(n))
+                    .collect();
+                if !spots.is_empty() {
+                    let spot = spots[(crate::rng::roll_dice(1, spots.len() as i32) - 1) as usize];
+                    build_data.spawn_list.push((spot, "Scuff Marks".to_string()));
+                }
+            }
+        }
+    }
+
+    /// Whether every staircase can still be reached from the start with these doors shut for good.
+    fn stairs_reachable(shut: &HashSet<usize>, build_data: &BuilderMap) -> bool {
+        let start = match &build_data.starting_position {
+            Some(start) => build_data.map.xy_idx(start.x, start.y),
+            None => return false, // Without a start there's no telling, so nothing is hidden
+        };
+
+        let mut reached = vec![false; build_data.map.tiles.len()];
+        let mut open = VecDeque::from([start]);
+        reached[start] = true;
+        while let Some(idx) = open.pop_front() {
+            for n in build_data.map.open_neighbours(idx, true) {
+                if !reached[n] && !shut.contains(&n) {
+                    reached[n] = true;
+                    open.push_back(n);
+                }
+            }
+        }
+
+        build_data
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .all(|(idx, tt)| (*tt != TileType::DownStairs && *tt != TileType::UpStairs) || reached[idx])
     }
 
     fn door_possible(&self, build_data: &mut BuilderMap, idx: usize) -> bool {
@@ -0,0 +1,282 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::{BuilderMap, MetaMapBuilder, Rect};
+
+/// A door the lock-and-key builder locked, and where it left the key.
+#[derive(Clone, Debug)]
+pub struct Lock {
+    /// The id shared by the door and its key. It is made from the branch and depth as well, so that a key carried
+    /// off to another level opens nothing there.
+    pub id: i32,
+    /// The door's tile.
+    pub door: usize,
+    /// The key's tile, which can be reached from the start with every locked door still shut.
+    pub key: usize,
+    /// The room the key was left in, by index into the level's rooms.
+    pub key_room: usize,
+    /// The rooms that stay shut away for as long as this door is locked, whichever other doors are open, by index
+    /// into the level's rooms.
+    pub opens: Vec<usize>,
+}
+
+/// Locks some of the doors between rooms, and leaves each one's key in a room that can be reached with all of them
+/// still locked, so the level can always be finished in any order. Only doors that shut a room away by themselves
+/// are locked, and never secret ones. The locks it makes are kept in `build_data.locks`.
+pub struct LockAndKey {
+    /// How many doors to try to lock.
+    count: i32,
+}
+
+impl MetaMapBuilder for LockAndKey {
+    fn build_map(&mut self, build_data: &mut BuilderMap) { self.build(build_data); }
+}
+
+impl LockAndKey {
+    #[allow(dead_code)]
+    pub fn new(count: i32) -> Box<LockAndKey> { Box::new(LockAndKey { count }) }
+
+    fn build(&mut self, build_data: &mut BuilderMap) {
+        let rooms = match &build_data.rooms {
+            Some(rooms) => rooms.clone(),
+            None => return, // Keys are left in rooms, so there have to be some
+        };
+        let start = match &build_data.starting_position {
+            Some(start) => build_data.map.xy_idx(start.x, start.y),
+            None => return,
+        };
+
+        // Secret doors might never be found, so nothing can rely on going through them
+        let secret: HashSet<usize> = build_data
+            .spawn_list
+            .iter()
+            .filter(|(_, name)| name == "Secret Door")
+            .map(|(idx, _)| *idx)
+            .collect();
+        let unlocked = LockAndKey::reachable(start, &secret, build_data);
+        let mut shut = secret.clone();
+        let mut doors: Vec<usize> = (0..build_data.spawn_list.len())
+            .filter(|i| build_data.spawn_list[*i].1 == "Door" && build_data.spawn_list[*i].0 != start)
+            .collect();
+
+        let mut locks: Vec<Lock> = Vec::new();
+        while (locks.len() as i32) < self.count && !doors.is_empty() {
+            let spawn = doors.remove((crate::rng::roll_dice(1, doors.len() as i32) - 1) as usize);
+            let door = build_data.spawn_list[spawn].0;
+
+            // A door that doesn't shut a room away by itself isn't worth locking
+            if LockAndKey::shut_away_by(door, &rooms, &secret, &unlocked, start, build_data).is_empty() {
+                continue;
+            }
+
+            // None of the keys so far may be shut away
+            shut.insert(door);
+            let after = LockAndKey::reachable(start, &shut, build_data);
+            if locks.iter().any(|lock| !after[lock.key]) {
+                shut.remove(&door);
+                continue;
+            }
+
+            match LockAndKey::key_spot(&rooms, &after, build_data) {
+                Some((key, key_room)) => {
+                    let id = (build_data.map.branch as i32 * 1000 + build_data.map.depth) * 100 + locks.len() as i32;
+                    build_data.spawn_list[spawn].1 = "Locked Door".to_string();
+                    build_data.spawn_list.push((key, "Key".to_string()));
+                    locks.push(Lock {
+                        id,
+                        door,
+                        key,
+                        key_room,
+                        opens: Vec::new(),
+                    });
+                },
+                None => {
+                    shut.remove(&door);
+                },
+            }
+        }
+
+        for lock in locks.iter_mut() {
+            lock.opens = LockAndKey::shut_away_by(lock.door, &rooms, &secret, &unlocked, start, build_data);
+        }
+
+        build_data.locks.extend(locks);
+        build_data.take_snapshot();
+    }
+
+    /// A free floor tile, in a random room the start can reach, for a key to be left on.
+    fn key_spot(rooms: &[Rect], reached: &[bool], build_data: &BuilderMap) -> Option<(usize, usize)> {
+        let taken: HashSet<usize> = build_data.spawn_list.iter().map(|(idx, _)| *idx).collect();
+        let start = build_data
+            .starting_position
+            .as_ref()
+            .map(|start| build_data.map.xy_idx(start.x, start.y));
+
+        let mut spots: Vec<(usize, usize)> = Vec::new();
+        for (r, room) in rooms.iter().enumerate() {
+            for y in room.y1 + 1..room.y2 {
+                for x in room.x1 + 1..room.x2 {
+                    let idx = build_data.map.xy_idx(x, y);
+                    if reached[idx] && !taken.contains(&idx) && Some(idx) != start {
+                        spots.push((idx, r));
+                    }
+                }
+            }
+        }
+
+        match spots.len() {
+            0 => None,
+            n => Some(spots[(crate::rng::roll_dice(1, n as i32) - 1) as usize]),
+        }
+    }
+
+    /// The rooms that `door` alone shuts away, out of those that can be reached with every lock open.
+    fn shut_away_by(
+        door: usize,
+        rooms: &[Rect],
+        secret: &HashSet<usize>,
+        unlocked: &[bool],
+        start: usize,
+        build_data: &BuilderMap,
+    ) -> Vec<usize> {
+        let mut shut = secret.clone();
+        shut.insert(door);
+        let reached = LockAndKey::reachable(start, &shut, build_data);
+
+        (0..rooms.len())
+            .filter(|r| {
+                let center = rooms[*r].center();
+                let idx = build_data.map.xy_idx(center.x, center.y);
+                unlocked[idx] && !reached[idx]
+            })
+            .collect()
+    }
+
+    /// Every tile that can be walked to from `start` without going through any of the `shut` ones.
+    fn reachable(start: usize, shut: &HashSet<usize>, build_data: &BuilderMap) -> Vec<bool> {
+        let mut reached = vec![false; build_data.map.tiles.len()];
+        let mut open = VecDeque::from([start]);
+        reached[start] = true;
+        while let Some(idx) = open.pop_front() {
+            for n in build_data.map.open_neighbours(idx, true) {
+                if !reached[n] && !shut.contains(&n) {
+                    reached[n] = true;
+                    open.push_back(n);
+                }
+            }
+        }
+
+        reached
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Mutex, Once};
+
+    use super::*;
+
+    static LOAD_RAWS: Once = Once::new();
+    /// Building a level goes through the shared random number generator and spatial index, so tests take turns.
+    static BUILDING: Mutex<()> = Mutex::new(());
+
+    const SEEDS: [u64; 6] = [1, 2, 3, 5, 8, 13];
+
+    /// Builds the locked-door level of the main dungeon from each seed. Alongside each comes what is shut to begin
+    /// with: the secret doors, which nobody can count on finding, and every locked door.
+    fn locked_levels() -> Vec<(BuilderMap, HashSet<usize>)> {
+        let _turn = BUILDING.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        LOAD_RAWS.call_once(|| crate::raws::load_raws().expect("Unable to load the raws"));
+
+        let levels: Vec<(BuilderMap, HashSet<usize>)> = SEEDS
+            .iter()
+            .map(|seed| {
+                crate::rng::reseed(*seed);
+                let mut builder = crate::map_builders::named_builder("Dark Elven City", 10, None)
+                    .expect("The Dark Elven City chain is missing");
+                builder.build_map();
+
+                let level = builder.build_data;
+                let shut = level
+                    .spawn_list
+                    .iter()
+                    .filter(|(_, name)| name == "Secret Door" || name == "Locked Door")
+                    .map(|(idx, _)| *idx)
+                    .collect();
+                (level, shut)
+            })
+            .collect();
+
+        assert!(
+            levels.iter().any(|(level, _)| !level.locks.is_empty()),
+            "None of the seeds locked a door"
+        );
+        levels
+    }
+
+    fn start(level: &BuilderMap) -> usize {
+        let start = level.starting_position.as_ref().expect("The level has no start");
+        level.map.xy_idx(start.x, start.y)
+    }
+
+    fn room_center(level: &BuilderMap, room: usize) -> usize {
+        let center = level.rooms.as_ref().expect("The level has no rooms")[room].center();
+        level.map.xy_idx(center.x, center.y)
+    }
+
+    #[test]
+    fn every_key_can_be_reached_with_all_locks_shut() {
+        for (level, shut) in locked_levels() {
+            let reached = LockAndKey::reachable(start(&level), &shut, &level);
+            let rooms = level.rooms.as_ref().unwrap();
+
+            for lock in level.locks.iter() {
+                let (x, y) = (lock.key as i32 % level.map.width, lock.key as i32 / level.map.width);
+                assert!(
+                    rooms[lock.key_room].point_in_rect(rltk::Point::new(x, y)),
+                    "Key {} isn't in its room",
+                    lock.id
+                );
+                assert!(reached[lock.key], "Key {} is shut away", lock.id);
+                assert!(
+                    reached[room_center(&level, lock.key_room)],
+                    "The room with key {} is shut away",
+                    lock.id
+                );
+                assert!(
+                    level.spawn_list.contains(&(lock.key, "Key".to_string())),
+                    "Key {} was never placed",
+                    lock.id
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn what_each_lock_opens_is_shut_away_until_it_opens() {
+        for (level, shut) in locked_levels() {
+            let doors: HashSet<usize> = level.locks.iter().map(|lock| lock.door).collect();
+            let secret: HashSet<usize> = shut.difference(&doors).cloned().collect();
+            let all_locked = LockAndKey::reachable(start(&level), &shut, &level);
+            let all_open = LockAndKey::reachable(start(&level), &secret, &level);
+
+            for lock in level.locks.iter() {
+                assert!(!lock.opens.is_empty(), "Lock {} shuts nothing away", lock.id);
+
+                let mut only_this = secret.clone();
+                only_this.insert(lock.door);
+                let this_locked = LockAndKey::reachable(start(&level), &only_this, &level);
+
+                for room in lock.opens.iter() {
+                    let center = room_center(&level, *room);
+                    assert!(
+                        !all_locked[center],
+                        "Room {} behind lock {} can be reached anyway",
+                        room, lock.id
+                    );
+                    assert!(!this_locked[center], "Room {} gets round lock {}", room, lock.id);
+                    assert!(all_open[center], "Opening lock {} doesn't reach room {}", lock.id, room);
+                }
+            }
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use crate::map_builders::*;
 
 pub mod door_placement;
+pub mod lock_and_key;
 
 pub use door_placement::DoorPlacement;
+pub use lock_and_key::{Lock, LockAndKey};
@@ -0,0 +1,27 @@
+use super::{BuilderMap, MetaMapBuilder, TileType};
+
+/// Adds more ways down, well away from the start and from the stairs already placed, so a level can be left by
+/// more than one staircase.
+pub struct ExtraStairs {
+    count: i32,
+}
+
+impl MetaMapBuilder for ExtraStairs {
+    fn build_map(&mut self, build_data: &mut BuilderMap) { self.build(build_data); }
+}
+
+impl ExtraStairs {
+    pub fn new(count: i32) -> Box<ExtraStairs> { Box::new(ExtraStairs { count }) }
+
+    fn build(&mut self, build_data: &mut BuilderMap) {
+        let start = build_data.starting_position.as_ref().unwrap();
+        let start_idx = build_data.map.xy_idx(start.x, start.y);
+
+        for _ in 0..self.count {
+            if let Some(idx) = build_data.map.spot_for_stairs(start_idx) {
+                build_data.map.tiles[idx] = TileType::DownStairs;
+            }
+        }
+        build_data.take_snapshot();
+    }
+}
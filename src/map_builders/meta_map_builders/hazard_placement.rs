@@ -0,0 +1,100 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::{BuilderMap, MetaMapBuilder, TileType};
+
+/// The most tiles a single patch of hazard spreads over.
+const MAX_PATCH_SIZE: i32 = 12;
+
+/// Scatters patches of a hazard, such as lava or a chasm, over the open floor. The way from the start to every
+/// staircase is kept clear, so nobody has to cross a hazard to get through the level.
+pub struct HazardPlacement {
+    hazard: TileType,
+    patches: i32,
+}
+
+impl MetaMapBuilder for HazardPlacement {
+    fn build_map(&mut self, build_data: &mut BuilderMap) { self.build(build_data); }
+}
+
+impl HazardPlacement {
+    #[allow(dead_code)]
+    pub fn new(hazard: TileType, patches: i32) -> Box<HazardPlacement> { Box::new(HazardPlacement { hazard, patches }) }
+
+    fn build(&mut self, build_data: &mut BuilderMap) {
+        let keep_clear = self.paths_to_stairs(build_data);
+        let can_cover = |map: &crate::map::Map, idx: usize| {
+            matches!(map.tiles[idx], TileType::Floor | TileType::Gravel) && !keep_clear.contains(&idx)
+        };
+
+        for _ in 0..self.patches {
+            let candidates: Vec<usize> = (0..build_data.map.tiles.len())
+                .filter(|idx| can_cover(&build_data.map, *idx))
+                .collect();
+            if candidates.is_empty() {
+                break;
+            }
+
+            // Spread out from a random seed tile over whatever floor is next to it
+            let seed = candidates[crate::rng::roll_dice(1, candidates.len() as i32) as usize - 1];
+            let size = crate::rng::roll_dice(1, MAX_PATCH_SIZE);
+            let mut open = VecDeque::from([seed]);
+            let mut placed = 0;
+            while let Some(idx) = open.pop_front() {
+                if placed >= size {
+                    break;
+                }
+                if !can_cover(&build_data.map, idx) {
+                    continue;
+                }
+
+                build_data.map.tiles[idx] = self.hazard;
+                placed += 1;
+
+                let (x, y) = (idx as i32 % build_data.map.width, idx as i32 / build_data.map.width);
+                for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                    let (nx, ny) = (x + dx, y + dy);
+                    if nx > 0 && ny > 0 && nx < build_data.map.width - 1 && ny < build_data.map.height - 1 {
+                        open.push_back(build_data.map.xy_idx(nx, ny));
+                    }
+                }
+            }
+        }
+
+        // Nothing starts the level standing in a hazard
+        let map = &build_data.map;
+        build_data.spawn_list.retain(|(idx, _)| map.tiles[*idx] != self.hazard);
+        build_data.take_snapshot();
+    }
+
+    /// The tiles along the way from the start to each staircase, and those either side of them.
+    fn paths_to_stairs(&self, build_data: &mut BuilderMap) -> HashSet<usize> {
+        let mut keep_clear = HashSet::new();
+        let start = match &build_data.starting_position {
+            Some(pos) => build_data.map.xy_idx(pos.x, pos.y),
+            None => return keep_clear,
+        };
+
+        build_data.map.populate_blocked();
+        let map = &build_data.map;
+        let stairs =
+            (0..map.tiles.len()).filter(|idx| matches!(map.tiles[*idx], TileType::DownStairs | TileType::UpStairs));
+        for stair in stairs {
+            let path = rltk::a_star_search(start, stair, map);
+            if !path.success {
+                continue;
+            }
+
+            for idx in path.steps.iter() {
+                let (x, y) = (*idx as i32 % map.width, *idx as i32 / map.width);
+                for ny in y - 1..=y + 1 {
+                    for nx in x - 1..=x + 1 {
+                        keep_clear.insert(map.xy_idx(nx, ny));
+                    }
+                }
+            }
+        }
+        keep_clear.insert(start);
+
+        keep_clear
+    }
+}
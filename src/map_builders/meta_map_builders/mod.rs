@@ -10,10 +10,16 @@ mod area_ending_position;
 mod area_starting_points;
 mod cull_unreachable;
 mod distant_exit;
+mod extra_stairs;
+mod hazard_placement;
+mod validate_level;
 mod voronoi_spawning;
 
 pub use area_ending_position::*;
 pub use area_starting_points::*;
 pub use cull_unreachable::CullUnreachable;
 pub use distant_exit::DistantExit;
+pub use extra_stairs::ExtraStairs;
+pub use hazard_placement::HazardPlacement;
+pub use validate_level::ValidateLevel;
 pub use voronoi_spawning::VoronoiSpawning;
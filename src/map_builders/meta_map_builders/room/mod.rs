@@ -7,6 +7,7 @@ pub mod room_corner_rounding;
 pub mod room_corridor_spawner;
 pub mod room_draw;
 pub mod room_exploder;
+pub mod room_furnisher;
 pub mod room_sorter;
 pub mod rooms_corridors_bsp;
 pub mod rooms_corridors_dogleg;
@@ -20,6 +21,7 @@ pub use room_corner_rounding::RoomCornerRounder;
 pub use room_corridor_spawner::CorridorSpawner;
 pub use room_draw::RoomDrawer;
 pub use room_exploder::RoomExploder;
+pub use room_furnisher::{RoomFurnisher, RoomPurpose};
 pub use room_sorter::{RoomSort, RoomSorter};
 pub use rooms_corridors_bsp::BspCorridors;
 pub use rooms_corridors_dogleg::DoglegCorridors;
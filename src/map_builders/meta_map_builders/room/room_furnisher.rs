@@ -0,0 +1,236 @@
+use std::collections::HashSet;
+
+use super::{BuilderMap, MetaMapBuilder, Rect, TileType};
+use crate::raws::{self, RawMaster, RAWS};
+
+/// How likely a room is to be left as an ordinary room, against the weights of the purposes it could have.
+const PLAIN_WEIGHT: i32 = 6;
+
+/// What a room is used for, which decides how it is furnished and who and what is found in it.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum RoomPurpose {
+    Barracks,
+    Library,
+    Shrine,
+    Storeroom,
+    Kennel,
+    Treasury,
+}
+
+const PURPOSES: [RoomPurpose; 6] = [
+    RoomPurpose::Barracks,
+    RoomPurpose::Library,
+    RoomPurpose::Shrine,
+    RoomPurpose::Storeroom,
+    RoomPurpose::Kennel,
+    RoomPurpose::Treasury,
+];
+
+impl RoomPurpose {
+    /// How likely a room of this area, this deep, is to be put to this use; 0 if it can't be.
+    fn weight(&self, area: i32, depth: i32) -> i32 {
+        match self {
+            RoomPurpose::Barracks if area >= 30 => 4,
+            RoomPurpose::Library if area >= 16 && depth >= 3 => 2,
+            RoomPurpose::Shrine if area <= 30 => 2,
+            RoomPurpose::Storeroom if area <= 40 => 4,
+            RoomPurpose::Kennel if area >= 12 && depth >= 2 => 2,
+            RoomPurpose::Treasury if area <= 24 && depth >= 4 => 1,
+            _ => 0,
+        }
+    }
+
+    /// No more than one room on a level is put to this use.
+    fn unique(&self) -> bool { matches!(self, RoomPurpose::Shrine | RoomPurpose::Treasury) }
+
+    fn furniture(&self, area: i32) -> Vec<&'static str> {
+        match self {
+            RoomPurpose::Barracks => {
+                let mut furniture = vec!["Bed"; i32::clamp(area / 12, 1, 6) as usize];
+                furniture.extend(["Weapon Rack", "Armor Stand", "Table", "Chair"]);
+                furniture
+            },
+            RoomPurpose::Library => vec!["Bookshelf", "Bookshelf", "Bookshelf", "Table", "Chair", "Candle"],
+            RoomPurpose::Shrine => vec!["Altar", "Candle", "Candle"],
+            RoomPurpose::Storeroom => vec!["Cabinet", "Keg", "Keg"],
+            RoomPurpose::Kennel => vec!["Water Trough", "Hide Rack", "Dead Thing"],
+            RoomPurpose::Treasury => vec!["Cabinet", "Armor Stand"],
+        }
+    }
+
+    /// Who might live here. Only those the spawn table could put on the level are ever found.
+    fn residents(&self) -> &'static [&'static str] {
+        match self {
+            RoomPurpose::Barracks => &[
+                "Bandit",
+                "Goblin",
+                "Orc",
+                "Lizardman",
+                "Dark Elf",
+                "Arbat Dark Elf",
+                "Arbat Orc Slave",
+                "Barbo Goblin Archer",
+            ],
+            RoomPurpose::Library => &["Cirro Dark Priestess", "Cirro Dark Elf"],
+            RoomPurpose::Shrine => &["Cirro Dark Priestess", "Spore Zombie"],
+            RoomPurpose::Storeroom => &["Rat", "Kobold", "Gelatinous Cube"],
+            RoomPurpose::Kennel => &[
+                "Mangy Wolf",
+                "Rat",
+                "Bat",
+                "Giant Lizard",
+                "Large Spider",
+                "Cirro Spider",
+            ],
+            RoomPurpose::Treasury => &["Gelatinous Cube", "Rock Golem"],
+        }
+    }
+
+    /// The loot table the room's stash is rolled on, and how many rolls it gets.
+    fn loot(&self, area: i32) -> (&'static str, i32) {
+        match self {
+            RoomPurpose::Barracks => ("Barracks", i32::min(1 + area / 40, 3)),
+            RoomPurpose::Library => ("Library", 2),
+            RoomPurpose::Shrine => ("Shrine", 1),
+            RoomPurpose::Storeroom => ("Storeroom", i32::min(2 + area / 20, 5)),
+            RoomPurpose::Kennel => ("Animal", 2),
+            RoomPurpose::Treasury => ("Treasury", 3),
+        }
+    }
+}
+
+/// Gives rooms a purpose to suit their size and depth, then furnishes and stocks them, and adds a few residents
+/// who belong there, much as the town builder does for its buildings.
+pub struct RoomFurnisher {}
+
+impl MetaMapBuilder for RoomFurnisher {
+    fn build_map(&mut self, build_data: &mut BuilderMap) { self.build(build_data); }
+}
+
+impl RoomFurnisher {
+    #[allow(dead_code)]
+    pub fn new() -> Box<RoomFurnisher> { Box::new(RoomFurnisher {}) }
+
+    fn build(&mut self, build_data: &mut BuilderMap) {
+        let rooms = match &build_data.rooms {
+            Some(rooms) => rooms.clone(),
+            None => return, // Levels without rooms have nothing to furnish
+        };
+
+        // Nothing goes where something already is, or on top of the player
+        let mut taken: HashSet<usize> = build_data.spawn_list.iter().map(|(idx, _)| *idx).collect();
+        if let Some(start) = &build_data.starting_position {
+            taken.insert(build_data.map.xy_idx(start.x, start.y));
+        }
+
+        let raws = RAWS.lock().unwrap();
+        let mut available: Vec<RoomPurpose> = PURPOSES.to_vec();
+        for room in rooms.iter() {
+            let interior = (room.width() - 1) * (room.height() - 1);
+            let floor: Vec<usize> = RoomFurnisher::interior(room, build_data)
+                .into_iter()
+                .filter(|idx| build_data.map.tiles[*idx] == TileType::Floor)
+                .collect();
+
+            // Rooms that later builders have carved up or built over are left alone
+            if interior < 1 || (floor.len() as i32) * 5 < interior * 4 {
+                continue;
+            }
+
+            let purpose = match RoomFurnisher::pick_purpose(&available, interior, build_data.map.depth) {
+                Some(purpose) => purpose,
+                None => continue,
+            };
+            if purpose.unique() {
+                available.retain(|p| *p != purpose);
+            }
+
+            let mut free: Vec<usize> = floor.into_iter().filter(|idx| !taken.contains(idx)).collect();
+
+            // Furniture goes against the walls where it can, so it doesn't stand in the way
+            for name in purpose.furniture(interior) {
+                let by_wall: Vec<usize> = free
+                    .iter()
+                    .copied()
+                    .filter(|idx| build_data.map.open_neighbours(*idx, true).len() < 8)
+                    .collect();
+                let spot = match RoomFurnisher::pick(&by_wall).or_else(|| RoomFurnisher::pick(&free)) {
+                    Some(spot) => spot,
+                    None => break,
+                };
+                RoomFurnisher::place(spot, name, &mut free, &mut taken, build_data);
+            }
+
+            let (table, rolls) = purpose.loot(interior);
+            for _ in 0..rolls {
+                if let (Some(spot), Some(item)) = (RoomFurnisher::pick(&free), raws::get_item_drop(&raws, table)) {
+                    RoomFurnisher::place(spot, &item, &mut free, &mut taken, build_data);
+                }
+            }
+
+            let residents: Vec<&str> = purpose
+                .residents()
+                .iter()
+                .copied()
+                .filter(|name| RoomFurnisher::belongs(&raws, name, build_data))
+                .collect();
+            if !residents.is_empty() {
+                for _ in 0..i32::min(1 + interior / 30, 4) {
+                    let name = residents[(crate::rng::roll_dice(1, residents.len() as i32) - 1) as usize];
+                    if let Some(spot) = RoomFurnisher::pick(&free) {
+                        RoomFurnisher::place(spot, name, &mut free, &mut taken, build_data);
+                    }
+                }
+            }
+
+            build_data.room_purposes.push((*room, purpose));
+        }
+
+        build_data.take_snapshot();
+    }
+
+    /// The tiles inside a room's walls.
+    fn interior(room: &Rect, build_data: &BuilderMap) -> Vec<usize> {
+        let mut tiles = Vec::new();
+        for y in room.y1 + 1..room.y2 {
+            for x in room.x1 + 1..room.x2 {
+                if x > 0 && x < build_data.map.width - 1 && y > 0 && y < build_data.map.height - 1 {
+                    tiles.push(build_data.map.xy_idx(x, y));
+                }
+            }
+        }
+        tiles
+    }
+
+    /// Picks a purpose by weight, or none at all for an ordinary room.
+    fn pick_purpose(available: &[RoomPurpose], area: i32, depth: i32) -> Option<RoomPurpose> {
+        let total: i32 = available.iter().map(|p| p.weight(area, depth)).sum::<i32>() + PLAIN_WEIGHT;
+        let mut roll = crate::rng::roll_dice(1, total);
+        for purpose in available.iter() {
+            let weight = purpose.weight(area, depth);
+            if roll <= weight {
+                return Some(*purpose);
+            }
+            roll -= weight;
+        }
+        None
+    }
+
+    /// Whether the spawn table could put something on this level, so a room's residents suit the depth.
+    fn belongs(raws: &RawMaster, name: &str, build_data: &BuilderMap) -> bool {
+        raws::spawns_at_depth(raws, name, build_data.map.branch, build_data.map.depth)
+    }
+
+    fn pick(tiles: &[usize]) -> Option<usize> {
+        match tiles.len() {
+            0 => None,
+            n => Some(tiles[(crate::rng::roll_dice(1, n as i32) - 1) as usize]),
+        }
+    }
+
+    fn place(idx: usize, name: &str, free: &mut Vec<usize>, taken: &mut HashSet<usize>, build_data: &mut BuilderMap) {
+        free.retain(|i| *i != idx);
+        taken.insert(idx);
+        build_data.spawn_list.push((idx, name.to_string()));
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::{HashSet, VecDeque};
+
+use super::{BuilderMap, MetaMapBuilder, TileType};
+use crate::map::{endangers, tile_walkable, LevelCheck, Map};
+
+/// Less open ground than this, as a share of the whole map, and the level isn't worth exploring.
+const MIN_FLOOR_PERCENT: usize = 10;
+
+/// Runs the checks every level has to pass, and records the outcome in the map's report. It changes nothing
+/// else; what to do about a level that fails is up to the chain.
+pub struct ValidateLevel {}
+
+impl MetaMapBuilder for ValidateLevel {
+    fn build_map(&mut self, build_data: &mut BuilderMap) { self.build(build_data); }
+}
+
+impl ValidateLevel {
+    pub fn new() -> Box<ValidateLevel> { Box::new(ValidateLevel {}) }
+
+    fn build(&mut self, build_data: &mut BuilderMap) {
+        let map = &build_data.map;
+        let start = build_data
+            .starting_position
+            .as_ref()
+            .filter(|start| start.x > 0 && start.y > 0 && start.x < map.width - 1 && start.y < map.height - 1)
+            .map(|start| map.xy_idx(start.x, start.y))
+            .filter(|idx| safe(map, *idx));
+
+        let exit_reachable = start.is_some_and(|start| {
+            let reached = reachable(map, start, &HashSet::new());
+            (0..map.tiles.len()).any(|idx| reached[idx] && map.tiles[idx] == TileType::DownStairs)
+        });
+        let floor = map.tiles.iter().filter(|tt| tile_walkable(**tt)).count();
+        let spawns_walkable = build_data
+            .spawn_list
+            .iter()
+            .all(|(idx, _)| *idx < map.tiles.len() && tile_walkable(map.tiles[*idx]));
+        let keys_reachable = build_data.locks.is_empty()
+            || start.is_some_and(|start| {
+                let locked: HashSet<usize> = build_data.locks.iter().map(|lock| lock.door).collect();
+                let reached = reachable(map, start, &locked);
+                let spawned =
+                    |idx: usize, name: &str| build_data.spawn_list.iter().any(|(i, n)| *i == idx && n == name);
+                build_data
+                    .locks
+                    .iter()
+                    .all(|lock| reached[lock.key] && spawned(lock.key, "Key") && spawned(lock.door, "Locked Door"))
+            });
+
+        let report = &mut build_data.map.report;
+        report.passed.clear();
+        report.failed.clear();
+        for (check, ok) in [
+            (LevelCheck::Start, start.is_some()),
+            (LevelCheck::ExitReachable, exit_reachable),
+            (
+                LevelCheck::FloorArea,
+                floor * 100 >= build_data.map.tiles.len() * MIN_FLOOR_PERCENT,
+            ),
+            (LevelCheck::SpawnsWalkable, spawns_walkable),
+            (LevelCheck::KeysReachable, keys_reachable),
+        ] {
+            if ok {
+                report.passed.push(check);
+            } else {
+                report.failed.push(check);
+            }
+        }
+    }
+}
+
+/// Can the tile be stood on without coming to harm?
+fn safe(map: &Map, idx: usize) -> bool { tile_walkable(map.tiles[idx]) && !endangers(map.tiles[idx], None) }
+
+/// Every tile that can be walked to from `start` over safe ground, moving as the player does, without going through
+/// any of the `shut` ones.
+fn reachable(map: &Map, start: usize, shut: &HashSet<usize>) -> Vec<bool> {
+    let mut reached = vec![false; map.tiles.len()];
+    let mut open = VecDeque::new();
+    reached[start] = true;
+    open.push_back(start);
+
+    while let Some(idx) = open.pop_front() {
+        let (x, y) = (idx as i32 % map.width, idx as i32 / map.width);
+        for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (1, -1), (-1, 1), (1, 1)] {
+            let (nx, ny) = (x + dx, y + dy);
+            if nx < 0 || ny < 0 || nx >= map.width || ny >= map.height {
+                continue;
+            }
+            let n = map.xy_idx(nx, ny);
+            if !reached[n] && safe(map, n) && !shut.contains(&n) {
+                reached[n] = true;
+                open.push_back(n);
+            }
+        }
+    }
+
+    reached
+}
@@ -1,5 +1,7 @@
 // Externals
 use super::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 mod common;
 use common::*;
@@ -12,15 +14,31 @@ use self::meta_map_builders::*;
 mod builders;
 use self::builders::*;
 
+// Chains described in data
+mod data_chain;
+use self::data_chain::pick_level_chain;
+pub use self::data_chain::{check_level_chains, covers_depth};
+use crate::raws::structs::LevelChain;
+
+// Checking the finished level
+mod validation;
+
 pub struct BuilderMap {
     pub spawn_list: Vec<(usize, String)>,
     pub map: Map,
     pub starting_position: Option<Position>,
     pub rooms: Option<Vec<Rect>>,
+    /// The rooms that have been given a use, and what it is.
+    pub room_purposes: Vec<(Rect, RoomPurpose)>,
+    /// The doors that have been locked, where their keys are, and what they shut away.
+    pub locks: Vec<Lock>,
     pub corridors: Option<Vec<Vec<usize>>>,
     pub history: Vec<Map>,
     pub width: i32,
     pub height: i32,
+    /// Raised to cut the build short. Builders that can run long, like wave function collapse, give up and leave
+    /// the map as they found it, and a level that then fails its checks isn't built again.
+    pub cancel: Arc<AtomicBool>,
 }
 
 impl BuilderMap {
@@ -38,6 +56,12 @@ impl BuilderMap {
 pub struct BuilderChain {
     starter: Option<Box<dyn InitialMapBuilder>>,
     builders: Vec<Box<dyn MetaMapBuilder>>,
+    daylight: bool,
+    theme: Option<String>,
+    /// Whether the finished level is checked over; only chains that make a whole level to play on are.
+    validate: bool,
+    /// The description the chain was assembled from, so it can be rolled again if the level fails its checks.
+    recipe: Option<LevelChain>,
     pub build_data: BuilderMap,
 }
 
@@ -46,15 +70,22 @@ impl BuilderChain {
         BuilderChain {
             starter: None,
             builders: Vec::new(),
+            daylight: false,
+            theme: None,
+            validate: false,
+            recipe: None,
             build_data: BuilderMap {
                 spawn_list: Vec::new(),
                 map: Map::new(new_depth, width, height, name),
                 starting_position: None,
                 rooms: None,
+                room_purposes: Vec::new(),
+                locks: Vec::new(),
                 corridors: None,
                 history: Vec::new(),
                 width,
                 height,
+                cancel: Arc::new(AtomicBool::new(false)),
             },
         }
     }
@@ -68,7 +99,19 @@ impl BuilderChain {
 
     pub fn with(&mut self, metabuilder: Box<dyn MetaMapBuilder>) { self.builders.push(metabuilder); }
 
+    /// A flag that cuts the build short once raised, from another thread if need be.
+    pub fn cancel_flag(&self) -> Arc<AtomicBool> { self.build_data.cancel.clone() }
+
     pub fn build_map(&mut self) {
+        // Any rebuilds are seeded from this, not from wherever a failed attempt left the generator
+        let seed = crate::rng::range(1, i32::MAX) as u64;
+        self.run_builders();
+        if self.validate {
+            self.validate_level(seed);
+        }
+    }
+
+    fn run_builders(&mut self) {
         match &mut self.starter {
             None => panic!("Cannot run a map builder chain without a starting build system"),
             Some(starter) => {
@@ -81,11 +124,45 @@ impl BuilderChain {
         for metabuilder in self.builders.iter_mut() {
             metabuilder.build_map(&mut self.build_data);
         }
+
+        // Set last, since some builders start the map over
+        if self.daylight {
+            self.build_data.map.daylight = true;
+        }
+        if let Some(theme) = &self.theme {
+            self.build_data.map.theme = Some(theme.clone());
+        }
+
+        // Levels from the data chains are held to the spawn table's limits, once every piece of them is in place
+        if let (Some(_), Some(start)) = (&self.recipe, &self.build_data.starting_position) {
+            let start = self.build_data.map.xy_idx(start.x, start.y);
+            spawner::spawn_quotas(&self.build_data.map, start, &mut self.build_data.spawn_list);
+        }
     }
 
     pub fn spawn_entities(&mut self, ecs: &mut World) {
+        let mut spawned: Vec<(usize, Entity)> = Vec::new();
         for entity in self.build_data.spawn_list.iter() {
-            spawner::spawn_entity(ecs, &(&entity.0, &entity.1));
+            if let Some(new_entity) = spawner::spawn_entity(ecs, &(&entity.0, &entity.1)) {
+                spawned.push((entity.0, new_entity));
+            }
+        }
+
+        // Pair each locked door with its key
+        let mut doors = ecs.write_storage::<Door>();
+        let mut keys = ecs.write_storage::<Key>();
+        let items = ecs.read_storage::<Item>();
+        for lock in self.build_data.locks.iter() {
+            for (idx, entity) in spawned.iter() {
+                if *idx == lock.door {
+                    if let Some(door) = doors.get_mut(*entity) {
+                        door.key = Some(lock.id);
+                    }
+                }
+                if *idx == lock.key && items.get(*entity).is_some() {
+                    keys.insert(*entity, Key { id: lock.id }).expect("Unable to insert key");
+                }
+            }
         }
     }
 }
@@ -98,167 +175,49 @@ pub trait MetaMapBuilder {
     fn build_map(&mut self, build_data: &mut BuilderMap);
 }
 
-fn random_start_position() -> (XStart, YStart) {
-    let xroll = crate::rng::roll_dice(1, 3);
-    let x = match xroll {
-        1 => XStart::Left,
-        2 => XStart::Center,
-        _ => XStart::Right,
-    };
-
-    let yroll = crate::rng::roll_dice(1, 3);
-    let y = match yroll {
-        1 => YStart::Bottom,
-        2 => YStart::Center,
-        _ => YStart::Top,
-    };
-
-    (x, y)
+pub fn file_builder(level: crate::map::LevelFile) -> BuilderChain {
+    let mut builder = BuilderChain::new(level.depth, level.width(), level.height(), &level.name);
+    builder.start_with(FileMapBuilder::new(level));
+    builder.validate = true;
+    builder
 }
 
-fn random_room_builder(builder: &mut BuilderChain) {
-    let build_roll = crate::rng::roll_dice(1, 3);
-    match build_roll {
-        1 => builder.start_with(SimpleMapBuilder::new()),
-        2 => builder.start_with(BspDungeonBuilder::new()),
-        _ => builder.start_with(BspInteriorBuilder::new()),
-    }
-
-    // BSP Interior still makes holes in the walls
-    if build_roll != 3 {
-        // Sort by one of the 5 available algorithms
-        let sort_roll = crate::rng::roll_dice(1, 5);
-        match sort_roll {
-            1 => builder.with(RoomSorter::new(RoomSort::Left)),
-            2 => builder.with(RoomSorter::new(RoomSort::Right)),
-            3 => builder.with(RoomSorter::new(RoomSort::Top)),
-            4 => builder.with(RoomSorter::new(RoomSort::Bottom)),
-            _ => builder.with(RoomSorter::new(RoomSort::Central)),
-        }
-
-        builder.with(RoomDrawer::new());
-
-        let corridor_roll = crate::rng::roll_dice(1, 4);
-        match corridor_roll {
-            1 => builder.with(DoglegCorridors::new()),
-            2 => builder.with(NearestCorridors::new()),
-            3 => builder.with(StraightLineCorridors::new()),
-            _ => builder.with(BspCorridors::new()),
-        }
-
-        let cspawn_roll = crate::rng::roll_dice(1, 2);
-        if cspawn_roll == 1 {
-            builder.with(CorridorSpawner::new());
-        }
-
-        let modifier_roll = crate::rng::roll_dice(1, 6);
-        match modifier_roll {
-            1 => builder.with(RoomExploder::new()),
-            2 => builder.with(RoomCornerRounder::new()),
-            _ => {},
-        }
-    }
-
-    let start_roll = crate::rng::roll_dice(1, 2);
-    match start_roll {
-        1 => builder.with(RoomBasedStartingPosition::new()),
-        _ => {
-            let (start_x, start_y) = random_start_position();
-            builder.with(AreaStartingPosition::new(start_x, start_y));
+/// Builds a level from one of the chains in `level_chains.json` that can appear at this depth of the branch. The
+/// level is the size the chain asks for, unless a `size` (width, height) is given instead.
+pub fn level_builder(branch: usize, new_depth: i32, size: Option<(i32, i32)>) -> BuilderChain {
+    match pick_level_chain(branch, new_depth) {
+        Some(chain) => {
+            let (width, height) = size.unwrap_or((chain.width, chain.height));
+            let mut builder = BuilderChain::from_raw(&chain, new_depth, width, height);
+            builder.build_data.map.branch = branch;
+            builder
+        },
+        // The raws are checked for this when they load, but a depth can still be asked for that no level is at
+        None => {
+            rltk::console::log(format!(
+                "No level chain covers depth {} of branch {}, using the fallback level",
+                new_depth, branch
+            ));
+            let (width, height) = size.unwrap_or((80, 50));
+            let mut builder = BuilderChain::new(new_depth, width, height, "New Map");
+            builder.start_with(FallbackBuilder::new());
+            builder.with(VoronoiSpawning::new());
+            builder.build_data.map.branch = branch;
+            builder
         },
     }
-
-    let exit_roll = crate::rng::roll_dice(1, 2);
-    match exit_roll {
-        1 => builder.with(RoomBasedStairs::new()),
-        _ => builder.with(DistantExit::new()),
-    }
-
-    let spawn_roll = crate::rng::roll_dice(1, 2);
-    match spawn_roll {
-        1 => builder.with(RoomBasedSpawner::new()),
-        _ => builder.with(VoronoiSpawning::new()),
-    }
-}
-
-fn random_shape_builder(builder: &mut BuilderChain) {
-    let builder_roll = crate::rng::roll_dice(1, 16);
-    match builder_roll {
-        1 => builder.start_with(CellularAutomataBuilder::new()),
-        2 => builder.start_with(DrunkardsWalkBuilder::open_area()),
-        3 => builder.start_with(DrunkardsWalkBuilder::open_halls()),
-        4 => builder.start_with(DrunkardsWalkBuilder::winding_passages()),
-        5 => builder.start_with(DrunkardsWalkBuilder::fat_passages()),
-        6 => builder.start_with(DrunkardsWalkBuilder::fearful_symmetry()),
-        7 => builder.start_with(MazeBuilder::new()),
-        8 => builder.start_with(DLABuilder::walk_inwards()),
-        9 => builder.start_with(DLABuilder::walk_outwards()),
-        10 => builder.start_with(DLABuilder::central_attractor()),
-        11 => builder.start_with(DLABuilder::insectoid()),
-        12 => builder.start_with(VoronoiCellBuilder::pythagoras()),
-        13 => builder.start_with(VoronoiCellBuilder::manhattan()),
-        _ => builder.start_with(PrefabBuilder::constant(prefab_builder::prefab_levels::WFC_POPULATED)),
-    }
-
-    // Set the start to the center and cull
-    builder.with(AreaStartingPosition::new(XStart::Center, YStart::Center));
-    builder.with(CullUnreachable::new());
-
-    // Now set the start to a random starting area
-    let (start_x, start_y) = random_start_position();
-    builder.with(AreaStartingPosition::new(start_x, start_y));
-
-    // Setup an exit and spawn mobs
-    builder.with(VoronoiSpawning::new());
-    builder.with(DistantExit::new());
-}
-
-pub fn random_builder(new_depth: i32, width: i32, height: i32) -> BuilderChain {
-    let mut builder = BuilderChain::new(new_depth, width, height, "New Map");
-    let type_roll = crate::rng::roll_dice(1, 2);
-    match type_roll {
-        1 => random_room_builder(&mut builder),
-        _ => random_shape_builder(&mut builder),
-    }
-
-    if crate::rng::roll_dice(1, 3) == 1 {
-        builder.with(WaveformCollapseBuilder::new());
-
-        // Now set the start to a random starting area
-        let (start_x, start_y) = random_start_position();
-        builder.with(AreaStartingPosition::new(start_x, start_y));
-
-        // Setup an exit and spawn mobs
-        builder.with(VoronoiSpawning::new());
-        builder.with(DistantExit::new());
-    }
-
-    if crate::rng::roll_dice(1, 20) == 1 {
-        builder.with(PrefabBuilder::sectional(
-            prefab_builder::prefab_sections::UNDERGROUND_FORT,
-        ));
-    }
-
-    builder.with(DoorPlacement::new());
-    builder.with(PrefabBuilder::vaults());
-
-    builder
 }
 
-pub fn level_builder(new_depth: i32, width: i32, height: i32) -> BuilderChain {
-    rltk::console::log(format!("Depth: {}", new_depth));
-
-    match new_depth {
-        1 => town_builder(new_depth, width, height),
-        2 => forest_builder(new_depth, width, height),
-        3 => limestone_cavern_builder(new_depth, width, height),
-        4 => limestone_deep_cavern_builder(new_depth, width, height),
-        5 => limestone_transition_builder(new_depth, width, height),
-        6 => dwarf_fort_builder(new_depth, width, height),
-        7 => mushroom_entrance(new_depth, width, height),
-        8 => mushroom_builder(new_depth, width, height),
-        9 => mushroom_exit(new_depth, width, height),
-        10 => dark_elf_city(new_depth, width, height),
-        _ => random_builder(new_depth, width, height),
-    }
+/// Builds a level from the chain with this name, whatever depth it would normally appear at.
+pub fn named_builder(name: &str, new_depth: i32, size: Option<(i32, i32)>) -> Option<BuilderChain> {
+    let (chain, branch) = {
+        let raws = RAWS.lock().unwrap();
+        let chain = raws::get_level_chain(&raws, name)?;
+        let branch = raws::branch_of(&raws, &chain.branch);
+        (chain, branch)
+    };
+    let (width, height) = size.unwrap_or((chain.width, chain.height));
+    let mut builder = BuilderChain::from_raw(&chain, new_depth, width, height);
+    builder.build_data.map.branch = branch;
+    Some(builder)
 }
@@ -0,0 +1,73 @@
+use super::*;
+use crate::map::LevelCheck;
+
+/// How many times a chain is built, counting the first, before it is given up on for the fallback.
+const MAX_ATTEMPTS: i32 = 5;
+
+impl BuilderChain {
+    /// Checks the finished level over. One that fails is rolled again from the same chain with a new seed, up to
+    /// `MAX_ATTEMPTS` times in all, and is then swapped for a plain hall that can't fail. A hand-made level has
+    /// nothing to roll again, so it is only reported on. Each new seed comes from `seed` and the attempt, so the
+    /// same `seed` always ends in the same level. Once the build has been cancelled, a failed level goes straight to
+    /// the fallback.
+    pub(super) fn validate_level(&mut self, seed: u64) {
+        ValidateLevel::new().build_map(&mut self.build_data);
+        let mut attempts = 1;
+
+        if let Some(recipe) = self.recipe.clone() {
+            while !self.build_data.map.report.failed.is_empty()
+                && attempts < MAX_ATTEMPTS
+                && !self.build_data.cancel.load(Ordering::Relaxed)
+            {
+                rltk::console::log(format!(
+                    "{} failed its checks ({}), building it again",
+                    recipe.name,
+                    describe(&self.build_data.map.report.failed)
+                ));
+                crate::rng::reseed(seed.wrapping_mul(MAX_ATTEMPTS as u64).wrapping_add(attempts as u64));
+                let (depth, width, height) = (self.build_data.map.depth, self.build_data.width, self.build_data.height);
+                self.rebuild(BuilderChain::from_raw(&recipe, depth, width, height));
+                attempts += 1;
+            }
+
+            if !self.build_data.map.report.failed.is_empty() {
+                rltk::console::log(format!(
+                    "{} kept failing its checks, using the fallback level",
+                    recipe.name
+                ));
+                let (depth, width, height) = (self.build_data.map.depth, self.build_data.width, self.build_data.height);
+                let mut fallback = BuilderChain::new(depth, width, height, &recipe.name);
+                fallback.start_with(FallbackBuilder::new());
+                fallback.with(VoronoiSpawning::new());
+                fallback.daylight = recipe.daylight;
+                fallback.theme = recipe.theme.clone();
+                self.rebuild(fallback);
+                self.build_data.map.report.fallback = true;
+            }
+        }
+
+        self.build_data.map.report.attempts = attempts;
+    }
+
+    /// Replaces the level with what another chain builds, in the same place in the dungeon.
+    fn rebuild(&mut self, mut other: BuilderChain) {
+        other.build_data.map.branch = self.build_data.map.branch;
+        other.build_data.cancel = self.build_data.cancel.clone();
+        other.run_builders();
+        ValidateLevel::new().build_map(&mut other.build_data);
+
+        // Keep the failed attempts in the history, so the visualizer shows them too
+        let mut history = std::mem::take(&mut self.build_data.history);
+        history.append(&mut other.build_data.history);
+        self.build_data = other.build_data;
+        self.build_data.history = history;
+    }
+}
+
+fn describe(checks: &[LevelCheck]) -> String {
+    checks
+        .iter()
+        .map(|check| check.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
@@ -0,0 +1,128 @@
+use crate::gamesystem;
+use crate::prelude::*;
+
+/// What a roll to pick a lock has to reach.
+const LOCKPICK_DIFFICULTY: i32 = 15;
+/// What a roll to break a locked door down has to reach.
+const BASH_DIFFICULTY: i32 = 17;
+
+/// The locked door the player would walk into, if there is one they know about.
+pub(super) fn locked_door_at(delta_x: i32, delta_y: i32, ecs: &World) -> Option<Entity> {
+    let player_pos = ecs.fetch::<Point>();
+    let map = ecs.fetch::<Map>();
+    let (x, y) = (player_pos.x + delta_x, player_pos.y + delta_y);
+    if x < 1 || x > map.width - 1 || y < 1 || y > map.height - 1 {
+        return None;
+    }
+
+    let doors = ecs.read_storage::<Door>();
+    let hidden = ecs.read_storage::<Hidden>();
+    crate::spatial::get_tile_content_clone(map.xy_idx(x, y))
+        .into_iter()
+        .find(|e| hidden.get(*e).is_none() && doors.get(*e).is_some_and(|door| door.locked))
+}
+
+/// Leaves a door open for good, with nothing in the way.
+fn open_door(door: Entity, ecs: &mut World) {
+    if let Some(door) = ecs.write_storage::<Door>().get_mut(door) {
+        door.open = true;
+        door.locked = false;
+    }
+    ecs.write_storage::<BlocksVisibility>().remove(door);
+    ecs.write_storage::<BlocksTile>().remove(door);
+    if let Some(glyph) = ecs.write_storage::<Renderable>().get_mut(door) {
+        glyph.glyph = rltk::to_cp437('/');
+    }
+
+    let player_entity = *ecs.fetch::<Entity>();
+    if let Some(viewshed) = ecs.write_storage::<Viewshed>().get_mut(player_entity) {
+        viewshed.dirty = true;
+    }
+}
+
+/// Opens a locked door with its key, if the player has it, and otherwise spends the turn trying to pick the lock.
+pub(super) fn try_unlock_door(door: Entity, ecs: &mut World) -> RunState {
+    let player_entity = *ecs.fetch::<Entity>();
+    let key_id = ecs.read_storage::<Door>().get(door).and_then(|door| door.key);
+
+    let key = key_id.and_then(|id| {
+        let entities = ecs.entities();
+        let keys = ecs.read_storage::<Key>();
+        let backpack = ecs.read_storage::<InBackpack>();
+        (&entities, &keys, &backpack)
+            .join()
+            .find(|(_, key, carried)| key.id == id && carried.owner == player_entity)
+            .map(|(entity, _, _)| entity)
+    });
+
+    // The key stays in the lock
+    if let Some(key) = key {
+        ecs.entities().delete(key).expect("Unable to delete key");
+        ecs.write_storage::<EquipmentChanged>()
+            .insert(player_entity, EquipmentChanged {})
+            .expect("Unable to insert");
+        crate::gamelog::Logger::new()
+            .append("You unlock the door with the key.")
+            .log();
+        open_door(door, ecs);
+        return RunState::Ticking;
+    }
+
+    let lockpicking = ecs
+        .read_storage::<Skills>()
+        .get(player_entity)
+        .map_or(0, |skills| gamesystem::skill_bonus(Skill::Lockpicking, skills));
+    let quickness = ecs
+        .read_storage::<Attributes>()
+        .get(player_entity)
+        .map_or(0, |attributes| attributes.quickness.bonus);
+
+    if crate::rng::roll_dice(1, 20) + lockpicking + quickness >= LOCKPICK_DIFFICULTY {
+        crate::gamelog::Logger::new().append("You pick the lock.").log();
+        open_door(door, ecs);
+    } else {
+        crate::gamelog::Logger::new()
+            .append("The door is locked, and you fail to pick it.")
+            .log();
+    }
+
+    RunState::Ticking
+}
+
+/// Throws the player's weight against a locked door, which may give way. It takes a turn either way.
+pub(super) fn try_bash_door(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState {
+    let door = match locked_door_at(delta_x, delta_y, ecs) {
+        Some(door) => door,
+        None => {
+            crate::gamelog::Logger::new()
+                .append("There is no locked door there to break down.")
+                .log();
+            return RunState::AwaitingInput;
+        },
+    };
+
+    let player_entity = *ecs.fetch::<Entity>();
+    let might = ecs
+        .read_storage::<Attributes>()
+        .get(player_entity)
+        .map_or(0, |attributes| attributes.might.bonus);
+
+    if crate::rng::roll_dice(1, 20) + might >= BASH_DIFFICULTY {
+        if let Some(pos) = ecs.read_storage::<Position>().get(door) {
+            let idx = ecs.fetch::<Map>().xy_idx(pos.x, pos.y);
+            crate::spatial::remove_entity(door, idx);
+        }
+        ecs.entities().delete(door).expect("Unable to delete door");
+        crate::gamelog::Logger::new().append("You smash the door down!").log();
+
+        if let Some(viewshed) = ecs.write_storage::<Viewshed>().get_mut(player_entity) {
+            viewshed.dirty = true;
+        }
+    } else {
+        crate::gamelog::Logger::new()
+            .append("The door shudders, but holds.")
+            .log();
+    }
+
+    RunState::Ticking
+}
@@ -1,7 +1,16 @@
 use std::cmp::{max, min};
 
+use crate::gamesystem;
 use crate::prelude::*;
 
+mod doors;
+use doors::{locked_door_at, try_bash_door, try_unlock_door};
+
+/// How far from the player a search reaches.
+const SEARCH_RANGE: f32 = 2.0;
+/// What a search roll has to reach to turn up something hidden.
+const SEARCH_DIFFICULTY: i32 = 15;
+
 fn get_player_target_list(ecs: &mut World) -> Vec<(f32, Entity)> {
     let viewsheds = ecs.read_storage::<Viewshed>();
     let equipped = ecs.read_storage::<Equipped>();
@@ -37,6 +46,18 @@ fn get_player_target_list(ecs: &mut World) -> Vec<(f32, Entity)> {
     }
 
     possible_targets.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    // Large creatures show up on every tile they cover; only keep their nearest
+    let mut seen: Vec<Entity> = Vec::new();
+    possible_targets.retain(|(_, target)| {
+        if seen.contains(target) {
+            false
+        } else {
+            seen.push(*target);
+            true
+        }
+    });
+
     possible_targets
 }
 
@@ -75,12 +96,12 @@ fn fire_on_target(ecs: &mut World) -> RunState {
             .insert(*player_entity, WantsToShoot { target })
             .expect("Insert Fail");
 
-        return RunState::Ticking;
+        RunState::Ticking
     } else {
         crate::gamelog::Logger::new()
             .append("You don't have a target selected!")
             .log();
-        return RunState::AwaitingInput;
+        RunState::AwaitingInput
     }
 }
 
@@ -119,12 +140,20 @@ fn cycle_target(ecs: &mut World) {
 }
 
 pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState {
+    // A locked door has to be dealt with before anyone can get through it
+    if let Some(door) = locked_door_at(delta_x, delta_y, ecs) {
+        return try_unlock_door(door, ecs);
+    }
+
     let mut positions = ecs.write_storage::<Position>();
     let mut viewsheds = ecs.write_storage::<Viewshed>();
     let combat_stats = ecs.read_storage::<Attributes>();
     let players = ecs.read_storage::<Player>();
     let factions = ecs.read_storage::<Faction>();
     let vendors = ecs.read_storage::<Vendor>();
+    let sizes = ecs.read_storage::<TileSize>();
+    let equipped = ecs.read_storage::<Equipped>();
+    let diggers = ecs.read_storage::<Digs>();
 
     let mut wants_to_melee = ecs.write_storage::<WantsToMelee>();
     let mut entity_moved = ecs.write_storage::<EntityMoved>();
@@ -132,6 +161,7 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
     let mut blocks_visibility = ecs.write_storage::<BlocksVisibility>();
     let mut blocks_movement = ecs.write_storage::<BlocksTile>();
     let mut renderables = ecs.write_storage::<Renderable>();
+    let hidden = ecs.read_storage::<Hidden>();
 
     let entities = ecs.entities();
     let map = ecs.fetch::<Map>();
@@ -169,6 +199,11 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
                 }
             }
             if !hostile {
+                // Large bystanders can't squeeze into our spot, so they just stay in the way
+                if sizes.get(potential_target).is_some() {
+                    return None;
+                }
+
                 // Note that we want to move the bystander
                 swap_entities.push((potential_target, pos.x, pos.y));
 
@@ -204,7 +239,10 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
                 }
             }
 
-            let door = doors.get_mut(potential_target);
+            // A secret door is just more wall until it has been found
+            let door = doors
+                .get_mut(potential_target)
+                .filter(|_| hidden.get(potential_target).is_none());
             if let Some(door) = door {
                 door.open = true;
 
@@ -221,12 +259,27 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
             None
         });
 
+        // Walking into rock with a pickaxe in hand tunnels through it
+        if result == RunState::AwaitingInput && map.can_dig(destination_idx) {
+            if let Some((_, digs)) = (&equipped, &diggers).join().find(|(e, _)| e.owner == entity) {
+                add_effect(
+                    Some(entity),
+                    EffectType::Dig { into: digs.into },
+                    Targets::Tile {
+                        tile_idx: destination_idx as i32,
+                    },
+                );
+                crate::gamelog::Logger::new().append("You dig through the rock.").log();
+                result = RunState::Ticking;
+            }
+        }
+
         if !crate::spatial::is_blocked(destination_idx) {
             let old_idx = map.xy_idx(pos.x, pos.y);
-            pos.x = min(map.width - 1, max(0, pos.x + delta_x));
-            pos.y = min(map.height - 1, max(0, pos.y + delta_y));
+            let new_idx = map.slide(old_idx, destination_idx);
+            pos.x = new_idx as i32 % map.width;
+            pos.y = new_idx as i32 / map.width;
 
-            let new_idx = map.xy_idx(pos.x, pos.y);
             entity_moved
                 .insert(entity, EntityMoved {})
                 .expect("Unable to insert marker");
@@ -240,7 +293,7 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState
             ppos.y = pos.y;
             result = RunState::Ticking;
 
-            match map.tiles[destination_idx] {
+            match map.tiles[new_idx] {
                 TileType::DownStairs => result = RunState::NextLevel,
                 TileType::UpStairs => result = RunState::PreviousLevel,
                 _ => {},
@@ -330,14 +383,15 @@ fn get_item(ecs: &mut World) {
     }
 }
 
-fn skip_turn(ecs: &mut World) -> RunState {
+/// Whether anything that would attack the player is in sight.
+fn enemies_in_view(ecs: &World) -> bool {
     let player_entity = ecs.fetch::<Entity>();
     let viewshed_components = ecs.read_storage::<Viewshed>();
     let factions = ecs.read_storage::<Faction>();
 
     let worldmap_resource = ecs.fetch::<Map>();
 
-    let mut can_heal = true;
+    let mut enemies = false;
     let viewshed = viewshed_components.get(*player_entity).unwrap();
     for tile in viewshed.visible_tiles.iter() {
         let idx = worldmap_resource.xy_idx(tile.x, tile.y);
@@ -349,13 +403,20 @@ fn skip_turn(ecs: &mut World) -> RunState {
                 Some(faction) => {
                     let reaction = raws::faction_reaction(&faction.name, "Player", &raws::RAWS.lock().unwrap());
                     if reaction == raws::structs::Reaction::Attack {
-                        can_heal = false;
+                        enemies = true;
                     }
                 },
             }
         });
     }
 
+    enemies
+}
+
+fn skip_turn(ecs: &mut World) -> RunState {
+    let mut can_heal = !enemies_in_view(ecs);
+    let player_entity = ecs.fetch::<Entity>();
+
     let hunger_clocks = ecs.read_storage::<HungerClock>();
     let hc = hunger_clocks.get(*player_entity);
     if let Some(hc) = hc {
@@ -379,6 +440,82 @@ fn skip_turn(ecs: &mut World) -> RunState {
     RunState::Ticking
 }
 
+/// Sets off overland for another area of the overworld - only from the surface, and never with enemies in sight.
+fn try_travel(ecs: &mut World) -> RunState {
+    let area = {
+        let map = ecs.fetch::<Map>();
+        let dm = ecs.fetch::<MasterDungeonMap>();
+        Some(map.area).filter(|area| map.on_surface() && dm.area(*area).is_some())
+    };
+
+    match area {
+        None => {
+            crate::gamelog::Logger::new()
+                .append("You can only travel overland from the surface.")
+                .log();
+            RunState::AwaitingInput
+        },
+        Some(_) if enemies_in_view(ecs) => {
+            crate::gamelog::Logger::new()
+                .append("You can't travel with enemies nearby!")
+                .log();
+            RunState::AwaitingInput
+        },
+        Some(at) => RunState::Travel { at, legs: 0 },
+    }
+}
+
+/// Spends a turn going over everything within reach, with a Perception roll to turn up each hidden thing there -
+/// much better odds than noticing it in passing.
+fn search(ecs: &mut World) -> RunState {
+    let player_entity = ecs.fetch::<Entity>();
+    let player_pos = ecs.fetch::<Point>();
+    let skills = ecs.read_storage::<Skills>();
+    let positions = ecs.read_storage::<Position>();
+    let names = ecs.read_storage::<Name>();
+    let mut hidden = ecs.write_storage::<Hidden>();
+    let entities = ecs.entities();
+    let mut map = ecs.fetch_mut::<Map>();
+
+    let perception = skills
+        .get(*player_entity)
+        .map_or(0, |skills| gamesystem::skill_bonus(Skill::Perception, skills));
+
+    let mut found: Vec<Entity> = Vec::new();
+    for (entity, pos, _hidden) in (&entities, &positions, &hidden).join() {
+        let distance = rltk::DistanceAlg::Pythagoras.distance2d(*player_pos, Point::new(pos.x, pos.y));
+        if distance <= SEARCH_RANGE
+            && map.visible_tiles[map.xy_idx(pos.x, pos.y)]
+            && crate::rng::roll_dice(1, 20) + perception >= SEARCH_DIFFICULTY
+        {
+            found.push(entity);
+        }
+    }
+
+    if found.is_empty() {
+        crate::gamelog::Logger::new()
+            .append("You search around, but find nothing.")
+            .log();
+    }
+
+    for entity in found {
+        if let Some(name) = names.get(entity) {
+            crate::gamelog::Logger::new()
+                .append("You found:")
+                .append_with_color(&name.name, rltk::RED)
+                .log();
+        }
+
+        if let Some(pos) = positions.get(entity) {
+            let idx = map.xy_idx(pos.x, pos.y);
+            map.disguised.remove(&idx);
+        }
+        hidden.remove(entity);
+    }
+
+    RunState::Ticking
+}
+
 fn use_consumable_hotkey(gs: &mut State, key: i32) -> RunState {
     let consumables = gs.ecs.read_storage::<Consumable>();
     let backpack = gs.ecs.read_storage::<InBackpack>();
@@ -459,6 +596,23 @@ fn use_spell_hotkey(gs: &mut State, key: i32) -> RunState {
     RunState::Ticking
 }
 
+/// Which way a movement key points.
+pub fn direction(key: VirtualKeyCode) -> Option<(i32, i32)> {
+    match key {
+        VirtualKeyCode::Left | VirtualKeyCode::Numpad4 | VirtualKeyCode::H => Some((-1, 0)),
+        VirtualKeyCode::Right | VirtualKeyCode::Numpad6 | VirtualKeyCode::L => Some((1, 0)),
+        VirtualKeyCode::Up | VirtualKeyCode::Numpad8 | VirtualKeyCode::K => Some((0, -1)),
+        VirtualKeyCode::Down | VirtualKeyCode::Numpad2 | VirtualKeyCode::J => Some((0, 1)),
+
+        // Diagonals
+        VirtualKeyCode::Numpad9 | VirtualKeyCode::U => Some((1, -1)),
+        VirtualKeyCode::Numpad7 | VirtualKeyCode::Y => Some((-1, -1)),
+        VirtualKeyCode::Numpad3 | VirtualKeyCode::N => Some((1, 1)),
+        VirtualKeyCode::Numpad1 | VirtualKeyCode::B => Some((-1, 1)),
+        _ => None,
+    }
+}
+
 pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
     // Hotkeys
     if ctx.shift && ctx.key.is_some() {
@@ -497,32 +651,23 @@ pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
         }
     }
 
-    // Player movement
+    // Player movement, or with shift held, shouldering a locked door
+    if let Some((delta_x, delta_y)) = ctx.key.and_then(direction) {
+        if ctx.shift {
+            return try_bash_door(delta_x, delta_y, &mut gs.ecs);
+        }
+        return try_move_player(delta_x, delta_y, &mut gs.ecs);
+    }
+
     match ctx.key {
         None => return RunState::AwaitingInput, // Nothing happened
         Some(key) => match key {
-            VirtualKeyCode::Left | VirtualKeyCode::Numpad4 | VirtualKeyCode::H => {
-                return try_move_player(-1, 0, &mut gs.ecs);
-            },
-            VirtualKeyCode::Right | VirtualKeyCode::Numpad6 | VirtualKeyCode::L => {
-                return try_move_player(1, 0, &mut gs.ecs);
-            },
-            VirtualKeyCode::Up | VirtualKeyCode::Numpad8 | VirtualKeyCode::K => {
-                return try_move_player(0, -1, &mut gs.ecs);
-            },
-            VirtualKeyCode::Down | VirtualKeyCode::Numpad2 | VirtualKeyCode::J => {
-                return try_move_player(0, 1, &mut gs.ecs);
-            },
-
-            // Diagonals
-            VirtualKeyCode::Numpad9 | VirtualKeyCode::U => return try_move_player(1, -1, &mut gs.ecs),
-            VirtualKeyCode::Numpad7 | VirtualKeyCode::Y => return try_move_player(-1, -1, &mut gs.ecs),
-            VirtualKeyCode::Numpad3 | VirtualKeyCode::N => return try_move_player(1, 1, &mut gs.ecs),
-            VirtualKeyCode::Numpad1 | VirtualKeyCode::B => return try_move_player(-1, 1, &mut gs.ecs),
-
             // Skip Turn
             VirtualKeyCode::Numpad5 | VirtualKeyCode::Space => return skip_turn(&mut gs.ecs),
 
+            // Searching for hidden things
+            VirtualKeyCode::S => return search(&mut gs.ecs),
+
             // Level changes
             VirtualKeyCode::Period => {
                 if try_next_level(&mut gs.ecs) {
@@ -535,6 +680,9 @@ pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
                 }
             },
 
+            // Overland travel
+            VirtualKeyCode::T => return try_travel(&mut gs.ecs),
+
             // Picking up items
             VirtualKeyCode::G => get_item(&mut gs.ecs),
             VirtualKeyCode::I => return RunState::ShowInventory,
@@ -7,7 +7,7 @@ pub use crate::spawner;
 pub use crate::systems::{dispatcher, particle_system, saveload_system};
 pub use crate::{
     map,
-    map::{Map, MasterDungeonMap, TileType},
+    map::{Map, MasterDungeonMap, TileType, MAIN_BRANCH},
 };
 pub use crate::{
     raws,
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use crate::prelude::*;
 
 #[derive(Debug)]
@@ -15,11 +17,24 @@ impl RandomEntry {
     }
 }
 
-#[derive(Default)]
+/// How many times a roll that lands on something the level already has its fill of is made again, before giving up.
+const CAPPED_REROLLS: i32 = 10;
+
+/// Everything that can spawn on a level, split into items, monsters and props.
 pub struct MasterTable {
     items: RandomTable,
     mobs: RandomTable,
     props: RandomTable,
+    /// The relative odds of a roll giving an item, a prop, a monster or nothing at all.
+    odds: [i32; 4],
+    /// The most of each thing one level may have, for the things that are limited.
+    caps: HashMap<String, i32>,
+    /// The table for a few levels further down, and the percent chance of a roll being made on it instead.
+    deeper: Option<(Box<MasterTable>, i32)>,
+}
+
+impl Default for MasterTable {
+    fn default() -> Self { Self::new() }
 }
 
 impl MasterTable {
@@ -28,6 +43,9 @@ impl MasterTable {
             items: RandomTable::new(),
             mobs: RandomTable::new(),
             props: RandomTable::new(),
+            odds: [1, 1, 1, 1],
+            caps: HashMap::new(),
+            deeper: None,
         }
     }
 
@@ -39,14 +57,66 @@ impl MasterTable {
         }
     }
 
-    pub fn roll(&self) -> String {
-        let roll = crate::rng::roll_dice(1, 4);
-        match roll {
-            1 => self.items.roll(),
-            2 => self.props.roll(),
-            3 => self.mobs.roll(),
-            _ => "None".to_string(),
+    /// Limits how many of something a level may have.
+    pub fn cap<S: ToString>(&mut self, name: S, max: i32) { self.caps.insert(name.to_string(), max); }
+
+    pub fn set_odds(&mut self, items: i32, props: i32, mobs: i32, nothing: i32) {
+        self.odds = [items, props, mobs, nothing];
+    }
+
+    /// Gives rolls a `chance` percent of being made on `table`, out of their depth, instead.
+    pub fn set_deeper(&mut self, table: MasterTable, chance: i32) { self.deeper = Some((Box::new(table), chance)); }
+
+    /// Rolls on the monster table alone. `placed` counts what the level has already.
+    pub fn roll_mob(&self, placed: &HashMap<String, i32>) -> String { self.roll_within(placed, &|t| t.mobs.roll()) }
+
+    /// Rolls for an item, a prop, a monster or nothing, by the level's odds. `placed` counts what the level has
+    /// already.
+    pub fn roll(&self, placed: &HashMap<String, i32>) -> String { self.roll_within(placed, &|t| t.roll_any()) }
+
+    fn roll_within(&self, placed: &HashMap<String, i32>, roll: &dyn Fn(&MasterTable) -> String) -> String {
+        // Now and then, something has come up from further down
+        if let Some((deeper, chance)) = &self.deeper {
+            if crate::rng::roll_dice(1, 100) <= *chance {
+                let name = deeper.roll_within(placed, roll);
+                if name != "None" {
+                    return name;
+                }
+            }
+        }
+
+        for _ in 0..CAPPED_REROLLS {
+            let name = roll(self);
+            let full = match self.caps.get(&name) {
+                Some(max) => placed.get(&name).copied().unwrap_or(0) >= *max,
+                None => false,
+            };
+            if !full {
+                return name;
+            }
+        }
+
+        "None".to_string()
+    }
+
+    fn roll_any(&self) -> String {
+        let total: i32 = self.odds.iter().filter(|w| **w > 0).sum();
+        if total < 1 {
+            return "None".to_string();
         }
+
+        let mut roll = crate::rng::roll_dice(1, total);
+        for (weight, table) in self.odds.iter().zip([&self.items, &self.props, &self.mobs]) {
+            if *weight < 1 {
+                continue;
+            }
+            if roll <= *weight {
+                return table.roll();
+            }
+            roll -= weight;
+        }
+
+        "None".to_string()
     }
 }
 
@@ -76,15 +146,12 @@ impl RandomTable {
             return "None".to_string();
         }
         let mut roll = crate::rng::roll_dice(1, self.total_weight) - 1;
-        let mut index: usize = 0;
-
-        while roll > 0 {
-            if roll < self.entries[index].weight {
-                return self.entries[index].name.clone();
+        for entry in self.entries.iter() {
+            if roll < entry.weight {
+                return entry.name.clone();
             }
 
-            roll -= self.entries[index].weight;
-            index += 1;
+            roll -= entry.weight;
         }
 
         "None".to_string()
@@ -7,6 +7,9 @@ use structs::*;
 pub mod rawmaster;
 pub use rawmaster::*;
 
+mod prefabs;
+pub use prefabs::legend_entry;
+
 rltk::embedded_resource!(RAW_ITEMS_FILE, "../../raws/items.json");
 rltk::embedded_resource!(RAW_MOBS_FILE, "../../raws/mobs.json");
 rltk::embedded_resource!(RAW_PROPS_FILE, "../../raws/props.json");
@@ -15,6 +18,10 @@ rltk::embedded_resource!(RAW_LOOT_TABLES_FILE, "../../raws/loot_tables.json");
 rltk::embedded_resource!(RAW_FACTION_TABLE_FILE, "../../raws/faction_table.json");
 rltk::embedded_resource!(RAW_SPELLS_FILE, "../../raws/spells.json");
 rltk::embedded_resource!(RAW_WEAPON_TRAITS_FILE, "../../raws/weapon_traits.json");
+rltk::embedded_resource!(RAW_WANDERING_MONSTERS_FILE, "../../raws/wandering_monsters.json");
+rltk::embedded_resource!(RAW_LEVEL_CHAINS_FILE, "../../raws/level_chains.json");
+rltk::embedded_resource!(RAW_TILES_FILE, "../../raws/tiles.json");
+rltk::embedded_resource!(RAW_BRANCHES_FILE, "../../raws/branches.json");
 
 lazy_static! {
     pub static ref RAWS: Mutex<RawMaster> = Mutex::new(RawMaster::empty());
@@ -30,6 +37,13 @@ pub struct Raws {
     pub faction_table: Vec<FactionInfo>,
     pub spells: Vec<Spell>,
     pub weapon_traits: Vec<WeaponTrait>,
+    pub wandering_monsters: Vec<WanderingMonsters>,
+    pub level_chains: Vec<LevelChain>,
+    pub tiles: Vec<TileDefinition>,
+    pub branches: Vec<Branch>,
+    /// Read from `raws/prefabs` rather than a JSON file.
+    #[serde(skip)]
+    pub prefabs: Vec<Prefab>,
 }
 
 fn load_file<'a, T: serde::Deserialize<'a>>(file_path: &str) -> T {
@@ -44,7 +58,23 @@ fn load_file<'a, T: serde::Deserialize<'a>>(file_path: &str) -> T {
     decoded_data
 }
 
-pub fn load_raws() {
+/// Like `load_file`, but a copy of the file in `./raws` takes precedence over the built-in one - so it can be
+/// changed without rebuilding the game.
+fn load_moddable_file<T: serde::de::DeserializeOwned>(file_path: &str) -> T {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let local_path = std::path::Path::new("raws").join(std::path::Path::new(file_path).file_name().unwrap());
+        if let Ok(raw_string) = std::fs::read_to_string(&local_path) {
+            return serde_json::from_str(&raw_string)
+                .unwrap_or_else(|e| panic!("Unable to parse {}: {}", local_path.display(), e));
+        }
+    }
+
+    load_file(file_path)
+}
+
+/// Loads everything in the raws, and checks that the level chains in them can build every level the game needs.
+pub fn load_raws() -> Result<(), String> {
     rltk::link_resource!(RAW_ITEMS_FILE, "../../raws/items.json");
     rltk::link_resource!(RAW_MOBS_FILE, "../../raws/mobs.json");
     rltk::link_resource!(RAW_PROPS_FILE, "../../raws/props.json");
@@ -53,6 +83,10 @@ pub fn load_raws() {
     rltk::link_resource!(RAW_FACTION_TABLE_FILE, "../../raws/faction_table.json");
     rltk::link_resource!(RAW_SPELLS_FILE, "../../raws/spells.json");
     rltk::link_resource!(RAW_WEAPON_TRAITS_FILE, "../../raws/weapon_traits.json");
+    rltk::link_resource!(RAW_WANDERING_MONSTERS_FILE, "../../raws/wandering_monsters.json");
+    rltk::link_resource!(RAW_LEVEL_CHAINS_FILE, "../../raws/level_chains.json");
+    rltk::link_resource!(RAW_TILES_FILE, "../../raws/tiles.json");
+    rltk::link_resource!(RAW_BRANCHES_FILE, "../../raws/branches.json");
 
     let items = load_file::<Vec<Item>>("../../raws/items.json");
     let mobs = load_file::<Vec<Mob>>("../../raws/mobs.json");
@@ -62,6 +96,11 @@ pub fn load_raws() {
     let faction_table = load_file::<Vec<FactionInfo>>("../../raws/faction_table.json");
     let spells = load_file::<Vec<Spell>>("../../raws/spells.json");
     let weapon_traits = load_file::<Vec<WeaponTrait>>("../../raws/weapon_traits.json");
+    let wandering_monsters = load_file::<Vec<WanderingMonsters>>("../../raws/wandering_monsters.json");
+    let level_chains = load_moddable_file::<Vec<LevelChain>>("../../raws/level_chains.json");
+    let tiles = load_moddable_file::<Vec<TileDefinition>>("../../raws/tiles.json");
+    let branches = load_moddable_file::<Vec<Branch>>("../../raws/branches.json");
+    let prefabs = prefabs::load_prefabs();
 
     RAWS.lock().unwrap().load(Raws {
         items,
@@ -72,5 +111,12 @@ pub fn load_raws() {
         faction_table,
         spells,
         weapon_traits,
+        wandering_monsters,
+        level_chains,
+        tiles,
+        branches,
+        prefabs,
     });
+
+    crate::map_builders::check_level_chains()
 }
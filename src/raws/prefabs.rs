@@ -0,0 +1,245 @@
+use std::collections::BTreeMap;
+
+use crate::map::TileType;
+
+use super::structs::{HorizontalPlacement, Prefab, PrefabKind, Rarity, VerticalPlacement};
+
+/// The prefabs that ship with the game, by file name. REX Paint prefabs are a `.xp` drawing, plus a `.txt` file of
+/// the same name with a `rex:` line pointing at it.
+const BUILT_IN: &[(&str, &str)] = &[
+    ("checkerboard.txt", include_str!("../../raws/prefabs/checkerboard.txt")),
+    ("drow_entry.txt", include_str!("../../raws/prefabs/drow_entry.txt")),
+    (
+        "flooded_cellar.txt",
+        include_str!("../../raws/prefabs/flooded_cellar.txt"),
+    ),
+    (
+        "goblin_armoury.txt",
+        include_str!("../../raws/prefabs/goblin_armoury.txt"),
+    ),
+    ("kobold_den.txt", include_str!("../../raws/prefabs/kobold_den.txt")),
+    ("orc_camp.txt", include_str!("../../raws/prefabs/orc_camp.txt")),
+    (
+        "ruined_watchtower.txt",
+        include_str!("../../raws/prefabs/ruined_watchtower.txt"),
+    ),
+    ("silly_smile.txt", include_str!("../../raws/prefabs/silly_smile.txt")),
+    (
+        "totally_not_a_trap.txt",
+        include_str!("../../raws/prefabs/totally_not_a_trap.txt"),
+    ),
+    (
+        "underground_fort.txt",
+        include_str!("../../raws/prefabs/underground_fort.txt"),
+    ),
+    (
+        "wfc_populated.txt",
+        include_str!("../../raws/prefabs/wfc_populated.txt"),
+    ),
+];
+
+const BUILT_IN_REX: &[(&str, &[u8])] = &[(
+    "goblin_armoury.xp",
+    include_bytes!("../../raws/prefabs/goblin_armoury.xp"),
+)];
+
+/// Every prefab, built-in or not. On native builds, files in `./raws/prefabs` are read too: one with the same file
+/// name as a built-in prefab replaces it, and any others are added to the library.
+pub fn load_prefabs() -> Vec<Prefab> {
+    let mut files: BTreeMap<String, String> = BUILT_IN
+        .iter()
+        .map(|(file, text)| (file.to_string(), text.to_string()))
+        .collect();
+
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Ok(dir) = std::fs::read_dir("raws/prefabs") {
+        for entry in dir.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "txt") {
+                let file = entry.file_name().to_string_lossy().to_string();
+                let text = std::fs::read_to_string(&path)
+                    .unwrap_or_else(|e| panic!("Unable to read {}: {}", path.display(), e));
+                files.insert(file, text);
+            }
+        }
+    }
+
+    files
+        .iter()
+        .map(|(file, text)| parse_prefab(text, read_rex).unwrap_or_else(|e| panic!("Unable to parse {}: {}", file, e)))
+        .collect()
+}
+
+/// What a legend entry spawns, and what tile it puts down, by name: `Name on Tile` gives both, and a lone name is
+/// a tile if there is one called that, or otherwise something to spawn.
+pub fn legend_entry(entry: &str) -> (Option<&str>, Option<&str>) {
+    match entry.split_once(" on ") {
+        Some((spawn, tile)) => (Some(spawn.trim()), Some(tile.trim())),
+        None if TileType::named(entry).is_some() => (None, Some(entry)),
+        None => (Some(entry), None),
+    }
+}
+
+/// The bytes of a `.xp` prefab drawing, preferring a copy in `./raws/prefabs` to a built-in one.
+fn read_rex(file: &str) -> Option<Vec<u8>> {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Ok(bytes) = std::fs::read(std::path::Path::new("raws/prefabs").join(file)) {
+        return Some(bytes);
+    }
+
+    BUILT_IN_REX
+        .iter()
+        .find(|(name, _)| *name == file)
+        .map(|(_, bytes)| bytes.to_vec())
+}
+
+/// Reads a prefab file: `key: value` lines, then a `[legend]` of `glyph Name` lines and the `[map]` itself, drawn
+/// glyph for glyph. Lines starting with `;` outside the map are comments. Anything left unsaid takes its default:
+/// a common vault, at any depth, in any theme, that is never turned or flipped.
+fn parse_prefab(data: &str, read_rex: impl Fn(&str) -> Option<Vec<u8>>) -> Result<Prefab, String> {
+    let mut prefab = Prefab {
+        name: String::new(),
+        kind: PrefabKind::Vault,
+        min_depth: 0,
+        max_depth: i32::MAX,
+        rarity: Rarity::Common,
+        themes: Vec::new(),
+        rotate: false,
+        mirror: false,
+        unique: false,
+        placement: (HorizontalPlacement::Center, VerticalPlacement::Center),
+        legend: BTreeMap::new(),
+        rows: Vec::new(),
+    };
+    let mut rex = None;
+
+    let mut section = "";
+    for (n, line) in data.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        let fail = |what: &str| format!("Line {}: {}", n + 1, what);
+
+        if section != "[map]" && (line.trim().is_empty() || line.starts_with(';')) {
+            continue;
+        }
+
+        if let heading @ ("[legend]" | "[map]") = line.trim() {
+            section = heading;
+            continue;
+        }
+
+        match section {
+            "[legend]" => {
+                let mut chars = line.chars();
+                let glyph = chars.next().unwrap();
+                prefab.legend.insert(glyph, chars.as_str().trim().to_string());
+            },
+            "[map]" => prefab.rows.push(line.chars().collect()),
+            _ => {
+                let (key, value) = line.split_once(':').ok_or(fail("expected 'key: value'"))?;
+                let value = value.trim();
+                match key.trim() {
+                    "name" => prefab.name = value.to_string(),
+                    "kind" => {
+                        prefab.kind = match value {
+                            "level" => PrefabKind::Level,
+                            "section" => PrefabKind::Section,
+                            "vault" => PrefabKind::Vault,
+                            _ => return Err(fail("kind should be level, section or vault")),
+                        }
+                    },
+                    "depth" => {
+                        let (min, max) = value.split_once('-').unwrap_or((value, value));
+                        prefab.min_depth = min.trim().parse().map_err(|_| fail("expected 'depth: min-max'"))?;
+                        prefab.max_depth = max.trim().parse().map_err(|_| fail("expected 'depth: min-max'"))?;
+                    },
+                    "rarity" => {
+                        prefab.rarity = match value {
+                            "common" => Rarity::Common,
+                            "uncommon" => Rarity::Uncommon,
+                            "rare" => Rarity::Rare,
+                            _ => return Err(fail("rarity should be common, uncommon or rare")),
+                        }
+                    },
+                    "themes" => {
+                        prefab.themes = value
+                            .split(',')
+                            .map(|t| t.trim().to_string())
+                            .filter(|t| !t.is_empty())
+                            .collect()
+                    },
+                    "rotate" => prefab.rotate = value.parse().map_err(|_| fail("expected true or false"))?,
+                    "mirror" => prefab.mirror = value.parse().map_err(|_| fail("expected true or false"))?,
+                    "unique" => prefab.unique = value.parse().map_err(|_| fail("expected true or false"))?,
+                    "placement" => {
+                        let mut words = value.split_whitespace();
+                        let x = match words.next() {
+                            Some("left") => HorizontalPlacement::Left,
+                            Some("center") => HorizontalPlacement::Center,
+                            Some("right") => HorizontalPlacement::Right,
+                            _ => return Err(fail("expected 'placement: left|center|right top|center|bottom'")),
+                        };
+                        let y = match words.next() {
+                            Some("top") => VerticalPlacement::Top,
+                            Some("center") => VerticalPlacement::Center,
+                            Some("bottom") => VerticalPlacement::Bottom,
+                            _ => return Err(fail("expected 'placement: left|center|right top|center|bottom'")),
+                        };
+                        prefab.placement = (x, y);
+                    },
+                    "rex" => rex = Some(value.to_string()),
+                    _ => return Err(fail("unknown key")),
+                }
+            },
+        }
+    }
+
+    if prefab.name.is_empty() {
+        return Err("A prefab needs a name".to_string());
+    }
+
+    if let Some(file) = rex {
+        let bytes = read_rex(&file).ok_or(format!("Unable to find {}", file))?;
+        prefab.rows = rex_rows(&bytes).map_err(|e| format!("Unable to read {}: {}", file, e))?;
+    }
+
+    // Blank lines at the end of the map are only there to space out the file
+    while prefab.rows.last().is_some_and(|row| row.is_empty()) {
+        prefab.rows.pop();
+    }
+    if prefab.rows.is_empty() {
+        return Err("A prefab needs a [map]".to_string());
+    }
+
+    let width = prefab.width();
+    for row in prefab.rows.iter_mut() {
+        row.resize(width, '.');
+    }
+
+    Ok(prefab)
+}
+
+/// The glyphs of a REX Paint drawing, one row at a time. Each layer is drawn over the ones beneath it, except where
+/// it is left transparent.
+fn rex_rows(bytes: &[u8]) -> std::io::Result<Vec<Vec<char>>> {
+    let xp_file = rltk::rex::XpFile::read(&mut std::io::Cursor::new(bytes))?;
+    let (width, height) = xp_file
+        .layers
+        .first()
+        .map(|layer| (layer.width, layer.height))
+        .unwrap_or((0, 0));
+
+    let mut rows = vec![vec!['.'; width]; height];
+    for (i, layer) in xp_file.layers.iter().enumerate() {
+        for (y, row) in rows.iter_mut().enumerate() {
+            for (x, glyph) in row.iter_mut().enumerate() {
+                if let Some(cell) = layer.get(x, y) {
+                    if i == 0 || !cell.bg.is_transparent() {
+                        *glyph = rltk::to_char(cell.ch as u8);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(rows)
+}
@@ -1,7 +1,7 @@
 // use super::{parse::parse_dice_string, RawMaster};
 
 use super::{
-    raws::{parse_dice_string, Item, MagicItem, SpawnTableEntry},
+    raws::{parse_dice_string, Item, MagicItem, Rarity, SpawnTableEntry},
     RawMaster,
 };
 
@@ -75,6 +75,11 @@ impl RawMaster {
                 min_depth: 1 + i32::abs((nmw.bonus - 1) * 3),
                 max_depth: 100,
                 add_map_depth_to_weight: None,
+                branch: None,
+                biomes: Vec::new(),
+                rarity: Rarity::Common,
+                min_per_level: 0,
+                max_per_level: None,
             });
         }
     }
@@ -101,6 +106,11 @@ impl RawMaster {
                         min_depth: 2 + i32::abs((nmw.bonus - 1) * 3),
                         max_depth: 100,
                         add_map_depth_to_weight: None,
+                        branch: None,
+                        biomes: Vec::new(),
+                        rarity: Rarity::Common,
+                        min_per_level: 0,
+                        max_per_level: None,
                     });
                 }
             }
@@ -14,6 +14,9 @@ impl RawMaster {
     pub fn load(&mut self, raws: Raws) {
         self.raws = raws;
 
+        // Tiles
+        crate::map::register_tiles(&self.raws.tiles);
+
         self.item_index = HashMap::new();
         let mut used_names: HashSet<String> = HashSet::new();
         let mut items_to_build: Vec<NewMagicItem> = Vec::new();
@@ -40,6 +43,23 @@ impl RawMaster {
             &mut items_to_build,
         );
 
+        // Branches
+        self.branch_index = HashMap::new();
+        for (i, branch) in self.raws.branches.iter().enumerate() {
+            self.branch_index.insert(branch.name.clone(), i + 1);
+        }
+        let branch_names = self
+            .raws
+            .spawn_table
+            .iter()
+            .map(|s| &s.branch)
+            .chain(self.raws.level_chains.iter().map(|c| &c.branch));
+        for name in branch_names.flatten() {
+            if !self.branch_index.contains_key(name) {
+                panic!("Raws refer to unknown dungeon branch {}", name);
+            }
+        }
+
         // Spawn Table
         for spawn in self.raws.spawn_table.iter() {
             if !used_names.contains(&spawn.name) {
@@ -50,6 +70,25 @@ impl RawMaster {
             }
         }
 
+        // Prefabs
+        for prefab in self.raws.prefabs.iter() {
+            for entry in prefab.legend.values() {
+                let (spawn, tile) = crate::raws::legend_entry(entry);
+                if let Some(name) = spawn.filter(|name| !used_names.contains(*name)) {
+                    rltk::console::log(format!(
+                        "WARNING - Prefab {} references unspecified entity {}",
+                        prefab.name, name
+                    ));
+                }
+                if let Some(name) = tile.filter(|name| crate::map::TileType::named(name).is_none()) {
+                    rltk::console::log(format!(
+                        "WARNING - Prefab {} references unknown tile type {}",
+                        prefab.name, name
+                    ));
+                }
+            }
+        }
+
         // Loot Table
         for (i, loot) in self.raws.loot_tables.iter().enumerate() {
             self.loot_index.insert(loot.name.clone(), i);
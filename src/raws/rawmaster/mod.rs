@@ -20,6 +20,7 @@ pub struct RawMaster {
     loot_index: HashMap<String, usize>,
     faction_index: HashMap<String, HashMap<String, raws::Reaction>>,
     spell_index: HashMap<String, usize>,
+    branch_index: HashMap<String, usize>,
 }
 
 impl RawMaster {
@@ -34,6 +35,11 @@ impl RawMaster {
                 faction_table: Vec::new(),
                 spells: Vec::new(),
                 weapon_traits: Vec::new(),
+                wandering_monsters: Vec::new(),
+                level_chains: Vec::new(),
+                tiles: Vec::new(),
+                branches: Vec::new(),
+                prefabs: Vec::new(),
             },
             item_index: HashMap::new(),
             mob_index: HashMap::new(),
@@ -41,33 +47,211 @@ impl RawMaster {
             loot_index: HashMap::new(),
             faction_index: HashMap::new(),
             spell_index: HashMap::new(),
+            branch_index: HashMap::new(),
         }
     }
 }
 
-pub fn get_spawn_table_for_depth(raws: &RawMaster, depth: i32) -> MasterTable {
-    let available_options: Vec<&raws::SpawnTableEntry> = raws
-        .raws
-        .spawn_table
-        .iter()
-        .filter(|a| depth >= a.min_depth && depth <= a.max_depth)
-        .collect();
+/// How many levels further down an out-of-depth spawn comes from.
+const OUT_OF_DEPTH_LEVELS: i32 = 3;
+/// The percent chance of a spawn being out of its depth, on levels whose chain and branch don't say.
+const OUT_OF_DEPTH_CHANCE: i32 = 2;
+
+/// The spawn table for a level: whatever its branch and depth allow that suits the kind of level it is, shared out
+/// the way its chain and branch say. `near` is the tile the spawns are for, since a level's looks can change across
+/// it.
+pub fn get_spawn_table_for_level(raws: &RawMaster, map: &Map, near: usize, depth: i32) -> MasterTable {
+    let biomes = level_biomes(raws, map, near);
+    let weights = level_spawn_weights(raws, map);
+
+    let mut rt = spawn_table(raws, map.branch, depth, &biomes, &weights);
+    let chance = weights.out_of_depth.unwrap_or(OUT_OF_DEPTH_CHANCE);
+    if chance > 0 {
+        let deeper = spawn_table(raws, map.branch, depth + OUT_OF_DEPTH_LEVELS, &biomes, &weights);
+        rt.set_deeper(deeper, chance);
+    }
 
-    let mut rt = MasterTable::new();
+    rt
+}
 
-    for e in available_options.iter() {
+/// The things a level like this one must have some of, or may only have so many of: their names, with the fewest
+/// and the most there may be.
+pub fn get_spawn_quotas_for_level(
+    raws: &RawMaster,
+    map: &Map,
+    near: usize,
+    depth: i32,
+) -> Vec<(String, i32, Option<i32>)> {
+    let biomes = level_biomes(raws, map, near);
+    available_spawns(raws, map.branch, depth, &biomes)
+        .filter(|e| e.min_per_level > 0 || e.max_per_level.is_some())
+        .map(|e| (e.name.clone(), e.min_per_level, e.max_per_level))
+        .collect()
+}
+
+fn spawn_table(
+    raws: &RawMaster,
+    branch: usize,
+    depth: i32,
+    biomes: &[String],
+    weights: &raws::SpawnWeights,
+) -> MasterTable {
+    let mut rt = MasterTable::new();
+    rt.set_odds(
+        weights.items.unwrap_or(1),
+        weights.props.unwrap_or(1),
+        weights.mobs.unwrap_or(1),
+        weights.nothing.unwrap_or(1),
+    );
+
+    for e in available_spawns(raws, branch, depth, biomes) {
         let mut weight = e.weight;
 
         if e.add_map_depth_to_weight.is_some() {
             weight += depth;
         }
 
-        rt.add(e.name.clone(), weight, raws);
+        rt.add(e.name.clone(), weight * e.rarity.weight(), raws);
+        if let Some(max) = e.max_per_level {
+            rt.cap(&e.name, max);
+        }
     }
 
     rt
 }
 
+fn available_spawns<'a>(
+    raws: &'a RawMaster,
+    branch: usize,
+    depth: i32,
+    biomes: &'a [String],
+) -> impl Iterator<Item = &'a raws::SpawnTableEntry> {
+    raws.raws.spawn_table.iter().filter(move |a| {
+        branch_of(raws, &a.branch) == branch
+            && depth >= a.min_depth
+            && depth <= a.max_depth
+            && (a.biomes.is_empty() || a.biomes.iter().any(|b| biomes.contains(b)))
+    })
+}
+
+/// Everything a spawn table biome could call a level: its name, the theme it is drawn with and its branch.
+fn level_biomes(raws: &RawMaster, map: &Map, near: usize) -> Vec<String> {
+    // Levels still being built don't have their chain's theme yet
+    let chain_theme = raws
+        .raws
+        .level_chains
+        .iter()
+        .find(|c| c.name == map.name)
+        .and_then(|c| c.theme.clone());
+    let theme = map
+        .theme
+        .clone()
+        .or(chain_theme)
+        .unwrap_or_else(|| crate::map::tile_theme(near, map).to_string());
+
+    let mut biomes = vec![map.name.clone(), theme];
+    if let Some(branch) = get_branch(raws, map.branch) {
+        biomes.push(branch.name);
+    }
+    biomes
+}
+
+/// How the level's spawns are shared out: what its chain says, then its branch.
+fn level_spawn_weights(raws: &RawMaster, map: &Map) -> raws::SpawnWeights {
+    let chain = raws
+        .raws
+        .level_chains
+        .iter()
+        .find(|c| c.name == map.name)
+        .map(|c| c.spawns.clone())
+        .unwrap_or_default();
+    let branch = get_branch(raws, map.branch).map(|b| b.spawns).unwrap_or_default();
+    chain.or(&branch)
+}
+
+/// Whether there is an item, mob or prop by this name.
+pub fn is_spawnable(raws: &RawMaster, name: &str) -> bool {
+    raws.item_index.contains_key(name) || raws.mob_index.contains_key(name) || raws.prop_index.contains_key(name)
+}
+
+/// Whether the spawn table can put something with this name on a level at a depth of a branch.
+pub fn spawns_at_depth(raws: &RawMaster, name: &str, branch: usize, depth: i32) -> bool {
+    raws.raws
+        .spawn_table
+        .iter()
+        .any(|a| a.name == name && branch_of(raws, &a.branch) == branch && depth >= a.min_depth && depth <= a.max_depth)
+}
+
+pub fn get_wandering_monsters_for_depth(raws: &RawMaster, depth: i32) -> Option<raws::WanderingMonsters> {
+    raws.raws
+        .wandering_monsters
+        .iter()
+        .find(|w| depth >= w.min_depth && depth <= w.max_depth)
+        .cloned()
+}
+
+pub fn get_level_chains_for_depth(raws: &RawMaster, branch: usize, depth: i32) -> Vec<raws::LevelChain> {
+    raws.raws
+        .level_chains
+        .iter()
+        .filter(|c| {
+            branch_of(raws, &c.branch) == branch && depth >= c.min_depth && c.max_depth.is_none_or(|max| depth <= max)
+        })
+        .cloned()
+        .collect()
+}
+
+pub fn get_level_chains(raws: &RawMaster) -> Vec<raws::LevelChain> { raws.raws.level_chains.clone() }
+
+pub fn get_level_chain(raws: &RawMaster, name: &str) -> Option<raws::LevelChain> {
+    raws.raws.level_chains.iter().find(|c| c.name == name).cloned()
+}
+
+/// The prefab with this name, if it is of the kind wanted.
+pub fn get_prefab(raws: &RawMaster, name: &str, kind: raws::PrefabKind) -> Option<raws::Prefab> {
+    raws.raws
+        .prefabs
+        .iter()
+        .find(|p| p.name == name && p.kind == kind)
+        .cloned()
+}
+
+/// Every vault that can be found at this depth.
+pub fn get_vaults_for_depth(raws: &RawMaster, depth: i32) -> Vec<raws::Prefab> {
+    raws.raws
+        .prefabs
+        .iter()
+        .filter(|p| p.kind == raws::PrefabKind::Vault && depth >= p.min_depth && depth <= p.max_depth)
+        .cloned()
+        .collect()
+}
+
+/// The index of a named branch, as `Map::branch` holds it: the main dungeon is 0, and the branches in
+/// `branches.json` follow on from 1. No name at all means the main dungeon.
+pub fn branch_of(raws: &RawMaster, name: &Option<String>) -> usize {
+    match name {
+        None => crate::map::MAIN_BRANCH,
+        Some(name) => raws.branch_index[name],
+    }
+}
+
+/// The side branch with this index, or None for the main dungeon.
+pub fn get_branch(raws: &RawMaster, branch: usize) -> Option<raws::Branch> {
+    match branch {
+        crate::map::MAIN_BRANCH => None,
+        _ => raws.raws.branches.get(branch - 1).cloned(),
+    }
+}
+
+/// Every side branch, with the index levels in it are known by.
+pub fn get_branches(raws: &RawMaster) -> Vec<(usize, raws::Branch)> {
+    raws.raws
+        .branches
+        .iter()
+        .map(|b| (raws.branch_index[&b.name], b.clone()))
+        .collect()
+}
+
 pub fn faction_reaction(my_faction: &str, their_faction: &str, raws: &RawMaster) -> raws::Reaction {
     if raws.faction_index.contains_key(my_faction) {
         let mf = &raws.faction_index[my_faction];
@@ -77,12 +77,16 @@ macro_rules! apply_effects {
                 }
                 "damage" => $eb = $eb.with(InflictsDamage{ damage : effect.1.parse::<i32>().unwrap() }),
                 "damage_over_time" => $eb = $eb.with( DamageOverTime { damage : effect.1.parse::<i32>().unwrap() } ),
+                "dig" => $eb = $eb.with(Digs{ into: TileType::named(&effect.1).expect("Unknown tile to dig into") }),
                 "duration" => $eb = $eb.with(Duration { turns: effect.1.parse::<i32>().unwrap() }),
                 "food" => $eb = $eb.with(ProvidesFood{}),
                 "identify" => $eb = $eb.with(ProvidesIdentification{}),
+                "ignite" => $eb = $eb.with(Ignites{ turns: effect.1.parse::<i32>().unwrap() }),
+                "knockback" => $eb = $eb.with(Knockback{ distance: effect.1.parse::<i32>().unwrap() }),
                 "magic_mapping" => $eb = $eb.with(MagicMapper{}),
                 "particle" => $eb = $eb.with(parse_particle(&effect.1)),
                 "particle_line" => $eb = $eb.with(parse_particle_line(&effect.1)),
+                "poison_gas" => $eb = $eb.with(ReleasesGas{ density: effect.1.parse::<i32>().unwrap() }),
                 "provides_healing" => $eb = $eb.with(ProvidesHealing{ heal_amount: effect.1.parse::<i32>().unwrap() }),
                 "provides_mana" => $eb = $eb.with(ProvidesMana{ mana_amount: effect.1.parse::<i32>().unwrap() }),
                 "ranged" => $eb = $eb.with(Ranged{ range: effect.1.parse::<i32>().unwrap() }),
@@ -158,6 +162,12 @@ pub fn spawn_named_item(raws: &RawMaster, ecs: &mut World, key: &str, pos: Spawn
         if let Some(proc_effects) = &weapon.proc_effects {
             apply_effects!(proc_effects, eb);
         }
+
+        if let Some(digs) = &weapon.digs {
+            eb = eb.with(Digs {
+                into: TileType::named(digs).expect("Unknown tile to dig into"),
+            });
+        }
     }
 
     // Wearable Component
@@ -370,6 +380,8 @@ pub fn spawn_named_mob(raws: &RawMaster, ecs: &mut World, key: &str, pos: SpawnT
     skills.skills.insert(Skill::Melee, 1);
     skills.skills.insert(Skill::Defense, 1);
     skills.skills.insert(Skill::Magic, 1);
+    skills.skills.insert(Skill::Perception, 1);
+    skills.skills.insert(Skill::Lockpicking, 1);
 
     if let Some(mobskills) = &mob_template.skills {
         for sk in mobskills.iter() {
@@ -383,6 +395,12 @@ pub fn spawn_named_mob(raws: &RawMaster, ecs: &mut World, key: &str, pos: SpawnT
                 "Magic" => {
                     skills.skills.insert(Skill::Magic, *sk.1);
                 },
+                "Perception" => {
+                    skills.skills.insert(Skill::Perception, *sk.1);
+                },
+                "Lockpicking" => {
+                    skills.skills.insert(Skill::Lockpicking, *sk.1);
+                },
                 _ => {
                     rltk::console::log(format!("Unknown skill referenced: [{}]", sk.0));
                 },
@@ -423,6 +441,7 @@ pub fn spawn_named_mob(raws: &RawMaster, ecs: &mut World, key: &str, pos: SpawnT
     if let Some(vendor) = &mob_template.vendor {
         eb = eb.with(Vendor {
             categories: vendor.clone(),
+            sold: HashMap::new(),
         });
     }
 
@@ -453,6 +472,23 @@ pub fn spawn_named_mob(raws: &RawMaster, ecs: &mut World, key: &str, pos: SpawnT
         eb = eb.with(a);
     }
 
+    // Item Use
+    if let Some(true) = mob_template.uses_items {
+        eb = eb.with(UsesItems {});
+    }
+
+    // Hazard Resistance
+    if let Some(hazards) = &mob_template.resists_hazards {
+        eb = eb.with(HazardResistance {
+            hazards: hazards.clone(),
+        });
+    }
+
+    // Darkvision
+    if let Some(true) = mob_template.darkvision {
+        eb = eb.with(Darkvision {});
+    }
+
     // Build a mob person thing
     let new_mob = eb.build();
 
@@ -476,6 +512,13 @@ pub fn spawn_named_prop(new_entity: EntityBuilder, prop_template: raws::Prop) ->
         };
     }
 
+    // Blocks Tile Trait
+    if let Some(blocks_tile) = prop_template.blocks_tile {
+        if blocks_tile {
+            eb = eb.with(BlocksTile {})
+        };
+    }
+
     // Blocks Visibility Trait
     if let Some(blocks_visibility) = prop_template.blocks_visibility {
         if blocks_visibility {
@@ -483,9 +526,28 @@ pub fn spawn_named_prop(new_entity: EntityBuilder, prop_template: raws::Prop) ->
         };
     }
 
+    // Cover
+    if prop_template.provides_cover.unwrap_or(false) {
+        eb = eb.with(ProvidesCover {});
+    }
+
     // Door?
     if let Some(door_open) = prop_template.door_open {
-        eb = eb.with(Door { open: door_open });
+        eb = eb.with(Door {
+            open: door_open,
+            locked: prop_template.door_locked.unwrap_or(false),
+            key: None,
+        });
+    }
+
+    // Secret Door
+    if prop_template.secret_door.unwrap_or(false) {
+        eb = eb.with(SecretDoor {});
+    }
+
+    // Flammable
+    if prop_template.flammable.unwrap_or(false) {
+        eb = eb.with(Flammable {});
     }
 
     // Trigger Trait (Traps)
@@ -0,0 +1,18 @@
+use serde::Deserialize;
+
+use super::SpawnWeights;
+
+/// A side branch off the main dungeon: a run of levels with their own builder chains and spawn tables, reached by
+/// an extra staircase down from one level of the main dungeon.
+#[derive(Deserialize, Debug, Clone)]
+pub struct Branch {
+    pub name: String,
+    /// The depths of the main dungeon the way in may be found on. Each game picks one of them.
+    pub min_entrance_depth: i32,
+    pub max_entrance_depth: i32,
+    /// How many levels the branch goes down below its entrance; the last of them has no way further down.
+    pub levels: i32,
+    /// How spawns are shared out on its levels, unless their chains say otherwise.
+    #[serde(default)]
+    pub spawns: SpawnWeights,
+}
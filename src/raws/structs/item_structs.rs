@@ -53,6 +53,8 @@ pub struct Weapon {
     pub proc_chance: Option<f32>,
     pub proc_target: Option<String>,
     pub proc_effects: Option<HashMap<String, String>>,
+    /// Lets the wielder tunnel by walking into rock, leaving this tile behind.
+    pub digs: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
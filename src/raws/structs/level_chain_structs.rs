@@ -0,0 +1,63 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use super::SpawnWeights;
+
+/// A kind of level: the builder chain that makes it and the depths it can appear on.
+#[derive(Deserialize, Debug, Clone)]
+pub struct LevelChain {
+    /// The name the level is shown with, and how `mapgen --chain` refers to it.
+    pub name: String,
+    pub min_depth: i32,
+    /// The deepest level the chain can build; with none, it goes on all the way down.
+    #[serde(default)]
+    pub max_depth: Option<i32>,
+    /// The dungeon branch the chain builds levels for; chains without one are for the main dungeon.
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// When several chains cover a depth, how likely this one is to be picked. One with no weight is never picked,
+    /// and is only built by name - as the areas of the overworld are.
+    #[serde(default = "default_weight")]
+    pub weight: i32,
+    /// Open to the sky, so the level goes from day into night with the world clock.
+    #[serde(default)]
+    pub daylight: bool,
+    /// Which of the themes in `tiles.json` the level is drawn with, instead of the one usual at its depth.
+    #[serde(default)]
+    pub theme: Option<String>,
+    /// How many tiles across and down the levels the chain builds are.
+    #[serde(default = "default_width")]
+    pub width: i32,
+    #[serde(default = "default_height")]
+    pub height: i32,
+    /// How the level's spawns are shared out, over what its branch says.
+    #[serde(default)]
+    pub spawns: SpawnWeights,
+    pub steps: Vec<BuilderStep>,
+}
+
+/// One step of a chain. A step names a single builder, runs a group of `steps` in order, or picks one of several
+/// weighted alternatives - and may only happen at all some `chance` percent of the time. A step with none of these
+/// does nothing, which is useful as an alternative.
+#[derive(Deserialize, Debug, Clone)]
+pub struct BuilderStep {
+    #[serde(default)]
+    pub builder: Option<String>,
+    #[serde(default)]
+    pub params: HashMap<String, String>,
+    #[serde(default)]
+    pub steps: Vec<BuilderStep>,
+    #[serde(default)]
+    pub one_of: Vec<BuilderStep>,
+    /// Relative odds of this step, when it is one of several alternatives.
+    #[serde(default = "default_weight")]
+    pub weight: i32,
+    #[serde(default)]
+    pub chance: Option<i32>,
+}
+
+fn default_weight() -> i32 { 1 }
+
+fn default_width() -> i32 { 80 }
+
+fn default_height() -> i32 { 50 }
@@ -37,6 +37,9 @@ pub struct Mob {
     pub vendor: Option<Vec<String>>,
     pub abilities: Option<Vec<MobAbility>>,
     pub on_death: Option<Vec<MobAbility>>,
+    pub uses_items: Option<bool>,
+    pub resists_hazards: Option<Vec<String>>,
+    pub darkvision: Option<bool>,
 }
 
 #[derive(Deserialize, Debug, Clone)]
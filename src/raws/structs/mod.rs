@@ -1,19 +1,29 @@
+pub mod branch_structs;
 pub mod faction_structs;
 pub mod item_structs;
+pub mod level_chain_structs;
 pub mod loot_structs;
 pub mod mob_structs;
+pub mod prefab_structs;
 pub mod prop_structs;
 pub mod spawn_table_structs;
 pub mod spell_structs;
+pub mod tile_structs;
+pub mod wandering_structs;
 pub mod weapon_traits;
 
+pub use branch_structs::*;
 pub use faction_structs::*;
 pub use item_structs::*;
+pub use level_chain_structs::*;
 pub use loot_structs::*;
 pub use mob_structs::*;
+pub use prefab_structs::*;
 pub use prop_structs::*;
 pub use spawn_table_structs::*;
 pub use spell_structs::*;
+pub use tile_structs::*;
+pub use wandering_structs::*;
 pub use weapon_traits::*;
 
 use core::fmt::Debug;
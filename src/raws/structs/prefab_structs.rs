@@ -0,0 +1,186 @@
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// What a prefab is for: a whole level, a section stamped onto a level at a set spot, or a vault dropped into open
+/// floor wherever it fits.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum PrefabKind {
+    Level,
+    Section,
+    Vault,
+}
+
+/// How often a prefab - or an entry in the spawn table - turns up, compared with the others that could.
+#[derive(Deserialize, PartialEq, Eq, Copy, Clone, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Rarity {
+    #[default]
+    Common,
+    Uncommon,
+    Rare,
+}
+
+impl Rarity {
+    pub fn weight(&self) -> i32 {
+        match self {
+            Rarity::Common => 6,
+            Rarity::Uncommon => 3,
+            Rarity::Rare => 1,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum HorizontalPlacement {
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum VerticalPlacement {
+    Top,
+    Center,
+    Bottom,
+}
+
+/// Glyphs that point a particular way, and what each becomes once turned a quarter clockwise.
+const TURNED: &[(char, char)] = &[
+    ('-', '|'),
+    ('|', '-'),
+    ('/', '\\'),
+    ('\\', '/'),
+    ('─', '│'),
+    ('│', '─'),
+    ('═', '║'),
+    ('║', '═'),
+    ('┌', '┐'),
+    ('┐', '┘'),
+    ('┘', '└'),
+    ('└', '┌'),
+    ('←', '↑'),
+    ('↑', '→'),
+    ('→', '↓'),
+    ('↓', '←'),
+];
+
+/// Glyphs that point a particular way, and what each becomes when flipped left to right.
+const FLIPPED: &[(char, char)] = &[
+    ('/', '\\'),
+    ('\\', '/'),
+    ('┌', '┐'),
+    ('┐', '┌'),
+    ('└', '┘'),
+    ('┘', '└'),
+    ('←', '→'),
+    ('→', '←'),
+];
+
+/// How a prefab is laid down: flipped left to right and top to bottom first, then turned clockwise a quarter at a
+/// time.
+#[derive(PartialEq, Eq, Copy, Clone, Debug, Default)]
+pub struct Orientation {
+    pub flip_x: bool,
+    pub flip_y: bool,
+    pub turns: i32,
+}
+
+/// A hand-drawn piece of map, from one of the files in `raws/prefabs`.
+#[derive(Clone, Debug)]
+pub struct Prefab {
+    pub name: String,
+    pub kind: PrefabKind,
+    pub min_depth: i32,
+    pub max_depth: i32,
+    pub rarity: Rarity,
+    /// The map themes it belongs in, as `tiles.json` names them; no themes at all means it fits in anywhere.
+    pub themes: Vec<String>,
+    /// Whether it may be turned a quarter at a time, or flipped, to fit.
+    pub rotate: bool,
+    pub mirror: bool,
+    /// No more than one of it on a level.
+    pub unique: bool,
+    /// Where a section goes on the level.
+    pub placement: (HorizontalPlacement, VerticalPlacement),
+    /// What the glyphs stand for: a tile, something to spawn on the floor, or both as `Name on Tile`. Glyphs
+    /// missing from the legend are read as tiles, by their `ascii` glyph.
+    pub legend: BTreeMap<char, String>,
+    pub rows: Vec<Vec<char>>,
+}
+
+impl Prefab {
+    pub fn width(&self) -> usize { self.rows.iter().map(|row| row.len()).max().unwrap_or(0) }
+
+    pub fn height(&self) -> usize { self.rows.len() }
+
+    /// The glyph at a spot, or a floor where a row falls short.
+    pub fn glyph(&self, x: usize, y: usize) -> char { self.rows[y].get(x).copied().unwrap_or('.') }
+
+    /// Whether it belongs on a level drawn in this theme.
+    pub fn suits(&self, theme: &str) -> bool { self.themes.is_empty() || self.themes.iter().any(|t| t == theme) }
+
+    /// Every way it may be laid down, as it was drawn first. Ways that come out looking the same are only listed
+    /// once, so a symmetrical prefab isn't more likely to be picked for having more of them.
+    pub fn orientations(&self) -> Vec<Orientation> {
+        let turns = if self.rotate { 0..4 } else { 0..1 };
+        let flips: &[bool] = if self.mirror { &[false, true] } else { &[false] };
+
+        let mut orientations: Vec<Orientation> = Vec::new();
+        let mut seen: Vec<Vec<Vec<char>>> = Vec::new();
+        for turns in turns {
+            for flip_x in flips.iter() {
+                for flip_y in flips.iter() {
+                    let orientation = Orientation {
+                        flip_x: *flip_x,
+                        flip_y: *flip_y,
+                        turns,
+                    };
+                    let rows = self.oriented(orientation).rows;
+                    if !seen.contains(&rows) {
+                        seen.push(rows);
+                        orientations.push(orientation);
+                    }
+                }
+            }
+        }
+
+        orientations
+    }
+
+    /// A copy laid down the given way, with the glyphs that point somewhere pointing the new way too.
+    pub fn oriented(&self, orientation: Orientation) -> Prefab {
+        let mut rows = self.rows.clone();
+        if orientation.flip_x {
+            for row in rows.iter_mut() {
+                row.reverse();
+                for glyph in row.iter_mut() {
+                    *glyph = remap(*glyph, FLIPPED);
+                }
+            }
+        }
+        if orientation.flip_y {
+            // Flipping top to bottom is the same as flipping left to right and turning halfway round
+            rows.reverse();
+            for row in rows.iter_mut() {
+                for glyph in row.iter_mut() {
+                    *glyph = remap(remap(remap(*glyph, FLIPPED), TURNED), TURNED);
+                }
+            }
+        }
+        for _ in 0..orientation.turns.rem_euclid(4) {
+            let (width, height) = (rows.first().map_or(0, |row| row.len()), rows.len());
+            rows = (0..width)
+                .map(|x| (0..height).rev().map(|y| remap(rows[y][x], TURNED)).collect())
+                .collect();
+        }
+
+        Prefab { rows, ..self.clone() }
+    }
+}
+
+fn remap(glyph: char, table: &[(char, char)]) -> char {
+    table
+        .iter()
+        .find(|(from, _)| *from == glyph)
+        .map_or(glyph, |(_, to)| *to)
+}
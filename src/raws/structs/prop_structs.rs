@@ -21,7 +21,13 @@ pub struct Prop {
     pub hidden: Option<bool>,
     pub blocks_tile: Option<bool>,
     pub blocks_visibility: Option<bool>,
+    /// Ranged fighters look for tiles beside props that provide cover.
+    pub provides_cover: Option<bool>,
     pub door_open: Option<bool>,
+    pub door_locked: Option<bool>,
+    /// A door that passes for a wall until it is found; see `SecretDoor`.
+    pub secret_door: Option<bool>,
+    pub flammable: Option<bool>,
     pub entry_trigger: Option<EntryTrigger>,
     pub light: Option<super::mob_structs::MobLight>,
 }
@@ -1,5 +1,7 @@
 use serde::Deserialize;
 
+use super::Rarity;
+
 #[derive(Deserialize, Debug)]
 pub struct SpawnTableEntry {
     pub name: String,
@@ -7,4 +9,50 @@ pub struct SpawnTableEntry {
     pub min_depth: i32,
     pub max_depth: i32,
     pub add_map_depth_to_weight: Option<bool>,
+    /// The dungeon branch the entry belongs to; entries without one are for the main dungeon.
+    #[serde(default)]
+    pub branch: Option<String>,
+    /// The kinds of level it is found on, by the level's name, the theme it is drawn with or the branch it is in.
+    /// An entry with none turns up on any level its depth allows.
+    #[serde(default)]
+    pub biomes: Vec<String>,
+    /// Scales the weight, so a rare entry stays rare however the weights around it change.
+    #[serde(default)]
+    pub rarity: Rarity,
+    /// How many of it every level it can appear on is sure to have.
+    #[serde(default)]
+    pub min_per_level: i32,
+    /// How many of it one level may have at most.
+    #[serde(default)]
+    pub max_per_level: Option<i32>,
+}
+
+/// How a kind of level shares out its spawns between items, props, monsters and nothing at all, and the percent
+/// chance of one coming from a few levels further down. Anything left out is inherited: a level chain's from its
+/// branch, and a branch's from the usual even split.
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct SpawnWeights {
+    #[serde(default)]
+    pub items: Option<i32>,
+    #[serde(default)]
+    pub props: Option<i32>,
+    #[serde(default)]
+    pub mobs: Option<i32>,
+    #[serde(default)]
+    pub nothing: Option<i32>,
+    #[serde(default)]
+    pub out_of_depth: Option<i32>,
+}
+
+impl SpawnWeights {
+    /// These weights, with any that are missing taken from `parent`.
+    pub fn or(&self, parent: &SpawnWeights) -> SpawnWeights {
+        SpawnWeights {
+            items: self.items.or(parent.items),
+            props: self.props.or(parent.props),
+            mobs: self.mobs.or(parent.mobs),
+            nothing: self.nothing.or(parent.nothing),
+            out_of_depth: self.out_of_depth.or(parent.out_of_depth),
+        }
+    }
 }
@@ -0,0 +1,79 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// A kind of terrain: how it behaves, how it is drawn and what it does to whoever steps onto it.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TileDefinition {
+    /// How the tile is referred to in saves, level files and other raws.
+    pub name: String,
+    /// The glyph the tile is written as in level files and `mapgen` output.
+    pub ascii: char,
+    #[serde(default)]
+    pub walkable: bool,
+    #[serde(default)]
+    pub opaque: bool,
+    /// How expensive the tile is to path across; 1.0 is an ordinary floor.
+    #[serde(default = "default_cost")]
+    pub cost: f32,
+    /// Catches fire, and keeps burning until the fire has consumed it.
+    #[serde(default)]
+    pub flammable: bool,
+    /// The tile left behind once a flammable tile has burnt out.
+    #[serde(default)]
+    pub burns_into: Option<String>,
+    /// What kind of danger the tile is, such as "fire" or "water". Monsters keep off it unless they resist that
+    /// hazard, and anything that resists it is spared the `on_enter` effects.
+    #[serde(default)]
+    pub hazard: Option<String>,
+    /// Anything moving onto the tile keeps sliding the same way until it leaves the tile or hits something.
+    #[serde(default)]
+    pub slippery: bool,
+    /// Can be dug or blasted away, becoming whatever the digging effect leaves behind.
+    #[serde(default)]
+    pub diggable: bool,
+    pub look: TileLook,
+    /// Replacement looks, by theme name, for levels drawn in that theme.
+    #[serde(default)]
+    pub themes: HashMap<String, TileLook>,
+    #[serde(default)]
+    pub on_enter: Option<TileEntryEffect>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TileLook {
+    pub glyph: char,
+    pub fg: String,
+    #[serde(default)]
+    pub bg: Option<String>,
+    /// Draw the tile with line-drawing glyphs that join up with its neighbours of the same type.
+    #[serde(default)]
+    pub connected: bool,
+}
+
+/// What happens to anything that moves onto the tile.
+#[derive(Deserialize, Debug, Clone)]
+pub struct TileEntryEffect {
+    /// Dice of damage, e.g. "2d6".
+    #[serde(default)]
+    pub damage: Option<String>,
+    /// Turns of confusion.
+    #[serde(default)]
+    pub confusion: Option<i32>,
+    /// Initiative penalty, as with the slow spell.
+    #[serde(default)]
+    pub slow: Option<f32>,
+    /// Damage per turn, as with poison.
+    #[serde(default)]
+    pub damage_over_time: Option<i32>,
+    /// Drop whoever enters to the next depth down.
+    #[serde(default)]
+    pub fall: bool,
+    /// Dice of damage for going under. The more the swimmer carries, the more likely that is.
+    #[serde(default)]
+    pub drown: Option<String>,
+    /// Shown when the player steps onto the tile.
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+fn default_cost() -> f32 { 1.0 }
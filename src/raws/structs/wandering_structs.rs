@@ -0,0 +1,20 @@
+use serde::Deserialize;
+
+/// How often new monsters wander onto levels in a range of depths.
+#[derive(Deserialize, Debug, Clone)]
+pub struct WanderingMonsters {
+    pub min_depth: i32,
+    pub max_depth: i32,
+    /// Turns the player can spend on a level before anything new turns up.
+    pub grace_turns: i32,
+    /// After the grace period, roll for an arrival once every this many turns...
+    pub turns_per_check: i32,
+    /// ...with this percent chance.
+    pub chance: i32,
+    /// On returning to a level, one arrival for every this many turns spent away...
+    pub turns_away_per_spawn: i32,
+    /// ...up to this many.
+    pub max_on_return: i32,
+    /// Nothing new arrives while the level holds this many monsters.
+    pub max_population: i32,
+}
@@ -6,6 +6,13 @@ use super::prelude::*;
 struct SpatialMap {
     blocked: Vec<(bool, bool)>,
     tile_content: Vec<Vec<(Entity, bool)>>,
+    /// Tiles that have had something on them since the last `clear`, so that it needn't sweep the whole map.
+    occupied: Vec<usize>,
+    /// Whether the terrain's part of `blocked` has been filled in for the current map. After that, terrain that
+    /// changes is kept up to date tile by tile.
+    terrain_known: bool,
+    /// Goes up whenever the terrain changes, or a different map becomes the current one.
+    terrain_revision: u64,
 }
 
 impl SpatialMap {
@@ -13,6 +20,9 @@ impl SpatialMap {
         Self {
             blocked: Vec::new(),
             tile_content: Vec::new(),
+            occupied: Vec::new(),
+            terrain_known: false,
+            terrain_revision: 0,
         }
     }
 }
@@ -25,29 +35,50 @@ lazy_static! {
 // Map Helpers
 ///////////////////////////////////////////////////////////////////////////
 
+/// Makes the index fit a new map, forgetting everything about the last one. It must be called whenever a different
+/// map becomes the current one.
 pub fn set_size(map_tile_count: usize) {
     let mut lock = SPATIAL_MAP.lock().unwrap();
     lock.blocked = vec![(false, false); map_tile_count];
     lock.tile_content = vec![Vec::new(); map_tile_count];
+    lock.occupied.clear();
+    lock.terrain_known = false;
+    lock.terrain_revision += 1;
 }
 
+/// Forgets where every entity is, ready for them to be indexed afresh. What the terrain blocks is kept.
 pub fn clear() {
     let mut lock = SPATIAL_MAP.lock().unwrap();
-
-    lock.blocked.iter_mut().for_each(|b| {
-        b.0 = false;
-        b.1 = false;
-    });
-
-    for content in lock.tile_content.iter_mut() {
-        content.clear();
+    let SpatialMap {
+        blocked,
+        tile_content,
+        occupied,
+        ..
+    } = &mut *lock;
+
+    for idx in occupied.drain(..) {
+        blocked[idx].1 = false;
+        tile_content[idx].clear();
     }
 }
 
 pub fn populate_blocked_from_map(map: &Map) {
     let mut lock = SPATIAL_MAP.lock().unwrap();
     for (i, tile) in map.tiles.iter().enumerate() {
-        lock.blocked[i].0 = !map::tile_walkable(*tile);
+        // Nothing may stand on the outer edge, even where it is water rather than wall
+        let (x, y) = (i as i32 % map.width, i as i32 / map.width);
+        let edge = x < 1 || y < 1 || x > map.width - 2 || y > map.height - 2;
+        lock.blocked[i].0 = edge || !map::tile_walkable(*tile);
+    }
+    lock.terrain_known = true;
+    lock.terrain_revision += 1;
+}
+
+/// Fills in what the terrain blocks, if it hasn't been for this map yet. Big maps are costly to sweep every turn.
+pub fn populate_blocked_if_needed(map: &Map) {
+    let known = SPATIAL_MAP.lock().unwrap().terrain_known;
+    if !known {
+        populate_blocked_from_map(map);
     }
 }
 
@@ -56,9 +87,34 @@ pub fn is_blocked(idx: usize) -> bool {
     lock.blocked[idx].0 || lock.blocked[idx].1
 }
 
+/// Like `is_blocked`, but entities in `ignore` (usually the mover itself) don't count.
+pub fn is_blocked_ignoring(idx: usize, ignore: &[Entity]) -> bool {
+    let lock = SPATIAL_MAP.lock().unwrap();
+    lock.blocked[idx].0
+        || lock.tile_content[idx]
+            .iter()
+            .any(|(e, blocks)| *blocks && !ignore.contains(e))
+}
+
+pub fn is_blocked_by_entity(idx: usize) -> bool {
+    let lock = SPATIAL_MAP.lock().unwrap();
+    lock.blocked[idx].1
+}
+
+/// Updates whether the terrain itself blocks a tile, after it has been dug out or built up.
+pub fn set_terrain_blocked(idx: usize, blocked: bool) {
+    let mut lock = SPATIAL_MAP.lock().unwrap();
+    lock.blocked[idx] = (blocked, lock.blocked[idx].1);
+    lock.terrain_revision += 1;
+}
+
+/// Changes whenever the terrain does, so anything worked out from it knows when to start over.
+pub fn terrain_revision() -> u64 { SPATIAL_MAP.lock().unwrap().terrain_revision }
+
 pub fn set_blocked(idx: usize, blocked: bool) {
     let mut lock = SPATIAL_MAP.lock().unwrap();
     lock.blocked[idx] = (lock.blocked[idx].0, blocked);
+    lock.occupied.push(idx);
 }
 
 ///////////////////////////////////////////////////////////////////////////
@@ -97,6 +153,7 @@ where F: FnMut(Entity) -> Option<RunState> {
 pub fn index_entity(entity: Entity, idx: usize, blocks_tile: bool) {
     let mut lock = SPATIAL_MAP.lock().unwrap();
     lock.tile_content[idx].push((entity, blocks_tile));
+    lock.occupied.push(idx);
 
     if blocks_tile {
         lock.blocked[idx].1 = true;
@@ -116,6 +173,7 @@ pub fn move_entity(entity: Entity, moving_from: usize, moving_to: usize) {
         }
     });
     lock.tile_content[moving_to].push((entity, entity_blocks));
+    lock.occupied.push(moving_to);
 
     // Recalculate blocks for both tiles
     let mut from_blocked = false;
@@ -136,6 +194,34 @@ pub fn move_entity(entity: Entity, moving_from: usize, moving_to: usize) {
     lock.blocked[moving_to].1 = to_blocked;
 }
 
+/// Moves an entity that covers several tiles, such as a dragon, from one footprint to another.
+pub fn move_entity_footprint(entity: Entity, moving_from: &[usize], moving_to: &[usize]) {
+    let mut lock = SPATIAL_MAP.lock().unwrap();
+    let mut entity_blocks = false;
+
+    for idx in moving_from.iter() {
+        lock.tile_content[*idx].retain(|(e, blocks)| {
+            if *e == entity {
+                entity_blocks = *blocks;
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    for idx in moving_to.iter() {
+        lock.tile_content[*idx].push((entity, entity_blocks));
+        lock.occupied.push(*idx);
+    }
+
+    // Recalculate blocks for every tile touched
+    for idx in moving_from.iter().chain(moving_to.iter()) {
+        let blocked = lock.tile_content[*idx].iter().any(|(_, blocks)| *blocks);
+        lock.blocked[*idx].1 = blocked;
+    }
+}
+
 pub fn remove_entity(entity: Entity, idx: usize) {
     let mut lock = SPATIAL_MAP.lock().unwrap();
     lock.tile_content[idx].retain(|(e, _)| *e != entity);
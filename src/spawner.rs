@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use crate::gamesystem;
 use crate::prelude::*;
@@ -11,6 +11,8 @@ pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
     skills.skills.insert(Skill::Melee, 1);
     skills.skills.insert(Skill::Defense, 1);
     skills.skills.insert(Skill::Magic, 1);
+    skills.skills.insert(Skill::Perception, 1);
+    skills.skills.insert(Skill::Lockpicking, 1);
 
     #[rustfmt::skip]
     let player = ecs
@@ -91,7 +93,18 @@ pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
 
 const MAX_MONSTERS: i32 = 4;
 
-fn room_table(map_depth: i32) -> MasterTable { raws::get_spawn_table_for_depth(&RAWS.lock().unwrap(), map_depth) }
+fn room_table(map: &Map, near: usize, map_depth: i32) -> MasterTable {
+    raws::get_spawn_table_for_level(&RAWS.lock().unwrap(), map, near, map_depth)
+}
+
+/// How many of each thing there is in a list of spawns.
+fn tally(spawn_list: &[(usize, String)]) -> HashMap<String, i32> {
+    let mut placed: HashMap<String, i32> = HashMap::new();
+    for (_, name) in spawn_list.iter() {
+        *placed.entry(name.clone()).or_insert(0) += 1;
+    }
+    placed
+}
 
 /// Fills a room with stuff!
 pub fn spawn_room(map: &Map, room: &Rect, map_depth: i32, spawn_list: &mut Vec<(usize, String)>) {
@@ -112,9 +125,13 @@ pub fn spawn_room(map: &Map, room: &Rect, map_depth: i32, spawn_list: &mut Vec<(
 }
 
 /// Fills a region with stuff!
-pub fn spawn_region(_map: &Map, area: &[usize], map_depth: i32, spawn_list: &mut Vec<(usize, String)>) {
-    let spawn_table = room_table(map_depth);
-    let mut spawn_points: HashMap<usize, String> = HashMap::new();
+pub fn spawn_region(map: &Map, area: &[usize], map_depth: i32, spawn_list: &mut Vec<(usize, String)>) {
+    if area.is_empty() {
+        return;
+    }
+    let spawn_table = room_table(map, area[0], map_depth);
+    let mut placed = tally(spawn_list);
+    let mut spawn_points: BTreeMap<usize, String> = BTreeMap::new();
     let mut areas: Vec<usize> = Vec::from(area);
 
     // Scope to keep the borrow checker happy
@@ -132,7 +149,11 @@ pub fn spawn_region(_map: &Map, area: &[usize], map_depth: i32, spawn_list: &mut
                 if areas.len() == 1 { 0usize } else { (crate::rng::roll_dice(1, areas.len() as i32) - 1) as usize };
 
             let map_idx = areas[array_index];
-            spawn_points.insert(map_idx, spawn_table.roll());
+            let name = spawn_table.roll(&placed);
+            if name != "None" {
+                *placed.entry(name.clone()).or_insert(0) += 1;
+                spawn_points.insert(map_idx, name);
+            }
             areas.remove(array_index);
         }
     }
@@ -143,8 +164,68 @@ pub fn spawn_region(_map: &Map, area: &[usize], map_depth: i32, spawn_list: &mut
     }
 }
 
+/// Brings a finished level's spawns within what the spawn table allows: anything there is too much of is thinned
+/// out, and anything there is too little of is added on free floor away from the start. Levels can be built in
+/// pieces, so this is only settled once they are whole.
+pub fn spawn_quotas(map: &Map, start: usize, spawn_list: &mut Vec<(usize, String)>) {
+    let quotas = raws::get_spawn_quotas_for_level(&RAWS.lock().unwrap(), map, start, map.depth);
+    if quotas.is_empty() {
+        return;
+    }
+
+    for (name, _, max) in quotas.iter() {
+        if let Some(max) = max {
+            let mut found: Vec<usize> = (0..spawn_list.len()).filter(|i| spawn_list[*i].1 == *name).collect();
+            while found.len() as i32 > *max {
+                let i = found.remove((crate::rng::roll_dice(1, found.len() as i32) - 1) as usize);
+                spawn_list[i].1 = "None".to_string();
+            }
+        }
+    }
+    spawn_list.retain(|(_, name)| name != "None");
+
+    let placed = tally(spawn_list);
+    let taken: HashSet<usize> = spawn_list.iter().map(|(idx, _)| *idx).collect();
+    let mut free = Vec::new();
+    for y in 1..map.height - 1 {
+        for x in 1..map.width - 1 {
+            let idx = map.xy_idx(x, y);
+            if map::tile_walkable(map.tiles[idx])
+                && !map::endangers(map.tiles[idx], None)
+                && !map.is_stairs(idx)
+                && idx != start
+                && !taken.contains(&idx)
+            {
+                free.push(idx);
+            }
+        }
+    }
+
+    for (name, min, _) in quotas.iter() {
+        for _ in placed.get(name).copied().unwrap_or(0)..*min {
+            if free.is_empty() {
+                return;
+            }
+            let idx = free.remove((crate::rng::roll_dice(1, free.len() as i32) - 1) as usize);
+            spawn_list.push((idx, name.clone()));
+        }
+    }
+}
+
+/// How many of each named thing is on the current level.
+fn level_census(ecs: &World) -> HashMap<String, i32> {
+    let positions = ecs.read_storage::<Position>();
+    let names = ecs.read_storage::<Name>();
+
+    let mut census: HashMap<String, i32> = HashMap::new();
+    for (_pos, name) in (&positions, &names).join() {
+        *census.entry(name.name.clone()).or_insert(0) += 1;
+    }
+    census
+}
+
 /// Spawns a named entity (name in tuple.1) at the location in (tuple.0)
-pub fn spawn_entity(ecs: &mut World, spawn: &(&usize, &String)) {
+pub fn spawn_entity(ecs: &mut World, spawn: &(&usize, &String)) -> Option<Entity> {
     let map = ecs.fetch::<Map>();
 
     let width = map.width as usize;
@@ -154,11 +235,11 @@ pub fn spawn_entity(ecs: &mut World, spawn: &(&usize, &String)) {
     std::mem::drop(map);
 
     let spawn_result = raws::spawn_named_entity(&RAWS.lock().unwrap(), ecs, spawn.1, SpawnType::AtPosition { x, y });
-    if spawn_result.is_some() {
-        return;
+    if spawn_result.is_none() {
+        rltk::console::log(format!("WARNING: We don't know how to spawn [{}]!", spawn.1));
     }
 
-    rltk::console::log(format!("WARNING: We don't know how to spawn [{}]!", spawn.1));
+    spawn_result
 }
 
 pub fn spawn_town_portal(ecs: &mut World) {
@@ -167,6 +248,7 @@ pub fn spawn_town_portal(ecs: &mut World) {
     let player_pos = ecs.fetch::<rltk::Point>();
 
     let player_depth = map.depth;
+    let player_branch = map.branch;
     let player_x = player_pos.x;
     let player_y = player_pos.y;
 
@@ -175,7 +257,7 @@ pub fn spawn_town_portal(ecs: &mut World) {
 
     // Find part of the town for the portal
     let dm = ecs.fetch::<MasterDungeonMap>();
-    let town_map = dm.get_map(1).unwrap();
+    let town_map = dm.get_map(MAIN_BRANCH, 1).unwrap();
     let mut stairs_idx = 0;
     for (idx, tt) in town_map.tiles.iter().enumerate() {
         if *tt == TileType::DownStairs {
@@ -193,6 +275,8 @@ pub fn spawn_town_portal(ecs: &mut World) {
             x: portal_x,
             y: portal_y,
             depth: 1,
+            branch: MAIN_BRANCH,
+            area: map::WorldCoord::TOWN,
         })
         .with(Renderable {
             glyph: rltk::to_cp437('♥'),
@@ -205,6 +289,7 @@ pub fn spawn_town_portal(ecs: &mut World) {
             x: player_x,
             y: player_y,
             depth: player_depth,
+            branch: player_branch,
             player_only: true,
         })
         .with(Name {
@@ -213,3 +298,198 @@ pub fn spawn_town_portal(ecs: &mut World) {
         .with(SingleActivation {})
         .build();
 }
+
+///////////////////////////////////////////////////////////////////////////
+// Wandering Monsters
+///////////////////////////////////////////////////////////////////////////
+
+/// How close to a staircase new arrivals turn up.
+const WANDERER_STAIR_RADIUS: f32 = 6.0;
+/// New arrivals never appear closer than this to the player.
+const WANDERER_MIN_PLAYER_DISTANCE: f32 = 12.0;
+
+/// Called once per player turn: the longer the player lingers on a level, the more likely something new shows up.
+pub fn wandering_monsters(ecs: &mut World) {
+    let depth = ecs.fetch::<Map>().depth;
+    let rules = match raws::get_wandering_monsters_for_depth(&RAWS.lock().unwrap(), depth) {
+        Some(rules) => rules,
+        None => return,
+    };
+
+    let turns_here = {
+        let dm = ecs.fetch::<MasterDungeonMap>();
+        dm.turn - dm.arrived_at
+    };
+
+    if turns_here < rules.grace_turns || rules.turns_per_check < 1 || turns_here % rules.turns_per_check != 0 {
+        return;
+    }
+
+    if crate::rng::roll_dice(1, 100) <= rules.chance {
+        spawn_wanderers(ecs, depth, 1, rules.max_population);
+    }
+}
+
+/// Called when returning to a level, with the number of turns the player was away.
+pub fn returning_wanderers(ecs: &mut World, turns_away: i32) {
+    let depth = ecs.fetch::<Map>().depth;
+    if let Some(rules) = raws::get_wandering_monsters_for_depth(&RAWS.lock().unwrap(), depth) {
+        if rules.turns_away_per_spawn > 0 {
+            let count = i32::min(turns_away / rules.turns_away_per_spawn, rules.max_on_return);
+            spawn_wanderers(ecs, depth, count, rules.max_population);
+        }
+    }
+}
+
+fn spawn_wanderers(ecs: &mut World, depth: i32, count: i32, max_population: i32) {
+    let population = {
+        let player_entity = ecs.fetch::<Entity>();
+        let positions = ecs.read_storage::<Position>();
+        let pools = ecs.read_storage::<Pools>();
+        let vendors = ecs.read_storage::<Vendor>();
+
+        (&ecs.entities(), &positions, &pools)
+            .join()
+            .filter(|(e, _, _)| *e != *player_entity && vendors.get(*e).is_none())
+            .count() as i32
+    };
+
+    let count = i32::min(count, max_population - population);
+    if count < 1 {
+        return;
+    }
+
+    let mut spawn_points = wanderer_spawn_points(ecs);
+    let spawn_table = {
+        let map = ecs.fetch::<Map>();
+        let player_pos = ecs.fetch::<Point>();
+        room_table(&map, map.xy_idx(player_pos.x, player_pos.y), depth)
+    };
+    let mut placed = level_census(ecs);
+
+    for _ in 0..count {
+        if spawn_points.is_empty() {
+            return;
+        }
+
+        let name = spawn_table.roll_mob(&placed);
+        if name == "None" {
+            continue;
+        }
+        *placed.entry(name.clone()).or_insert(0) += 1;
+
+        let idx = spawn_points.remove((crate::rng::roll_dice(1, spawn_points.len() as i32) - 1) as usize);
+        spawn_entity(ecs, &(&idx, &name));
+    }
+}
+
+/// Free tiles near the stairs that the player can't see - as if the new arrivals had just come up or down.
+fn wanderer_spawn_points(ecs: &World) -> Vec<usize> {
+    let map = ecs.fetch::<Map>();
+    let player_pos = *ecs.fetch::<Point>();
+    let positions = ecs.read_storage::<Position>();
+    let blockers = ecs.read_storage::<BlocksTile>();
+
+    let occupied: Vec<usize> = (&positions, &blockers)
+        .join()
+        .map(|(pos, _)| map.xy_idx(pos.x, pos.y))
+        .collect();
+
+    let stairs: Vec<Point> = map
+        .tiles
+        .iter()
+        .enumerate()
+        .filter(|(_, tt)| matches!(**tt, TileType::UpStairs | TileType::DownStairs))
+        .map(|(idx, _)| Point::new(idx as i32 % map.width, idx as i32 / map.width))
+        .collect();
+
+    let free_and_hidden = |idx: usize, pt: Point| {
+        map::tile_walkable(map.tiles[idx])
+            && !map::endangers(map.tiles[idx], None)
+            && !map.visible_tiles[idx]
+            && !occupied.contains(&idx)
+            && rltk::DistanceAlg::Pythagoras.distance2d(pt, player_pos) >= WANDERER_MIN_PLAYER_DISTANCE
+    };
+
+    let mut near_stairs = Vec::new();
+    let mut anywhere = Vec::new();
+    for y in 1..map.height - 1 {
+        for x in 1..map.width - 1 {
+            let idx = map.xy_idx(x, y);
+            let pt = Point::new(x, y);
+            if free_and_hidden(idx, pt) {
+                if stairs
+                    .iter()
+                    .any(|s| rltk::DistanceAlg::Pythagoras.distance2d(*s, pt) <= WANDERER_STAIR_RADIUS)
+                {
+                    near_stairs.push(idx);
+                }
+                anywhere.push(idx);
+            }
+        }
+    }
+
+    // Levels whose stairs are all in plain view still get visitors, just from somewhere else
+    if near_stairs.is_empty() { anywhere } else { near_stairs }
+}
+
+///////////////////////////////////////////////////////////////////////////
+// Encounters
+///////////////////////////////////////////////////////////////////////////
+
+/// How close to the player an ambush springs from, and how far.
+const AMBUSH_RANGE: (f32, f32) = (3.0, 7.0);
+
+/// Sets a band of whatever roams the area on the player, who has just been caught out on the road.
+pub fn ambush(ecs: &mut World) {
+    let mut spawn_points = {
+        let map = ecs.fetch::<Map>();
+        let player_pos = *ecs.fetch::<Point>();
+        let positions = ecs.read_storage::<Position>();
+        let blockers = ecs.read_storage::<BlocksTile>();
+
+        let occupied: Vec<usize> = (&positions, &blockers)
+            .join()
+            .map(|(pos, _)| map.xy_idx(pos.x, pos.y))
+            .collect();
+
+        let mut spawn_points = Vec::new();
+        for y in 1..map.height - 1 {
+            for x in 1..map.width - 1 {
+                let idx = map.xy_idx(x, y);
+                let distance = rltk::DistanceAlg::Pythagoras.distance2d(Point::new(x, y), player_pos);
+                if distance >= AMBUSH_RANGE.0
+                    && distance <= AMBUSH_RANGE.1
+                    && map::tile_walkable(map.tiles[idx])
+                    && !map::endangers(map.tiles[idx], None)
+                    && !occupied.contains(&idx)
+                {
+                    spawn_points.push(idx);
+                }
+            }
+        }
+        spawn_points
+    };
+
+    let spawn_table = {
+        let map = ecs.fetch::<Map>();
+        let player_pos = ecs.fetch::<Point>();
+        room_table(&map, map.xy_idx(player_pos.x, player_pos.y), map.depth)
+    };
+    let mut placed = level_census(ecs);
+
+    for _ in 0..crate::rng::roll_dice(1, 3) + 1 {
+        if spawn_points.is_empty() {
+            return;
+        }
+
+        let name = spawn_table.roll_mob(&placed);
+        if name == "None" {
+            continue;
+        }
+        *placed.entry(name.clone()).or_insert(0) += 1;
+
+        let idx = spawn_points.remove((crate::rng::roll_dice(1, spawn_points.len() as i32) - 1) as usize);
+        spawn_entity(ecs, &(&idx, &name));
+    }
+}
@@ -1,6 +1,6 @@
 use specs::prelude::*;
 
-use super::{Map, RunState, State};
+use super::{Map, MasterDungeonMap, RunState, State};
 use crate::{components::*, gui::CheatMenuResult};
 
 ///////////////////////////////////////////////////////////////////////////
@@ -12,9 +12,18 @@ impl State {
             CheatMenuResult::NoResponse => RunState::ShowCheatMenu,
             CheatMenuResult::Cancel => RunState::AwaitingInput,
             CheatMenuResult::TeleportToExit => {
-                self.goto_level(1);
-                self.mapgen_next_state = Some(RunState::PreRun);
-                RunState::MapGeneration
+                let below = {
+                    let map = self.ecs.fetch::<Map>();
+                    self.ecs.fetch::<MasterDungeonMap>().stairs_down(map.branch, map.depth)
+                };
+                match below {
+                    Some(below) => {
+                        self.goto_level(below.branch, below.depth, 1);
+                        self.mapgen_next_state = Some(RunState::PreRun);
+                        RunState::MapGeneration
+                    },
+                    None => RunState::AwaitingInput,
+                }
             },
             CheatMenuResult::Heal => {
                 let player = self.ecs.fetch::<Entity>();
@@ -37,6 +46,25 @@ impl State {
                 player_pools.god_mode = true;
                 RunState::AwaitingInput
             },
+            CheatMenuResult::ExportLevel => {
+                let level = crate::map::LevelFile::from_world(&self.ecs);
+                for path in [
+                    format!("./level_{}.txt", level.depth),
+                    format!("./level_{}.json", level.depth),
+                ] {
+                    match level.save(&path) {
+                        Ok(()) => crate::gamelog::Logger::new()
+                            .append("Level exported to")
+                            .append(&path)
+                            .log(),
+                        Err(e) => crate::gamelog::Logger::new()
+                            .append("Unable to export level:")
+                            .append(e)
+                            .log(),
+                    }
+                }
+                RunState::AwaitingInput
+            },
         }
     }
 }
@@ -22,7 +22,7 @@ impl State {
         self.ecs.delete_entity(item.unwrap()).expect("Unable to delete");
     }
 
-    pub fn buy_items(&mut self, tag: Option<String>, price: Option<f32>) {
+    pub fn buy_items(&mut self, vendor: Entity, tag: Option<String>, price: Option<f32>) {
         let tag = tag.unwrap();
         let price = price.unwrap();
         let player_entity = self.ecs.fetch::<Entity>();
@@ -41,6 +41,10 @@ impl State {
             player_pools.gold -= price;
             std::mem::drop(pools);
 
+            if let Some(vendor) = self.ecs.write_storage::<Vendor>().get_mut(vendor) {
+                *vendor.sold.entry(tag.clone()).or_insert(0) += 1;
+            }
+
             let player_entity = *self.ecs.fetch::<Entity>();
             raws::spawn_named_item(
                 &RAWS.lock().unwrap(),
@@ -16,6 +16,22 @@ pub struct State {
     pub(crate) dispatcher: Box<dyn crate::systems::UnifiedDispatcher + 'static>,
 }
 
+impl State {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> State {
+        State {
+            ecs: World::new(),
+            mapgen_index: 0,
+            mapgen_history: Vec::new(),
+            mapgen_timer: 0.0,
+            mapgen_next_state: Some(RunState::MainMenu {
+                menu_selection: crate::gui::MainMenuSelection::NewGame,
+            }),
+            dispatcher: crate::systems::build(),
+        }
+    }
+}
+
 ///////////////////////////////////////////////////////////////////////////
 // Running Systems
 ///////////////////////////////////////////////////////////////////////////
@@ -30,17 +46,97 @@ impl State {
 // Helper Functions
 ///////////////////////////////////////////////////////////////////////////
 impl State {
-    pub fn goto_level(&mut self, offset: i32) {
+    /// Moves the player to another level without using the stairs: by portal, teleport or a fall.
+    pub fn goto_level(&mut self, branch: usize, new_depth: i32, offset: i32) {
         map::freeze_level_entities(&mut self.ecs);
 
         // Build a new map and place the player
-        let current_depth = self.ecs.fetch::<Map>().depth;
-        self.generate_world_map(current_depth + offset, offset);
+        self.generate_world_map(branch, new_depth, offset);
+
+        // Notify the player
+        crate::gamelog::Logger::new().append("You change level.").log();
+    }
+
+    /// Takes the player up (`offset` -1) or down (`offset` 1) the staircase they are standing on.
+    pub fn take_stairs(&mut self, offset: i32) {
+        map::freeze_level_entities(&mut self.ecs);
+
+        // Build a new map if need be, and place the player at the other end of the stairs
+        self.enter_level(|ecs| map::stairs_transition(ecs, offset));
 
         // Notify the player
         crate::gamelog::Logger::new().append("You change level.").log();
     }
 
+    /// Travels overland to another area of the overworld. An `ambushed` party is set upon the moment it arrives.
+    pub fn travel_to(&mut self, to: map::WorldCoord, ambushed: bool) {
+        let from = self.ecs.fetch::<Map>().area;
+        map::freeze_level_entities(&mut self.ecs);
+
+        let heading = ((to.x - from.x).signum(), (to.y - from.y).signum());
+        self.enter_level(|ecs| map::area_transition(ecs, to, heading));
+
+        let name = self.ecs.fetch::<Map>().name.clone();
+        crate::gamelog::Logger::new()
+            .append("You arrive at")
+            .append_with_color(name, rltk::CYAN)
+            .log();
+        if ambushed {
+            spawner::ambush(&mut self.ecs);
+            crate::gamelog::Logger::new()
+                .append_with_color("You are ambushed on the road!", rltk::RED)
+                .log();
+        }
+    }
+
+    /// Spends `turns` on the road, one at a time. The clock runs, and the player goes hungry and their status effects
+    /// wear off as they would waiting in place; the level being left is frozen, so nothing else moves. Stops early
+    /// if the player doesn't survive the journey.
+    pub fn pass_travel_time(&mut self, turns: i32) {
+        let player_entity = *self.ecs.fetch::<Entity>();
+        for _ in 0..turns {
+            self.ecs.write_resource::<MasterDungeonMap>().turn += 1;
+
+            // Hunger only counts down on the turns someone takes
+            self.ecs
+                .write_storage::<MyTurn>()
+                .insert(player_entity, MyTurn {})
+                .expect("Unable to insert");
+            hunger_system::HungerSystem {}.run_now(&self.ecs);
+            self.ecs.write_storage::<MyTurn>().remove(player_entity);
+
+            {
+                let (entities, mut durations, statuses, dots, mut dirty_equipment) = self.ecs.system_data::<(
+                    Entities,
+                    WriteStorage<Duration>,
+                    ReadStorage<StatusEffect>,
+                    ReadStorage<DamageOverTime>,
+                    WriteStorage<EquipmentChanged>,
+                )>();
+                ai::tick_status_effects(
+                    &entities,
+                    &mut durations,
+                    &statuses,
+                    &dots,
+                    &mut dirty_equipment,
+                    Some(player_entity),
+                );
+            }
+
+            crate::effects::run_effects_queue(&mut self.ecs);
+            self.ecs.maintain();
+
+            let alive = self
+                .ecs
+                .read_storage::<Pools>()
+                .get(player_entity)
+                .is_none_or(|pools| pools.hit_points.current > 0);
+            if !alive {
+                break;
+            }
+        }
+    }
+
     pub fn game_over_cleanup(&mut self) {
         // Delete everything
         let mut to_delete = Vec::new();
@@ -62,21 +158,37 @@ impl State {
         self.ecs.insert(map::MasterDungeonMap::new());
 
         // Build a new map and place the player
-        self.generate_world_map(1, 0);
+        self.generate_world_map(MAIN_BRANCH, 1, 0);
     }
 
-    pub fn generate_world_map(&mut self, new_depth: i32, offset: i32) {
+    pub fn generate_world_map(&mut self, branch: usize, new_depth: i32, offset: i32) {
+        self.enter_level(|ecs| map::level_transition(ecs, branch, new_depth, offset));
+    }
+
+    fn enter_level(&mut self, transition: impl FnOnce(&mut World) -> Option<Vec<Map>>) {
         self.mapgen_index = 0;
         self.mapgen_timer = 0.0;
         self.mapgen_history.clear();
-        let map_building_info = map::level_transition(&mut self.ecs, new_depth, offset);
+        let map_building_info = transition(&mut self.ecs);
 
         if let Some(history) = map_building_info {
             self.mapgen_history = history;
-        } else {
-            map::thaw_level_entities(&mut self.ecs);
         }
 
+        // Even a brand new level may already have visitors, if something fell down to it
+        map::thaw_level_entities(&mut self.ecs);
+
+        self.welcome();
+    }
+
+    pub fn generate_custom_map(&mut self, level: map::LevelFile) {
+        self.mapgen_index = 0;
+        self.mapgen_timer = 0.0;
+        self.mapgen_history = map::custom_level_transition(&mut self.ecs, level);
+        self.welcome();
+    }
+
+    fn welcome(&mut self) {
         crate::gamelog::clear_log();
         crate::gamelog::Logger::new()
             .append("Welcome to")
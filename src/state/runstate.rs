@@ -16,7 +16,8 @@ pub enum RunState {
     TownPortal,
     MapGeneration,
     MagicMapReveal { row: i32 },
-    TeleportingToOtherLevel { x: i32, y: i32, depth: i32 },
+    TeleportingToOtherLevel { x: i32, y: i32, depth: i32, branch: usize },
+    Travel { at: map::WorldCoord, legs: i32 },
     // GUI
     MainMenu { menu_selection: gui::MainMenuSelection },
     ShowCheatMenu,
@@ -83,6 +84,9 @@ impl GameState for State {
                 newrunstate = player::player_input(self, ctx);
                 if newrunstate != RunState::AwaitingInput {
                     crate::gamelog::record_event("Turn", 1);
+                    self.ecs.write_resource::<MasterDungeonMap>().turn += 1;
+                    spawner::wandering_monsters(&mut self.ecs);
+                    map::restock_vendors(&mut self.ecs);
                 }
             },
             RunState::Ticking => {
@@ -99,8 +103,8 @@ impl GameState for State {
                         },
                         RunState::MagicMapReveal { .. } => newrunstate = RunState::MagicMapReveal { row: 0 },
                         RunState::TownPortal => newrunstate = RunState::TownPortal,
-                        RunState::TeleportingToOtherLevel { x, y, depth } => {
-                            newrunstate = RunState::TeleportingToOtherLevel { x, y, depth }
+                        RunState::TeleportingToOtherLevel { x, y, depth, branch } => {
+                            newrunstate = RunState::TeleportingToOtherLevel { x, y, depth, branch }
                         },
                         RunState::ShowRemoveCurse => newrunstate = RunState::ShowRemoveCurse,
                         RunState::ShowIdentify => newrunstate = RunState::ShowIdentify,
@@ -224,7 +228,7 @@ impl GameState for State {
                             mode: VendorMode::Sell,
                         }
                     },
-                    gui::VendorResult::Buy => self.buy_items(result.2, result.3),
+                    gui::VendorResult::Buy => self.buy_items(vendor, result.2, result.3),
                     gui::VendorResult::Sell => self.sell_items(result.1),
                 }
             },
@@ -303,12 +307,12 @@ impl GameState for State {
                 };
             },
             RunState::NextLevel => {
-                self.goto_level(1);
+                self.take_stairs(1);
                 self.mapgen_next_state = Some(RunState::PreRun);
                 newrunstate = RunState::MapGeneration;
             },
             RunState::PreviousLevel => {
-                self.goto_level(-1);
+                self.take_stairs(-1);
                 self.mapgen_next_state = Some(RunState::PreRun);
                 newrunstate = RunState::MapGeneration;
             },
@@ -317,19 +321,24 @@ impl GameState for State {
                 spawner::spawn_town_portal(&mut self.ecs);
 
                 // Transition
-                let map_depth = self.ecs.fetch::<Map>().depth;
-                let destination_offset = 0 - (map_depth - 1);
-
-                self.goto_level(destination_offset);
+                self.goto_level(MAIN_BRANCH, 1, -1);
                 self.mapgen_next_state = Some(RunState::PreRun);
 
                 newrunstate = RunState::MapGeneration;
             },
-            RunState::TeleportingToOtherLevel { x, y, depth } => {
-                self.goto_level(depth - 1);
+            RunState::TeleportingToOtherLevel { x, y, depth, branch } => {
+                let current_depth = self.ecs.fetch::<Map>().depth;
+                self.goto_level(branch, depth, depth - current_depth);
+
+                // Don't leave the player inside a wall (or a lake of lava) on arrival
+                let player_entity = *self.ecs.fetch::<Entity>();
+                let (x, y) = self
+                    .ecs
+                    .fetch::<Map>()
+                    .nearest_safe_tile(x, y, self.ecs.read_storage::<HazardResistance>().get(player_entity))
+                    .unwrap_or((x, y));
 
-                let player_entity = self.ecs.fetch::<Entity>();
-                if let Some(pos) = self.ecs.write_storage::<Position>().get_mut(*player_entity) {
+                if let Some(pos) = self.ecs.write_storage::<Position>().get_mut(player_entity) {
                     pos.x = x;
                     pos.y = y;
                 }
@@ -341,6 +350,36 @@ impl GameState for State {
 
                 newrunstate = RunState::MapGeneration;
             },
+            RunState::Travel { at, legs } => {
+                let result = gui::show_travel_map(self, ctx, at);
+
+                // `legs` counts the areas crossed so far. The time they took is only spent once the player sets off, so
+                // backing out of the map costs nothing
+                match result {
+                    gui::TravelResult::NoResponse => {},
+                    gui::TravelResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::TravelResult::Stop => {
+                        if at == self.ecs.fetch::<Map>().area {
+                            newrunstate = RunState::AwaitingInput;
+                        } else {
+                            self.pass_travel_time(legs * map::TRAVEL_TURNS);
+                            self.travel_to(at, false);
+                            self.mapgen_next_state = Some(RunState::PreRun);
+                            newrunstate = RunState::MapGeneration;
+                        }
+                    },
+                    gui::TravelResult::Go { to } => {
+                        if map::roll_encounter(to) {
+                            self.pass_travel_time((legs + 1) * map::TRAVEL_TURNS);
+                            self.travel_to(to, true);
+                            self.mapgen_next_state = Some(RunState::PreRun);
+                            newrunstate = RunState::MapGeneration;
+                        } else {
+                            newrunstate = RunState::Travel { at: to, legs: legs + 1 };
+                        }
+                    },
+                }
+            },
             RunState::MagicMapReveal { row } => {
                 let mut map = self.ecs.fetch_mut::<Map>();
                 for x in 0..map.width {
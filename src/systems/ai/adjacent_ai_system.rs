@@ -24,14 +24,19 @@ impl<'a> System<'a> for AdjacentAI {
         Entities<'a>,
         ReadExpect<'a, Entity>,
         ReadStorage<'a, TileSize>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, Weapon>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut turns, factions, positions, map, mut want_melee, entities, player, sizes) = data;
+        let (mut turns, factions, positions, map, mut want_melee, entities, player, sizes, equipped, weapons) = data;
 
         let mut turn_done: Vec<Entity> = Vec::new();
         for (entity, _turn, my_faction, pos) in (&entities, &turns, &factions, &positions).join() {
-            if entity != *player {
+            // Archers and other ranged attackers decide for themselves whether to back off or stand and shoot
+            let is_ranged = super::ranged_tactics::equipped_ranged_weapon(entity, &equipped, &weapons).is_some();
+
+            if entity != *player && !is_ranged {
                 let mut reactions: Vec<(Entity, Reaction)> = Vec::new();
                 let idx = map.xy_idx(pos.x, pos.y);
                 let w = map.width;
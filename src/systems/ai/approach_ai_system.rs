@@ -1,40 +1,92 @@
 use specs::prelude::*;
 
-use super::{ApplyMove, Map, MyTurn, Position, WantsToApproach};
+use super::{
+    ApplyMove, Faction, HazardResistance, Map, MyTurn, Position, TileSize, WantsToApproach, map::ClearanceMap,
+    map::FlowFields, map::endangers,
+};
 
 pub struct ApproachAI {}
 
 impl<'a> System<'a> for ApproachAI {
+    #[allow(clippy::type_complexity)]
     type SystemData = (
         WriteStorage<'a, MyTurn>,
         WriteStorage<'a, WantsToApproach>,
         ReadStorage<'a, Position>,
-        WriteExpect<'a, Map>,
+        ReadExpect<'a, Map>,
         Entities<'a>,
         WriteStorage<'a, ApplyMove>,
+        WriteExpect<'a, FlowFields>,
+        ReadStorage<'a, TileSize>,
+        ReadStorage<'a, Faction>,
+        ReadExpect<'a, Entity>,
+        ReadStorage<'a, HazardResistance>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut turns, mut want_approach, positions, map, entities, mut apply_move) = data;
+        let (
+            mut turns,
+            mut want_approach,
+            positions,
+            map,
+            entities,
+            mut apply_move,
+            mut flow,
+            sizes,
+            factions,
+            player_entity,
+            resistances,
+        ) = data;
+
+        flow.sync();
+
+        let player_idx = positions.get(*player_entity).map(|pos| map.xy_idx(pos.x, pos.y));
 
         let mut turn_done: Vec<Entity> = Vec::new();
         for (entity, pos, approach, _myturn) in (&entities, &positions, &want_approach, &turns).join() {
             turn_done.push(entity);
 
-            let path = rltk::a_star_search(
-                map.xy_idx(pos.x, pos.y) as i32,
-                map.xy_idx(approach.idx % map.width, approach.idx / map.width) as i32,
-                &*map,
-            );
+            let my_idx = map.xy_idx(pos.x, pos.y);
+            let target_idx = approach.idx as usize;
+            let safe = |idx: usize| !endangers(map.tiles[idx], resistances.get(entity));
 
-            if path.success && path.steps.len() > 1 {
-                apply_move
-                    .insert(
-                        entity,
-                        ApplyMove {
-                            dest_idx: path.steps[1],
+            // Everyone closing in on the player shares one field, and everyone after the same creature shares another
+            let mut quarry: Option<Entity> = None;
+            if Some(target_idx) != player_idx {
+                crate::spatial::for_each_tile_content(target_idx, |other| {
+                    if quarry.is_none() && other != entity && factions.get(other).is_some() {
+                        quarry = Some(other);
+                    }
+                });
+            }
+
+            let field = if Some(target_idx) == player_idx {
+                Some(flow.toward(&map, &[target_idx]))
+            } else {
+                quarry.map(|quarry| flow.toward_creature(&map, quarry, target_idx))
+            };
+
+            let step = match (field, sizes.get(entity)) {
+                // Large creatures can only take steps their whole body fits into
+                (Some(field), Some(size)) => {
+                    FlowFields::step_toward(field, my_idx, &ClearanceMap::new(&map, size, vec![entity]), safe)
+                },
+                (Some(field), None) => FlowFields::step_toward(field, my_idx, &*map, safe),
+                // Anywhere else is a one-off, such as a shooter stepping aside, and not worth a whole field
+                (None, size) => {
+                    let path = match size {
+                        Some(size) => {
+                            rltk::a_star_search(my_idx, target_idx, &ClearanceMap::new(&map, size, vec![entity]))
                         },
-                    )
+                        None => rltk::a_star_search(my_idx, target_idx, &*map),
+                    };
+                    if path.success && path.steps.len() > 1 && safe(path.steps[1]) { Some(path.steps[1]) } else { None }
+                },
+            };
+
+            if let Some(step) = step {
+                apply_move
+                    .insert(entity, ApplyMove { dest_idx: step })
                     .expect("Unable to insert");
             }
         }
@@ -1,7 +1,12 @@
 use specs::prelude::*;
 use std::collections::HashMap;
 
-use super::{ApplyMove, Chasing, Map, MyTurn, Position, TileSize};
+use super::{
+    ApplyMove, Chasing, HazardResistance, Map, MyTurn, Position, TileSize, map::ClearanceMap, map::FlowFields,
+    map::endangers,
+};
+
+const MAX_CHASE_DISTANCE: f32 = 15.0;
 
 pub struct ChaseAI {}
 
@@ -10,14 +15,30 @@ impl<'a> System<'a> for ChaseAI {
         WriteStorage<'a, MyTurn>,
         WriteStorage<'a, Chasing>,
         ReadStorage<'a, Position>,
-        WriteExpect<'a, Map>,
+        ReadExpect<'a, Map>,
         Entities<'a>,
         WriteStorage<'a, ApplyMove>,
         ReadStorage<'a, TileSize>,
+        WriteExpect<'a, FlowFields>,
+        ReadExpect<'a, Entity>,
+        ReadStorage<'a, HazardResistance>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut turns, mut chasing, positions, map, entities, mut apply_move, sizes) = data;
+        let (
+            mut turns,
+            mut chasing,
+            positions,
+            map,
+            entities,
+            mut apply_move,
+            sizes,
+            mut flow,
+            player_entity,
+            resistances,
+        ) = data;
+
+        flow.sync();
 
         let mut targets: HashMap<Entity, (i32, i32)> = HashMap::new();
         let mut end_chase: Vec<Entity> = Vec::new();
@@ -37,38 +58,47 @@ impl<'a> System<'a> for ChaseAI {
         end_chase.clear();
 
         let mut turn_done: Vec<Entity> = Vec::new();
-        for (entity, pos, _chase, _myturn) in (&entities, &positions, &chasing, &turns).join() {
+        for (entity, pos, chase, _myturn) in (&entities, &positions, &chasing, &turns).join() {
             turn_done.push(entity);
 
             let target_pos = targets[&entity];
+            let my_idx = map.xy_idx(pos.x, pos.y);
+            let target_idx = map.xy_idx(target_pos.0, target_pos.1);
+            let safe = |idx: usize| !endangers(map.tiles[idx], resistances.get(entity));
 
-            let path = if let Some(size) = sizes.get(entity) {
-                let mut map_copy = map.clone();
-
-                map_copy.populate_blocked_multi(size.x, size.y);
-
-                rltk::a_star_search(
-                    map_copy.xy_idx(pos.x, pos.y),
-                    map_copy.xy_idx(target_pos.0, target_pos.1),
-                    &map_copy,
-                )
+            // Whether the quarry is still within reach, and the step to take toward it if one is free
+            let (in_reach, step) = if chase.target == *player_entity && sizes.get(entity).is_none() {
+                // Everyone after the player shares one field
+                let field = flow.toward(&map, &[target_idx]);
+                match FlowFields::distance(field, my_idx) {
+                    Some(d) if d < MAX_CHASE_DISTANCE => (true, FlowFields::step_toward(field, my_idx, &*map, safe)),
+                    _ => (false, None),
+                }
             } else {
-                rltk::a_star_search(map.xy_idx(pos.x, pos.y), map.xy_idx(target_pos.0, target_pos.1), &*map)
+                // Anyone else is chased down on their own, along a path the chaser's whole body fits
+                let size = sizes.get(entity).cloned().unwrap_or(TileSize { x: 1, y: 1 });
+                let clearance = ClearanceMap::new(&map, &size, vec![entity, chase.target]);
+                let path = rltk::a_star_search(my_idx, target_idx, &clearance);
+
+                if path.success && path.steps.len() > 1 && path.steps.len() < MAX_CHASE_DISTANCE as usize {
+                    // The end of the path overlaps the quarry itself, so only take steps we really fit into
+                    let step = path.steps[1];
+                    let fits = ClearanceMap::new(&map, &size, vec![entity])
+                        .fits(step as i32 % map.width, step as i32 / map.width);
+                    (true, if fits && safe(step) { Some(step) } else { None })
+                } else {
+                    (false, None)
+                }
             };
 
-            if path.success && path.steps.len() > 1 && path.steps.len() < 15 {
+            if let Some(step) = step {
                 apply_move
-                    .insert(
-                        entity,
-                        ApplyMove {
-                            dest_idx: path.steps[1],
-                        },
-                    )
+                    .insert(entity, ApplyMove { dest_idx: step })
                     .expect("Unable to insert");
-                turn_done.push(entity);
-            } else {
+            } else if !in_reach {
                 end_chase.push(entity);
             }
+            // Otherwise someone is in the way - wait for the path to clear
         }
 
         for done in end_chase.iter() {
@@ -1,6 +1,9 @@
 use specs::prelude::*;
 
-use super::{map::tile_walkable, ApplyMove, Map, MoveMode, Movement, MyTurn, Position};
+use super::{
+    map::{endangers, tile_walkable},
+    ApplyMove, HazardResistance, Map, MoveMode, Movement, MyTurn, Position,
+};
 
 pub struct DefaultMoveAI {}
 
@@ -12,14 +15,16 @@ impl<'a> System<'a> for DefaultMoveAI {
         WriteExpect<'a, Map>,
         WriteStorage<'a, ApplyMove>,
         Entities<'a>,
+        ReadStorage<'a, HazardResistance>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut turns, mut move_mode, positions, map, mut apply_move, entities) = data;
+        let (mut turns, mut move_mode, positions, map, mut apply_move, entities, resistances) = data;
 
         let mut turn_done: Vec<Entity> = Vec::new();
         for (entity, pos, mut mode, _myturn) in (&entities, &positions, &mut move_mode, &turns).join() {
             turn_done.push(entity);
+            let safe = |idx: usize| !endangers(map.tiles[idx], resistances.get(entity));
 
             match &mut mode.mode {
                 Movement::Static => {},
@@ -38,7 +43,7 @@ impl<'a> System<'a> for DefaultMoveAI {
 
                     if x > 0 && x < map.width - 1 && y > 0 && y < map.height - 1 {
                         let dest_idx = map.xy_idx(x, y);
-                        if !crate::spatial::is_blocked(dest_idx) {
+                        if !crate::spatial::is_blocked(dest_idx) && safe(dest_idx) {
                             apply_move
                                 .insert(entity, ApplyMove { dest_idx })
                                 .expect("Unable to insert");
@@ -50,7 +55,10 @@ impl<'a> System<'a> for DefaultMoveAI {
                 Movement::RandomWaypoint { path } => {
                     if let Some(path) = path {
                         // We have a target - go there
-                        if path.len() > 1 {
+                        if path.len() > 1 && !safe(path[1]) {
+                            // The only way on is through a hazard, so pick somewhere else to go
+                            mode.mode = Movement::RandomWaypoint { path: None };
+                        } else if path.len() > 1 {
                             if !crate::spatial::is_blocked(path[1] as usize) {
                                 apply_move
                                     .insert(entity, ApplyMove { dest_idx: path[1] })
@@ -68,7 +76,7 @@ impl<'a> System<'a> for DefaultMoveAI {
                         let target_y = crate::rng::roll_dice(1, map.height - 2);
                         let idx = map.xy_idx(target_x, target_y);
 
-                        if tile_walkable(map.tiles[idx]) {
+                        if tile_walkable(map.tiles[idx]) && safe(idx) {
                             let path =
                                 rltk::a_star_search(map.xy_idx(pos.x, pos.y), map.xy_idx(target_x, target_y), &*map);
 
@@ -1,6 +1,9 @@
 use specs::prelude::*;
 
-use super::{ApplyMove, Map, MyTurn, Position, WantsToFlee};
+use super::{
+    ApplyMove, HazardResistance, Map, MyTurn, Position, TileSize, WantsToFlee, map::ClearanceMap, map::FlowFields,
+    map::endangers,
+};
 
 pub struct FleeAI {}
 
@@ -9,26 +12,36 @@ impl<'a> System<'a> for FleeAI {
         WriteStorage<'a, MyTurn>,
         WriteStorage<'a, WantsToFlee>,
         WriteStorage<'a, Position>,
-        WriteExpect<'a, Map>,
+        ReadExpect<'a, Map>,
         Entities<'a>,
         WriteStorage<'a, ApplyMove>,
+        WriteExpect<'a, FlowFields>,
+        ReadStorage<'a, TileSize>,
+        ReadStorage<'a, HazardResistance>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut turns, mut want_flee, positions, mut map, entities, mut apply_move) = data;
+        let (mut turns, mut want_flee, positions, map, entities, mut apply_move, mut flow, sizes, resistances) = data;
+
+        flow.sync();
 
         let mut turn_done: Vec<Entity> = Vec::new();
         for (entity, pos, flee, _myturn) in (&entities, &positions, &want_flee, &turns).join() {
             turn_done.push(entity);
 
             let my_idx = map.xy_idx(pos.x, pos.y);
-            map.populate_blocked();
-
-            let flee_map = rltk::DijkstraMap::new(map.width as usize, map.height as usize, &flee.indices, &*map, 100.0);
-            let flee_target = rltk::DijkstraMap::find_highest_exit(&flee_map, my_idx, &*map);
+            let safe = |idx: usize| !endangers(map.tiles[idx], resistances.get(entity));
+            let flee_map = flow.away_from(&map, &flee.indices);
+            let flee_target = match sizes.get(entity) {
+                // Large creatures can only take steps their whole body fits into
+                Some(size) => {
+                    FlowFields::step_away(flee_map, my_idx, &ClearanceMap::new(&map, size, vec![entity]), safe)
+                },
+                None => FlowFields::step_away(flee_map, my_idx, &*map, safe),
+            };
 
             if let Some(flee_target) = flee_target {
-                if !crate::spatial::is_blocked(flee_target as usize) {
+                if !crate::spatial::is_blocked_ignoring(flee_target, &[entity]) {
                     apply_move
                         .insert(entity, ApplyMove { dest_idx: flee_target })
                         .expect("Unable to insert");
@@ -93,28 +93,41 @@ impl<'a> System<'a> for InitiativeSystem {
 
         // Handle durations
         if *runstate == RunState::AwaitingInput {
-            use crate::effects::*;
-
-            for (effect_entity, duration, status) in (&entities, &mut durations, &statuses).join() {
-                if entities.is_alive(status.target) {
-                    duration.turns -= 1;
-
-                    if let Some(dot) = dots.get(effect_entity) {
-                        add_effect(
-                            None,
-                            EffectType::Damage { amount: dot.damage },
-                            Targets::Single { target: status.target },
-                        );
-                    }
+            tick_status_effects(&entities, &mut durations, &statuses, &dots, &mut dirty_equipment, None);
+        }
+    }
+}
 
-                    if duration.turns < 1 {
-                        dirty_equipment
-                            .insert(status.target, EquipmentChanged {})
-                            .expect("Unable to insert");
+/// Counts a turn off every status effect, or only those on `target`, dealing any damage over time and ending the
+/// ones that run out.
+pub fn tick_status_effects(
+    entities: &Entities,
+    durations: &mut WriteStorage<Duration>,
+    statuses: &ReadStorage<StatusEffect>,
+    dots: &ReadStorage<DamageOverTime>,
+    dirty_equipment: &mut WriteStorage<EquipmentChanged>,
+    target: Option<Entity>,
+) {
+    use crate::effects::*;
+
+    for (effect_entity, duration, status) in (entities, durations, statuses).join() {
+        if entities.is_alive(status.target) && target.is_none_or(|target| target == status.target) {
+            duration.turns -= 1;
+
+            if let Some(dot) = dots.get(effect_entity) {
+                add_effect(
+                    None,
+                    EffectType::Damage { amount: dot.damage },
+                    Targets::Single { target: status.target },
+                );
+            }
 
-                        entities.delete(effect_entity).expect("Unable to delete");
-                    }
-                }
+            if duration.turns < 1 {
+                dirty_equipment
+                    .insert(status.target, EquipmentChanged {})
+                    .expect("Unable to insert");
+
+                entities.delete(effect_entity).expect("Unable to delete");
             }
         }
     }
@@ -0,0 +1,245 @@
+use super::raws::structs::Reaction;
+use super::*;
+
+/// Below this fraction of their hit points, item users reach for a healing potion.
+const HEAL_THRESHOLD: f32 = 0.35;
+/// Percent chance per turn that an item user holding an offensive scroll reads it at a visible enemy.
+const OFFENSIVE_ITEM_CHANCE: i32 = 50;
+
+pub struct ItemUseAI {}
+
+impl<'a> System<'a> for ItemUseAI {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteStorage<'a, MyTurn>,
+        ReadStorage<'a, UsesItems>,
+        ReadStorage<'a, Faction>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, Pools>,
+        ReadStorage<'a, Viewshed>,
+        ReadExpect<'a, Map>,
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadStorage<'a, Item>,
+        ReadStorage<'a, InBackpack>,
+        ReadStorage<'a, Equippable>,
+        ReadStorage<'a, Equipped>,
+        ReadStorage<'a, Weapon>,
+        ReadStorage<'a, Wearable>,
+        ReadStorage<'a, CursedItem>,
+        ReadStorage<'a, EquipFailed>,
+        ReadStorage<'a, ProvidesHealing>,
+        ReadStorage<'a, InflictsDamage>,
+        ReadStorage<'a, Confusion>,
+        ReadStorage<'a, Ranged>,
+        ReadStorage<'a, AreaOfEffect>,
+        WriteStorage<'a, WantsToUseItem>,
+        WriteStorage<'a, WantsToPickupItem>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            mut turns,
+            uses_items,
+            factions,
+            positions,
+            pools,
+            viewsheds,
+            map,
+            entities,
+            player,
+            items,
+            backpack,
+            equippable,
+            equipped,
+            weapons,
+            wearables,
+            cursed,
+            equip_failed,
+            healing,
+            damage,
+            confusion,
+            ranged,
+            aoe,
+            mut wants_use,
+            mut wants_pickup,
+        ) = data;
+
+        let mut turn_done: Vec<Entity> = Vec::new();
+        for (entity, _turn, _uses, my_faction, pos, stats, viewshed) in (
+            &entities,
+            &turns,
+            &uses_items,
+            &factions,
+            &positions,
+            &pools,
+            &viewsheds,
+        )
+            .join()
+        {
+            if entity == *player {
+                continue;
+            }
+
+            let my_idx = map.xy_idx(pos.x, pos.y);
+            let carried: Vec<Entity> = (&entities, &backpack)
+                .join()
+                .filter(|(_, pack)| pack.owner == entity)
+                .map(|(item, _)| item)
+                .collect();
+
+            let is_offensive = |item: Entity| {
+                ranged.get(item).is_some() && (damage.get(item).is_some() || confusion.get(item).is_some())
+            };
+            let is_upgrade = |item: Entity| {
+                equippable.get(item).is_some_and(|eq| {
+                    let current = (&entities, &equipped)
+                        .join()
+                        .find(|(_, worn)| worn.owner == entity && worn.slot == eq.slot)
+                        .map(|(worn_item, _)| worn_item);
+
+                    // Cursed gear can't be taken off, so there's no upgrading it
+                    match current {
+                        None => true,
+                        Some(current) => {
+                            cursed.get(current).is_none()
+                                && same_kind(current, item, &weapons)
+                                && gear_score(item, &weapons, &wearables) > gear_score(current, &weapons, &wearables)
+                        },
+                    }
+                })
+            };
+
+            let mut intent: Option<WantsToUseItem> = None;
+
+            // Badly hurt - drink something
+            if (stats.hit_points.current as f32) < stats.hit_points.max as f32 * HEAL_THRESHOLD {
+                if let Some(potion) = carried.iter().find(|item| healing.get(**item).is_some()) {
+                    intent = Some(WantsToUseItem {
+                        item: *potion,
+                        target: None,
+                    });
+                }
+            }
+
+            // Read an offensive scroll at the nearest visible enemy
+            if intent.is_none() && crate::rng::roll_dice(1, 100) <= OFFENSIVE_ITEM_CHANCE {
+                if let Some(scroll) = carried.iter().find(|item| is_offensive(**item)) {
+                    let range = ranged.get(*scroll).unwrap().range as f32;
+                    let min_range = aoe.get(*scroll).map_or(0.0, |a| a.radius as f32 + 1.0);
+
+                    intent = nearest_enemy(&map, my_idx, &my_faction.name, &factions, viewshed)
+                        .filter(|target| {
+                            let d = rltk::DistanceAlg::Pythagoras.distance2d(Point::new(pos.x, pos.y), *target);
+                            d <= range && d >= min_range
+                        })
+                        .map(|target| WantsToUseItem {
+                            item: *scroll,
+                            target: Some(target),
+                        });
+                }
+            }
+
+            // Put on anything better than what we're wearing
+            if intent.is_none() {
+                if let Some(gear) = carried
+                    .iter()
+                    .find(|item| equip_failed.get(**item).is_none() && is_upgrade(**item))
+                {
+                    intent = Some(WantsToUseItem {
+                        item: *gear,
+                        target: None,
+                    });
+                }
+            }
+
+            if let Some(intent) = intent {
+                wants_use.insert(entity, intent).expect("Unable to insert");
+                turn_done.push(entity);
+                continue;
+            }
+
+            // Pick up anything useful lying underfoot
+            let mut found: Option<Entity> = None;
+            crate::spatial::for_each_tile_content(my_idx, |other| {
+                if found.is_none()
+                    && items.get(other).is_some()
+                    && (healing.get(other).is_some() || is_offensive(other) || is_upgrade(other))
+                {
+                    found = Some(other);
+                }
+            });
+
+            if let Some(item) = found {
+                wants_pickup
+                    .insert(
+                        entity,
+                        WantsToPickupItem {
+                            collected_by: entity,
+                            item,
+                        },
+                    )
+                    .expect("Unable to insert");
+                turn_done.push(entity);
+            }
+        }
+
+        // Using or picking up an item takes the whole turn
+        for done in turn_done.iter() {
+            turns.remove(*done);
+        }
+    }
+}
+
+/// A rough measure of how good a piece of gear is, for comparing it with another in the same slot.
+fn gear_score(item: Entity, weapons: &ReadStorage<Weapon>, wearables: &ReadStorage<Wearable>) -> f32 {
+    if let Some(weapon) = weapons.get(item) {
+        let average = (weapon.damage_n_dice * (weapon.damage_die_type + 1)) as f32 / 2.0;
+        average + (weapon.damage_bonus + weapon.hit_bonus) as f32
+    } else if let Some(wearable) = wearables.get(item) {
+        wearable.armor_class
+    } else {
+        0.0
+    }
+}
+
+/// Archers keep their bows and brawlers their clubs; only trade like for like.
+fn same_kind(a: Entity, b: Entity, weapons: &ReadStorage<Weapon>) -> bool {
+    match (weapons.get(a), weapons.get(b)) {
+        (Some(a), Some(b)) => a.range.is_some() == b.range.is_some(),
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+fn nearest_enemy(
+    map: &Map,
+    my_idx: usize,
+    my_faction: &str,
+    factions: &ReadStorage<Faction>,
+    viewshed: &Viewshed,
+) -> Option<Point> {
+    let me = Point::new(my_idx as i32 % map.width, my_idx as i32 / map.width);
+    let mut enemies: Vec<(Point, f32)> = Vec::new();
+
+    for tile in viewshed.visible_tiles.iter() {
+        let idx = map.xy_idx(tile.x, tile.y);
+        if idx == my_idx {
+            continue;
+        }
+
+        crate::spatial::for_each_tile_content(idx, |other| {
+            if let Some(faction) = factions.get(other) {
+                if let Reaction::Attack = raws::faction_reaction(my_faction, &faction.name, &raws::RAWS.lock().unwrap())
+                {
+                    enemies.push((*tile, rltk::DistanceAlg::Pythagoras.distance2d(me, *tile)));
+                }
+            }
+        });
+    }
+
+    enemies
+        .iter()
+        .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .map(|(pt, _)| *pt)
+}
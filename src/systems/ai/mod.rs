@@ -7,7 +7,9 @@ mod default_move_system;
 mod encumbrance_system;
 mod flee_ai_system;
 mod initiative_system;
+mod item_use_ai_system;
 mod quipping;
+mod ranged_tactics;
 mod turn_status;
 mod visible_ai_system;
 
@@ -17,7 +19,8 @@ pub use chase_ai_system::ChaseAI;
 pub use default_move_system::DefaultMoveAI;
 pub use encumbrance_system::EncumbranceSystem;
 pub use flee_ai_system::FleeAI;
-pub use initiative_system::InitiativeSystem;
+pub use initiative_system::{tick_status_effects, InitiativeSystem};
+pub use item_use_ai_system::ItemUseAI;
 pub use quipping::QuipSystem;
 pub use turn_status::TurnStatusSystem;
 pub use visible_ai_system::VisibleAI;
@@ -0,0 +1,149 @@
+use super::*;
+
+/// What a monster with a ranged weapon should do about a hostile target this turn.
+pub enum RangedPlan {
+    /// Fire on the target from where we stand.
+    Shoot,
+    /// Step to the given tile index (backing off, finding cover or a clear shot).
+    Reposition(usize),
+    /// No useful ranged option - fall back to closing in.
+    Close,
+}
+
+/// How far a ranged attacker tries to keep from its target, based on weapon range.
+pub fn preferred_distance(weapon_range: i32) -> f32 { i32::max(2, weapon_range / 2) as f32 }
+
+/// Returns the range of the ranged weapon `owner` has equipped, if any.
+pub fn equipped_ranged_weapon(
+    owner: Entity,
+    equipped: &ReadStorage<Equipped>,
+    weapons: &ReadStorage<Weapon>,
+) -> Option<i32> {
+    (equipped, weapons)
+        .join()
+        .filter(|(equip, _)| equip.owner == owner)
+        .find_map(|(_, weapon)| weapon.range)
+}
+
+/// A line of fire is clear if no tile strictly between the shooter and the target blocks sight, and no blocking
+/// entity (an ally or a heavy prop) stands in the way.
+pub fn line_of_fire_clear(map: &Map, from: Point, to: Point) -> bool {
+    rltk::line2d(rltk::LineAlg::Bresenham, from, to)
+        .iter()
+        .filter(|pt| **pt != from && **pt != to)
+        .all(|pt| {
+            if pt.x < 0 || pt.x >= map.width || pt.y < 0 || pt.y >= map.height {
+                return false;
+            }
+
+            let idx = map.xy_idx(pt.x, pt.y);
+            !map.is_opaque(idx) && !crate::spatial::is_blocked_by_entity(idx)
+        })
+}
+
+/// A tile is in cover if it sits next to a tile holding a prop that provides cover.
+pub fn tile_has_cover<F>(map: &Map, pt: Point, is_cover: &F) -> bool
+where F: Fn(usize) -> bool {
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            let (x, y) = (pt.x + dx, pt.y + dy);
+            if (dx != 0 || dy != 0)
+                && x > 0
+                && x < map.width - 1
+                && y > 0
+                && y < map.height - 1
+                && is_cover(map.xy_idx(x, y))
+            {
+                return true;
+            }
+        }
+    }
+
+    false
+}
+
+/// Decide how a ranged attacker at `me` should deal with a target at `target`.
+///
+/// Too close: back off to the free neighbour that opens the most distance, favouring cover.
+/// In range with a clear line: shoot, unless a neighbouring cover tile also has a clear shot - then step into it.
+/// In range but blocked: sidestep to a neighbour with a clear shot. Otherwise close in.
+pub fn plan<F>(map: &Map, me: Point, target: Point, weapon_range: i32, is_cover: F) -> RangedPlan
+where F: Fn(usize) -> bool {
+    let distance = rltk::DistanceAlg::Pythagoras.distance2d(me, target);
+    let preferred = preferred_distance(weapon_range);
+    let range = weapon_range as f32;
+    let clear_shot = distance <= range && line_of_fire_clear(map, me, target);
+
+    // (tile, distance to target, in cover) for every free neighbouring tile
+    let candidates: Vec<(Point, f32, bool)> = free_neighbours(map, me)
+        .into_iter()
+        .map(|pt| {
+            let d = rltk::DistanceAlg::Pythagoras.distance2d(pt, target);
+            (pt, d, tile_has_cover(map, pt, &is_cover))
+        })
+        .collect();
+
+    // Too close for comfort - kite away
+    if distance < preferred {
+        let retreat_score = |d: f32, cover: bool| if cover { d + 1.5 } else { d };
+        let retreat = candidates
+            .iter()
+            .filter(|(_, d, _)| *d > distance)
+            .max_by(|a, b| retreat_score(a.1, a.2).partial_cmp(&retreat_score(b.1, b.2)).unwrap());
+
+        return match retreat {
+            Some((pt, _, _)) => RangedPlan::Reposition(map.xy_idx(pt.x, pt.y)),
+            None if clear_shot => RangedPlan::Shoot,
+            None => RangedPlan::Close,
+        };
+    }
+
+    // Neighbouring firing positions that keep the target in range, at a comfortable distance, with a clear line
+    let firing_spot = |require_cover: bool| {
+        candidates
+            .iter()
+            .filter(|(pt, d, cover)| {
+                *d <= range && *d >= preferred && (*cover || !require_cover) && line_of_fire_clear(map, *pt, target)
+            })
+            .max_by_key(|(_, _, cover)| *cover)
+            .map(|(pt, _, _)| map.xy_idx(pt.x, pt.y))
+    };
+
+    if clear_shot {
+        if !tile_has_cover(map, me, &is_cover) {
+            if let Some(idx) = firing_spot(true) {
+                return RangedPlan::Reposition(idx);
+            }
+        }
+
+        return RangedPlan::Shoot;
+    }
+
+    if distance <= range {
+        if let Some(idx) = firing_spot(false) {
+            return RangedPlan::Reposition(idx);
+        }
+    }
+
+    RangedPlan::Close
+}
+
+fn free_neighbours(map: &Map, pt: Point) -> Vec<Point> {
+    let mut result = Vec::new();
+    for dy in -1..=1 {
+        for dx in -1..=1 {
+            let (x, y) = (pt.x + dx, pt.y + dy);
+            if (dx != 0 || dy != 0)
+                && x > 0
+                && x < map.width - 1
+                && y > 0
+                && y < map.height - 1
+                && !crate::spatial::is_blocked(map.xy_idx(x, y))
+            {
+                result.push(Point::new(x, y));
+            }
+        }
+    }
+
+    result
+}
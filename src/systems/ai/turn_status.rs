@@ -48,6 +48,17 @@ impl<'a> System<'a> for TurnStatusSystem {
                         },
                     );
 
+                    // Sometimes they stagger about, which may take them somewhere they'd never walk
+                    if crate::rng::roll_dice(1, 3) == 1 {
+                        add_effect(
+                            None,
+                            EffectType::Stumble,
+                            Targets::Single {
+                                target: status_effect.target,
+                            },
+                        );
+                    }
+
                     not_my_turn.push(status_effect.target);
                 }
             }
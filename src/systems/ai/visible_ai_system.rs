@@ -1,3 +1,4 @@
+use super::ranged_tactics::RangedPlan;
 use super::raws::structs::Reaction;
 use super::*;
 
@@ -5,7 +6,7 @@ pub struct VisibleAI {}
 
 impl<'a> System<'a> for VisibleAI {
     type SystemData = (
-        ReadStorage<'a, MyTurn>,
+        WriteStorage<'a, MyTurn>,
         ReadStorage<'a, Faction>,
         ReadStorage<'a, Position>,
         ReadExpect<'a, Map>,
@@ -22,11 +23,13 @@ impl<'a> System<'a> for VisibleAI {
         ReadStorage<'a, Equipped>,
         ReadStorage<'a, Weapon>,
         WriteStorage<'a, WantsToShoot>,
+        ReadStorage<'a, ProvidesCover>,
+        ReadStorage<'a, Darkvision>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         let (
-            turns,
+            mut turns,
             factions,
             positions,
             map,
@@ -43,8 +46,11 @@ impl<'a> System<'a> for VisibleAI {
             equipped,
             weapons,
             mut wants_shoot,
+            cover,
+            darkvision,
         ) = data;
 
+        let mut turn_done: Vec<Entity> = Vec::new();
         for (entity, _turn, my_faction, pos, viewshed) in (&entities, &turns, &factions, &positions, &viewsheds).join()
         {
             if entity != *player {
@@ -59,6 +65,30 @@ impl<'a> System<'a> for VisibleAI {
                     }
                 }
 
+                // Large creatures are seen on every tile they cover; deal with each one at its nearest tile
+                let me = rltk::Point::new(pos.x, pos.y);
+                reactions.sort_by_key(|r| {
+                    let (x, y) = (r.0 as i32 % map.width, r.0 as i32 / map.width);
+                    (x - me.x).abs().max((y - me.y).abs())
+                });
+                let mut seen: Vec<Entity> = Vec::new();
+                reactions.retain(|r| {
+                    if seen.contains(&r.2) {
+                        false
+                    } else {
+                        seen.push(r.2);
+                        true
+                    }
+                });
+
+                // Foes standing in the dark may go unnoticed, unless we can see in the dark or are already after them
+                reactions.retain(|r| {
+                    r.1 != Reaction::Attack
+                        || darkvision.get(entity).is_some()
+                        || chasing.get(entity).is_some_and(|c| c.target == r.2)
+                        || crate::rng::roll_dice(1, 6) > crate::map::stealth_modifier(&map, r.0)
+                });
+
                 let mut done = false;
                 for reaction in reactions.iter() {
                     match reaction.1 {
@@ -98,20 +128,38 @@ impl<'a> System<'a> for VisibleAI {
                             }
 
                             if !done {
-                                for (weapon, equip) in (&weapons, &equipped).join() {
-                                    if let Some(wrange) = weapon.range {
-                                        if equip.owner == entity {
-                                            rltk::console::log(format!("Owner found. Ranges: {}/{}", wrange, range));
-                                            if wrange >= range as i32 {
-                                                rltk::console::log("Inserting shoot");
-
-                                                wants_shoot
-                                                    .insert(entity, WantsToShoot { target: reaction.2 })
-                                                    .expect("Insert fail");
-
-                                                done = true;
-                                            }
-                                        }
+                                if let Some(wrange) =
+                                    ranged_tactics::equipped_ranged_weapon(entity, &equipped, &weapons)
+                                {
+                                    let target_pt =
+                                        rltk::Point::new(reaction.0 as i32 % map.width, reaction.0 as i32 / map.width);
+                                    let is_cover = |idx: usize| {
+                                        crate::spatial::get_tile_content_clone(idx)
+                                            .iter()
+                                            .any(|e| cover.get(*e).is_some())
+                                    };
+
+                                    match ranged_tactics::plan(
+                                        &map,
+                                        rltk::Point::new(pos.x, pos.y),
+                                        target_pt,
+                                        wrange,
+                                        is_cover,
+                                    ) {
+                                        RangedPlan::Shoot => {
+                                            wants_shoot
+                                                .insert(entity, WantsToShoot { target: reaction.2 })
+                                                .expect("Insert fail");
+                                            turn_done.push(entity);
+                                            done = true;
+                                        },
+                                        RangedPlan::Reposition(idx) => {
+                                            want_approach
+                                                .insert(entity, WantsToApproach { idx: idx as i32 })
+                                                .expect("Unable to insert");
+                                            done = true;
+                                        },
+                                        RangedPlan::Close => {},
                                     }
                                 }
                             }
@@ -142,6 +190,11 @@ impl<'a> System<'a> for VisibleAI {
                 }
             }
         }
+
+        // Shooters hold their position for the rest of the turn
+        for done in turn_done.iter() {
+            turns.remove(*done);
+        }
     }
 }
 
@@ -1,6 +1,6 @@
 use specs::prelude::*;
 
-use super::{Equipped, InBackpack, LootTable, Name, Player, Pools, Position, RunState};
+use super::{Equipped, InBackpack, LootTable, Name, Player, Pools, Position, RunState, TileSize};
 
 pub fn delete_the_dead(ecs: &mut World) {
     let mut dead: Vec<Entity> = Vec::new();
@@ -48,9 +48,12 @@ pub fn delete_the_dead(ecs: &mut World) {
         let mut positions = ecs.write_storage::<Position>();
 
         let loot_tables = ecs.read_storage::<LootTable>();
+        let sizes = ecs.read_storage::<TileSize>();
 
         for victim in dead.iter() {
-            let pos = positions.get(*victim);
+            // Large creatures drop their belongings from the middle of their body
+            let centre = positions.get(*victim).map(|pos| body_centre(pos, sizes.get(*victim)));
+            let pos = centre.as_ref();
             for (entity, equipped) in (&entities, &equipped).join() {
                 if equipped.owner == *victim {
                     // Drop their stuff
@@ -115,6 +118,7 @@ pub fn delete_the_dead(ecs: &mut World) {
                     let map = ecs.fetch::<Map>();
 
                     if let Some(pos) = ecs.read_storage::<Position>().get(*victim) {
+                        let pos = body_centre(pos, ecs.read_storage::<TileSize>().get(*victim));
                         let spell_entity = crate::raws::find_spell_entity(ecs, &effect.spell).unwrap();
                         let tile_idx = map.xy_idx(pos.x, pos.y);
 
@@ -145,3 +149,14 @@ pub fn delete_the_dead(ecs: &mut World) {
         ecs.delete_entity(victim).expect("Unable to delete");
     }
 }
+
+/// The middle of an entity's body; its position for ordinary, single-tile entities.
+fn body_centre(pos: &Position, size: Option<&TileSize>) -> Position {
+    match size {
+        Some(size) => Position {
+            x: pos.x + size.x / 2,
+            y: pos.y + size.y / 2,
+        },
+        None => pos.clone(),
+    }
+}
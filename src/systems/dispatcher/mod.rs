@@ -26,6 +26,7 @@ construct_dispatcher!(
     (InitiativeSystem, "initiative", &[]),
     (TurnStatusSystem, "turnstatus", &[]),
     (QuipSystem, "quips", &[]),
+    (ItemUseAI, "item_use_ai", &[]),
     (AdjacentAI, "adjacent", &[]),
     (VisibleAI, "visible", &[]),
     (ApproachAI, "approach", &[]),
@@ -44,6 +45,7 @@ construct_dispatcher!(
     (ItemDropSystem, "drop", &[]),
     (ItemRemoveSystem, "remove", &[]),
     (HungerSystem, "hunger", &[]),
+    (EnvironmentSystem, "environment", &[]),
     (ParticleSpawnSystem, "particle_spawn", &[]),
     (LightingSystem, "lighting", &[])
 );
@@ -0,0 +1,120 @@
+use super::*;
+use crate::map::{has_fuel, tile_burns_into, tile_walkable};
+
+/// One in this many chances, each turn, that a fire spreads to each flammable tile next to it.
+const SPREAD_CHANCE: i32 = 5;
+
+pub struct EnvironmentSystem {}
+
+impl<'a> System<'a> for EnvironmentSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteExpect<'a, Map>,
+        WriteExpect<'a, MasterDungeonMap>,
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadStorage<'a, MyTurn>,
+        ReadStorage<'a, Flammable>,
+        ReadStorage<'a, Pools>,
+        ReadStorage<'a, HazardResistance>,
+        WriteStorage<'a, Viewshed>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut map, mut dungeon_master, entities, player_entity, turns, flammable, pools, resistances, mut viewsheds) =
+            data;
+
+        // Fire, smoke and gas move on once for every turn the player takes
+        if turns.get(*player_entity).is_none() || map.environment.is_empty() {
+            return;
+        }
+
+        // Fires spread to anything that will burn beside them, and consume what they stand on
+        let burning: Vec<(usize, i32)> = map
+            .environment
+            .iter()
+            .filter(|(_, env)| env.fire > 0)
+            .map(|(idx, env)| (*idx, env.fire))
+            .collect();
+
+        for (idx, fire) in burning {
+            for n in map.open_neighbours(idx, true) {
+                let already_burning = map.environment.get(&n).is_some_and(|env| env.fire > 0);
+                if !already_burning && has_fuel(&map, n, &flammable) && crate::rng::roll_dice(1, SPREAD_CHANCE) == 1 {
+                    let turns = crate::rng::range(3, 7);
+                    map.ignite(n, turns, true);
+                }
+            }
+
+            if fire == 1 {
+                if let Some(into) = tile_burns_into(map.tiles[idx]) {
+                    map.tiles[idx] = into;
+                    crate::spatial::set_terrain_blocked(idx, !tile_walkable(into));
+                    dungeon_master.set_tile(&map, idx, into);
+                }
+
+                for entity in crate::spatial::get_tile_content_clone(idx) {
+                    if flammable.get(entity).is_some() {
+                        crate::spatial::remove_entity(entity, idx);
+                        entities.delete(entity).expect("Unable to delete");
+                    }
+                }
+            }
+
+            if let Some(env) = map.environment.get_mut(&idx) {
+                env.fire -= 1;
+            }
+        }
+
+        // Anything caught in flames or gas gets hurt, unless it is used to it
+        for (idx, env) in map.environment.iter() {
+            crate::spatial::for_each_tile_content(*idx, |entity| {
+                if pools.get(entity).is_none() {
+                    return;
+                }
+                let resists = |hazard: &str| {
+                    resistances
+                        .get(entity)
+                        .is_some_and(|r| r.hazards.iter().any(|h| h == hazard))
+                };
+
+                if env.fire > 0 && !resists("fire") {
+                    if entity == *player_entity {
+                        crate::gamelog::Logger::new()
+                            .append_with_color("You are burning!", rltk::ORANGE)
+                            .log();
+                    }
+                    add_effect(
+                        None,
+                        EffectType::Damage {
+                            amount: crate::rng::roll_dice(1, 6),
+                        },
+                        Targets::Single { target: entity },
+                    );
+                }
+
+                if env.gas > 0 && !resists("gas") {
+                    if entity == *player_entity {
+                        crate::gamelog::Logger::new()
+                            .append_with_color("You choke on the poison gas!", rltk::GREEN)
+                            .log();
+                    }
+                    add_effect(
+                        None,
+                        EffectType::Damage {
+                            amount: 1 + env.gas / 4,
+                        },
+                        Targets::Single { target: entity },
+                    );
+                }
+            });
+        }
+
+        map.drift_environment();
+
+        // The smoke has shifted, so everyone needs a fresh look around
+        for viewshed in (&mut viewsheds).join() {
+            viewshed.dirty = true;
+        }
+    }
+}
@@ -10,9 +10,11 @@ impl<'a> System<'a> for ItemCollectionSystem {
         ReadStorage<'a, Name>,
         WriteStorage<'a, InBackpack>,
         WriteStorage<'a, EquipmentChanged>,
+        WriteStorage<'a, EquipFailed>,
         ReadStorage<'a, MagicItem>,
         ReadStorage<'a, ObfuscatedName>,
         ReadExpect<'a, MasterDungeonMap>,
+        ReadExpect<'a, Map>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -23,13 +25,16 @@ impl<'a> System<'a> for ItemCollectionSystem {
             names,
             mut backpack,
             mut dirty_equipment,
+            mut equip_failed,
             magic_items,
             obfuscated_names,
             dm,
+            map,
         ) = data;
 
         for pickup in wants_pickup.join() {
-            positions.remove(pickup.item);
+            let item_pos = positions.remove(pickup.item);
+            equip_failed.remove(pickup.item);
 
             backpack
                 .insert(
@@ -56,6 +61,24 @@ impl<'a> System<'a> for ItemCollectionSystem {
                         &dm,
                     ))
                     .log();
+            } else if let Some(pos) = item_pos {
+                // Let the player know when they see a monster help itself
+                if map.visible_tiles[map.xy_idx(pos.x, pos.y)] {
+                    if let Some(collector) = names.get(pickup.collected_by) {
+                        crate::gamelog::Logger::new()
+                            .npc_name(&collector.name)
+                            .append("picks up the")
+                            .color(rltk::CYAN)
+                            .append(super::obfuscate_name(
+                                pickup.item,
+                                &names,
+                                &magic_items,
+                                &obfuscated_names,
+                                &dm,
+                            ))
+                            .log();
+                    }
+                }
             }
         }
 
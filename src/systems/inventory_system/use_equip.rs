@@ -14,6 +14,7 @@ impl<'a> System<'a> for ItemEquipOnUse {
         WriteStorage<'a, EquipmentChanged>,
         WriteStorage<'a, IdentifiedItem>,
         ReadStorage<'a, CursedItem>,
+        WriteStorage<'a, EquipFailed>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -28,6 +29,7 @@ impl<'a> System<'a> for ItemEquipOnUse {
             mut dirty,
             mut identified_item,
             cursed,
+            mut equip_failed,
         ) = data;
 
         let mut remove_use: Vec<Entity> = Vec::new();
@@ -43,11 +45,13 @@ impl<'a> System<'a> for ItemEquipOnUse {
                     if already_equipped.owner == target && already_equipped.slot == target_slot {
                         if cursed.get(item_entity).is_some() {
                             // Cursed item unequipping
-                            crate::gamelog::Logger::new()
-                                .append("You cannot unequip")
-                                .item_name(&name.name)
-                                .append("- it is cursed!")
-                                .log();
+                            if target == *player_entity {
+                                crate::gamelog::Logger::new()
+                                    .append("You cannot unequip")
+                                    .item_name(&name.name)
+                                    .append("- it is cursed!")
+                                    .log();
+                            }
 
                             can_equip = false;
                         } else {
@@ -105,6 +109,11 @@ impl<'a> System<'a> for ItemEquipOnUse {
                             .append(&names.get(useitem.item).unwrap().name)
                             .log();
                     }
+                } else if target != *player_entity {
+                    // Remember it, so the monster doesn't spend every turn trying again
+                    equip_failed
+                        .insert(useitem.item, EquipFailed {})
+                        .expect("Unable to insert");
                 }
 
                 // Done with item
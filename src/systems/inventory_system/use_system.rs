@@ -3,6 +3,7 @@ use super::*;
 pub struct ItemUseSystem {}
 
 impl<'a> System<'a> for ItemUseSystem {
+    #[allow(clippy::type_complexity)]
     type SystemData = (
         ReadExpect<'a, Entity>,
         WriteExpect<'a, Map>,
@@ -12,11 +13,28 @@ impl<'a> System<'a> for ItemUseSystem {
         ReadStorage<'a, AreaOfEffect>,
         WriteStorage<'a, EquipmentChanged>,
         WriteStorage<'a, IdentifiedItem>,
+        ReadStorage<'a, Position>,
+        ReadStorage<'a, MagicItem>,
+        ReadStorage<'a, ObfuscatedName>,
+        ReadExpect<'a, MasterDungeonMap>,
     );
 
     #[allow(clippy::cognitive_complexity)]
     fn run(&mut self, data: Self::SystemData) {
-        let (player_entity, map, entities, mut wants_use, names, aoe, mut dirty_equipment, mut identified_item) = data;
+        let (
+            player_entity,
+            map,
+            entities,
+            mut wants_use,
+            names,
+            aoe,
+            mut dirty_equipment,
+            mut identified_item,
+            positions,
+            magic_items,
+            obfuscated_names,
+            dm,
+        ) = data;
 
         for (entity, useitem) in (&entities, &wants_use).join() {
             dirty_equipment
@@ -33,6 +51,22 @@ impl<'a> System<'a> for ItemUseSystem {
                         },
                     )
                     .expect("Unable to insert");
+            } else if let (Some(pos), Some(name)) = (positions.get(entity), names.get(entity)) {
+                // Monsters using items in plain sight
+                if map.visible_tiles[map.xy_idx(pos.x, pos.y)] {
+                    crate::gamelog::Logger::new()
+                        .npc_name(&name.name)
+                        .append("uses the")
+                        .color(rltk::CYAN)
+                        .append(super::obfuscate_name(
+                            useitem.item,
+                            &names,
+                            &magic_items,
+                            &obfuscated_names,
+                            &dm,
+                        ))
+                        .log();
+                }
             }
 
             // Call the effects system
@@ -40,7 +74,7 @@ impl<'a> System<'a> for ItemUseSystem {
                 Some(entity),
                 EffectType::ItemUse { item: useitem.item },
                 match useitem.target {
-                    None => Targets::Single { target: *player_entity },
+                    None => Targets::Single { target: entity },
                     Some(target) => {
                         if let Some(aoe) = aoe.get(useitem.item) {
                             Targets::Tiles {
@@ -1,6 +1,7 @@
 use specs::prelude::*;
 
-use super::{LightSource, Map, Position, Viewshed};
+use super::{LightSource, Map, MasterDungeonMap, Position, Viewshed};
+use crate::map::ambient_light;
 use rltk::RGB;
 
 pub struct LightingSystem {}
@@ -8,35 +9,59 @@ pub struct LightingSystem {}
 impl<'a> System<'a> for LightingSystem {
     type SystemData = (
         WriteExpect<'a, Map>,
-        ReadStorage<'a, Viewshed>,
+        ReadExpect<'a, MasterDungeonMap>,
+        WriteStorage<'a, Viewshed>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, LightSource>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut map, viewshed, positions, lighting) = data;
+        let (mut map, dungeon_master, mut viewsheds, positions, lighting) = data;
 
-        if map.outdoors {
-            return;
-        }
-
-        let black = RGB::from_f32(0.0, 0.0, 0.0);
-        for l in map.light.iter_mut() {
-            *l = black;
+        // Only a change in the ambient light means going over the whole level; otherwise just the tiles lamps lit
+        // last time need putting back, which keeps large levels cheap
+        let ambient = ambient_light(&map, dungeon_master.turn);
+        let relit = map.ambient != Some(ambient);
+        let before: Vec<(usize, RGB)> = map.lamplit.iter().map(|idx| (*idx, map.light[*idx])).collect();
+        if relit {
+            for l in map.light.iter_mut() {
+                *l = ambient;
+            }
+            map.ambient = Some(ambient);
+        } else {
+            for (idx, _) in before.iter() {
+                map.light[*idx] = ambient;
+            }
         }
+        map.lamplit.clear();
 
-        for (viewshed, pos, light) in (&viewshed, &positions, &lighting).join() {
-            let light_point = rltk::Point::new(pos.x, pos.y);
-            let range_f = light.range as f32;
-            for t in viewshed.visible_tiles.iter() {
-                if t.x > 0 && t.x < map.width && t.y > 0 && t.y < map.height {
-                    let idx = map.xy_idx(t.x, t.y);
-                    let distance = rltk::DistanceAlg::Pythagoras.distance2d(light_point, *t);
-                    let intensity = (range_f - distance) / range_f;
+        // Under a sky that never darkens, lamps make no difference
+        if !map.outdoors || map.daylight {
+            for (pos, light) in (&positions, &lighting).join() {
+                let light_point = rltk::Point::new(pos.x, pos.y);
+                let range_f = light.range as f32;
+                for t in rltk::field_of_view(light_point, light.range, &*map).iter() {
+                    if t.x > 0 && t.x < map.width && t.y > 0 && t.y < map.height {
+                        let idx = map.xy_idx(t.x, t.y);
+                        let distance = rltk::DistanceAlg::Pythagoras.distance2d(light_point, *t);
+                        let intensity = (range_f - distance) / range_f;
 
-                    map.light[idx] = map.light[idx] + (light.color * intensity);
+                        let lit = map.light[idx] + (light.color * intensity);
+                        map.light[idx] = RGB::from_f32(lit.r.min(1.0), lit.g.min(1.0), lit.b.min(1.0));
+                        map.lamplit.push(idx);
+                    }
                 }
             }
         }
+        map.lamplit.sort_unstable();
+        map.lamplit.dedup();
+
+        // Everyone sees differently as the light changes, so they need a fresh look around
+        let after: Vec<(usize, RGB)> = map.lamplit.iter().map(|idx| (*idx, map.light[*idx])).collect();
+        if relit || after != before {
+            for viewshed in (&mut viewsheds).join() {
+                viewshed.dirty = true;
+            }
+        }
     }
 }
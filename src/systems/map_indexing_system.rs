@@ -16,7 +16,7 @@ impl<'a> System<'a> for MapIndexingSystem {
         let (map, position, blockers, pools, sizes, entities) = data;
 
         crate::spatial::clear();
-        crate::spatial::populate_blocked_from_map(&*map);
+        crate::spatial::populate_blocked_if_needed(&*map);
 
         for (entity, position) in (&entities, &position).join() {
             let mut alive = true;
@@ -27,20 +27,10 @@ impl<'a> System<'a> for MapIndexingSystem {
             }
 
             if alive {
-                if let Some(size) = sizes.get(entity) {
-                    // Multi-tile
-                    for y in position.y..position.y + size.y {
-                        for x in position.x..position.x + size.x {
-                            if x > 0 && x < map.width - 1 && y > 0 && y < map.height - 1 {
-                                let idx = map.xy_idx(x, y);
-                                crate::spatial::index_entity(entity, idx, blockers.get(entity).is_some());
-                            }
-                        }
-                    }
-                } else {
-                    // Single tile
-                    let idx = map.xy_idx(position.x, position.y);
-                    crate::spatial::index_entity(entity, idx, blockers.get(entity).is_some());
+                // Multi-tile entities are indexed on every tile they cover
+                let blocks = blockers.get(entity).is_some();
+                for idx in map.footprint(position.x, position.y, sizes.get(entity)) {
+                    crate::spatial::index_entity(entity, idx, blocks);
                 }
             }
         }
@@ -5,6 +5,7 @@ pub use dispatcher::UnifiedDispatcher;
 
 pub mod ai;
 pub mod damage_system;
+pub mod environment_system;
 pub mod hunger_system;
 pub mod inventory_system;
 pub mod lighting_system;
@@ -20,6 +21,7 @@ pub mod visibility_system;
 use ai::*;
 use inventory_system::*;
 
+use environment_system::EnvironmentSystem;
 use hunger_system::HungerSystem;
 use lighting_system::LightingSystem;
 use map_indexing_system::MapIndexingSystem;
@@ -1,6 +1,9 @@
 use specs::prelude::*;
 
-use super::{ApplyMove, ApplyTeleport, EntityMoved, Map, OtherLevelPosition, Position, RunState, Viewshed};
+use super::{
+    map::ClearanceMap, ApplyMove, ApplyTeleport, EntityMoved, Map, OtherLevelPosition, Position, RunState, TileSize,
+    Viewshed,
+};
 
 pub struct MovementSystem {}
 
@@ -17,6 +20,7 @@ impl<'a> System<'a> for MovementSystem {
         WriteStorage<'a, Viewshed>,
         ReadExpect<'a, Entity>,
         WriteExpect<'a, RunState>,
+        ReadStorage<'a, TileSize>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -32,11 +36,16 @@ impl<'a> System<'a> for MovementSystem {
             mut viewsheds,
             player_entity,
             mut runstate,
+            sizes,
         ) = data;
 
+        // Teleports land where they were aimed, rather than sliding on from there
+        let mut teleported: Vec<Entity> = Vec::new();
+
         // Apply teleports
         for (entity, teleport) in (&entities, &apply_teleport).join() {
-            if teleport.dest_depth == map.depth {
+            if teleport.dest_depth == map.depth && teleport.dest_branch == map.branch {
+                teleported.push(entity);
                 apply_move
                     .insert(
                         entity,
@@ -50,12 +59,12 @@ impl<'a> System<'a> for MovementSystem {
                     x: teleport.dest_x,
                     y: teleport.dest_y,
                     depth: teleport.dest_depth,
+                    branch: teleport.dest_branch,
                 };
             } else if let Some(pos) = position.get(entity) {
-                let idx = map.xy_idx(pos.x, pos.y);
-                let dest_idx = map.xy_idx(teleport.dest_x, teleport.dest_y);
-
-                crate::spatial::move_entity(entity, idx, dest_idx);
+                for idx in map.footprint(pos.x, pos.y, sizes.get(entity)) {
+                    crate::spatial::remove_entity(entity, idx);
+                }
 
                 other_level
                     .insert(
@@ -64,6 +73,8 @@ impl<'a> System<'a> for MovementSystem {
                             x: teleport.dest_x,
                             y: teleport.dest_y,
                             depth: teleport.dest_depth,
+                            branch: teleport.dest_branch,
+                            area: crate::map::WorldCoord::default(),
                         },
                     )
                     .expect("Unable to insert");
@@ -75,13 +86,31 @@ impl<'a> System<'a> for MovementSystem {
 
         // Apply broad movement
         for (entity, movement, mut pos) in (&entities, &apply_move, &mut position).join() {
+            let forced = teleported.contains(&entity);
             let start_idx = map.xy_idx(pos.x, pos.y);
-            let dest_idx = movement.dest_idx as usize;
+            let dest_idx = if forced || sizes.get(entity).is_some() {
+                movement.dest_idx
+            } else {
+                map.slide(start_idx, movement.dest_idx)
+            };
+            let dest_x = dest_idx as i32 % map.width;
+            let dest_y = dest_idx as i32 / map.width;
 
-            crate::spatial::move_entity(entity, start_idx, dest_idx);
+            if let Some(size) = sizes.get(entity) {
+                // Large entities only move if their whole body fits at the destination
+                if !ClearanceMap::new(&map, size, vec![entity]).fits(dest_x, dest_y) {
+                    continue;
+                }
+
+                let from = map.footprint(pos.x, pos.y, Some(size));
+                let to = map.footprint(dest_x, dest_y, Some(size));
+                crate::spatial::move_entity_footprint(entity, &from, &to);
+            } else {
+                crate::spatial::move_entity(entity, start_idx, dest_idx);
+            }
 
-            pos.x = movement.dest_idx as i32 % map.width;
-            pos.y = movement.dest_idx as i32 / map.width;
+            pos.x = dest_x;
+            pos.y = dest_y;
 
             if let Some(vs) = viewsheds.get_mut(entity) {
                 vs.dirty = true;
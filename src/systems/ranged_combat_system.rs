@@ -18,6 +18,7 @@ impl<'a> System<'a> for RangedCombatSystem {
         ReadStorage<'a, NaturalAttackDefense>,
         ReadStorage<'a, Position>,
         ReadExpect<'a, Map>,
+        ReadStorage<'a, TileSize>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -35,6 +36,7 @@ impl<'a> System<'a> for RangedCombatSystem {
             natural,
             positions,
             map,
+            sizes,
         ) = data;
 
         for (entity, wants_shoot, name, attacker_attributes, attacker_skills, attacker_pools) in
@@ -51,6 +53,14 @@ impl<'a> System<'a> for RangedCombatSystem {
                 let apos = positions.get(entity).unwrap();
                 let dpos = positions.get(wants_shoot.target).unwrap();
 
+                // Aim for the nearest part of a large target
+                let dpos = map
+                    .footprint(dpos.x, dpos.y, sizes.get(wants_shoot.target))
+                    .iter()
+                    .map(|idx| Point::new(*idx as i32 % map.width, *idx as i32 / map.width))
+                    .min_by_key(|pt| (pt.x - apos.x).abs().max((pt.y - apos.y).abs()))
+                    .unwrap_or(Point::new(dpos.x, dpos.y));
+
                 add_effect(
                     None,
                     EffectType::ParticleProjectile {
@@ -59,11 +69,7 @@ impl<'a> System<'a> for RangedCombatSystem {
                         bg: RGB::named(rltk::BLACK),
                         lifespan: 300.0,
                         speed: 50.0,
-                        path: rltk::line2d(
-                            rltk::LineAlg::Bresenham,
-                            Point::new(apos.x, apos.y),
-                            Point::new(dpos.x, dpos.y),
-                        ),
+                        path: rltk::line2d(rltk::LineAlg::Bresenham, Point::new(apos.x, apos.y), dpos),
                     },
                     Targets::Tile {
                         tile_idx: map.xy_idx(apos.x, apos.y) as i32,
@@ -62,14 +62,15 @@ pub fn save_game(ecs: &mut World) {
             AreaOfEffect, Confusion, ProvidesHealing, InBackpack, WantsToPickupItem, WantsToUseItem,
             WantsToDropItem, SerializationHelper, Equippable, Equipped, Weapon, Wearable,
             WantsToRemoveItem, ParticleLifetime, HungerClock, ProvidesFood, MagicMapper, Hidden,
-            EntryTrigger, EntityMoved, SingleActivation, BlocksVisibility, Door,
+            EntryTrigger, EntityMoved, SingleActivation, BlocksVisibility, Door, SecretDoor, Key,
             Quips, Attributes, Skills, Pools, NaturalAttackDefense, LootTable,
             OtherLevelPosition, DMSerializationHelper, LightSource, Initiative, MyTurn, Faction,
             WantsToApproach, WantsToFlee, MoveMode, Chasing, Vendor, TownPortal, TeleportTo, ApplyMove,
             ApplyTeleport, MagicItem, ObfuscatedName, IdentifiedItem, CursedItem, ProvidesRemoveCurse,
             ProvidesIdentification, AttributeBonus, Duration, StatusEffect, KnownSpells, SpellTemplate,
             WantsToCastSpell, ProvidesMana, TeachesSpell, DamageOverTime, Slow, SpecialAbilities,
-            TileSize, OnDeath, AlwaysTargetsSelf, WantsToShoot, WantsToShoot
+            TileSize, OnDeath, AlwaysTargetsSelf, UsesItems, HazardResistance, Knockback, Digs,
+            Flammable, Ignites, ReleasesGas, Darkvision, EquipFailed, ProvidesCover, WantsToShoot, WantsToShoot
         );
     }
 
@@ -124,14 +125,15 @@ pub fn load_game(ecs: &mut World) {
             AreaOfEffect, Confusion, ProvidesHealing, InBackpack, WantsToPickupItem, WantsToUseItem,
             WantsToDropItem, SerializationHelper, Equippable, Equipped, Weapon, Wearable,
             WantsToRemoveItem, ParticleLifetime, HungerClock, ProvidesFood, MagicMapper, Hidden,
-            EntryTrigger, EntityMoved, SingleActivation, BlocksVisibility, Door,
+            EntryTrigger, EntityMoved, SingleActivation, BlocksVisibility, Door, SecretDoor, Key,
             Quips, Attributes, Skills, Pools, NaturalAttackDefense, LootTable,
             OtherLevelPosition, DMSerializationHelper, LightSource, Initiative, MyTurn, Faction,
             WantsToApproach, WantsToFlee, MoveMode, Chasing, Vendor, TownPortal, TeleportTo, ApplyMove,
             ApplyTeleport, MagicItem, ObfuscatedName, IdentifiedItem, CursedItem, ProvidesRemoveCurse,
             ProvidesIdentification, AttributeBonus, Duration, StatusEffect, KnownSpells, SpellTemplate,
             WantsToCastSpell, ProvidesMana, TeachesSpell, DamageOverTime, Slow, SpecialAbilities,
-            TileSize, OnDeath, AlwaysTargetsSelf
+            TileSize, OnDeath, AlwaysTargetsSelf, UsesItems, HazardResistance, Knockback, Digs,
+            Flammable, Ignites, ReleasesGas, Darkvision, EquipFailed, ProvidesCover
         );
     }
 
@@ -12,10 +12,28 @@ impl<'a> System<'a> for TriggerSystem {
         ReadStorage<'a, Name>,
         Entities<'a>,
         ReadStorage<'a, AreaOfEffect>,
+        ReadExpect<'a, Entity>,
+        ReadStorage<'a, HazardResistance>,
+        ReadStorage<'a, Pools>,
+        ReadStorage<'a, Attributes>,
+        ReadExpect<'a, MasterDungeonMap>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (map, mut entity_moved, position, entry_trigger, names, entities, area_of_effect) = data;
+        let (
+            map,
+            mut entity_moved,
+            position,
+            entry_trigger,
+            names,
+            entities,
+            area_of_effect,
+            player_entity,
+            resistances,
+            pools,
+            attributes,
+            dungeon_master,
+        ) = data;
 
         // Iterate the entities that moved and their final position
         for (entity, mut _entity_moved, pos) in (&entities, &mut entity_moved, &position).join() {
@@ -53,9 +71,99 @@ impl<'a> System<'a> for TriggerSystem {
                     }
                 }
             });
+
+            // The ground itself may hurt, unless they are used to it
+            let tt = map.tiles[idx];
+            if let Some(on_enter) = crate::map::tile_entry_effect(tt) {
+                if !crate::map::resists(tt, resistances.get(entity)) {
+                    let burden = burden(pools.get(entity), attributes.get(entity));
+                    let below = dungeon_master
+                        .stairs_down(map.branch, map.depth)
+                        .filter(|below| crate::map_builders::covers_depth(below.branch, below.depth));
+                    tile_entry_effects(entity, &on_enter, entity == *player_entity, pos, below, burden);
+                }
+            }
         }
 
         // Remove all entity movement markers
         entity_moved.clear();
     }
 }
+
+/// How much of its carrying capacity an entity is using; above 1.0 it is overburdened.
+fn burden(pools: Option<&Pools>, attributes: Option<&Attributes>) -> f32 {
+    match (pools, attributes) {
+        (Some(pools), Some(attr)) => {
+            let capacity = i32::max(1, (attr.might.base + attr.might.modifiers) * 15);
+            pools.total_weight / capacity as f32
+        },
+        _ => 0.0,
+    }
+}
+
+fn tile_entry_effects(
+    entity: Entity,
+    on_enter: &raws::structs::TileEntryEffect,
+    is_player: bool,
+    pos: &Position,
+    below: Option<crate::map::StairLink>,
+    burden: f32,
+) {
+    let target = || Targets::Single { target: entity };
+
+    if let Some(message) = &on_enter.message {
+        if is_player {
+            crate::gamelog::Logger::new()
+                .append_with_color(message, rltk::ORANGE)
+                .log();
+        }
+    }
+
+    if let Some(damage) = &on_enter.damage {
+        let dice = rltk::parse_dice_string(damage).expect("Invalid tile damage dice");
+        let amount = crate::rng::roll_dice(dice.n_dice, dice.die_type) + dice.bonus;
+        add_effect(None, EffectType::Damage { amount }, target());
+    }
+
+    if let Some(turns) = on_enter.confusion {
+        add_effect(None, EffectType::Confusion { turns }, target());
+    }
+
+    if let Some(initiative_penalty) = on_enter.slow {
+        add_effect(None, EffectType::Slow { initiative_penalty }, target());
+    }
+
+    if let Some(damage) = on_enter.damage_over_time {
+        add_effect(None, EffectType::DamageOverTime { damage }, target());
+    }
+
+    // Swimmers go under more often the more they carry
+    if let Some(damage) = &on_enter.drown {
+        if crate::rng::roll_dice(1, 100) <= 2 + (burden * 40.0) as i32 {
+            if is_player {
+                crate::gamelog::Logger::new()
+                    .append_with_color("You are dragged under and swallow water!", rltk::CYAN)
+                    .log();
+            }
+
+            let dice = rltk::parse_dice_string(damage).expect("Invalid tile drowning dice");
+            let amount = crate::rng::roll_dice(dice.n_dice, dice.die_type) + dice.bonus;
+            add_effect(None, EffectType::Damage { amount }, target());
+        }
+    }
+
+    // At the bottom of a branch, or above depths no chain builds, there is nowhere further to fall
+    if let (true, Some(below)) = (on_enter.fall, below) {
+        add_effect(
+            None,
+            EffectType::TeleportTo {
+                x: pos.x,
+                y: pos.y,
+                depth: below.depth,
+                branch: below.branch,
+                player_only: false,
+            },
+            target(),
+        );
+    }
+}
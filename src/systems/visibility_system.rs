@@ -1,6 +1,7 @@
 use specs::prelude::*;
 
-use super::{BlocksVisibility, Hidden, Map, Name, Player, Position, Viewshed};
+use super::{BlocksVisibility, Darkvision, Hidden, Map, MasterDungeonMap, Name, Player, Position, SecretDoor, Viewshed};
+use crate::map::{ambient_light, is_lit, sight_in_dark};
 use rltk::{field_of_view, Point};
 
 pub struct VisibilitySystem {}
@@ -15,10 +16,25 @@ impl<'a> System<'a> for VisibilitySystem {
         WriteStorage<'a, Hidden>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, BlocksVisibility>,
+        ReadExpect<'a, MasterDungeonMap>,
+        ReadStorage<'a, Darkvision>,
+        ReadStorage<'a, SecretDoor>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (mut map, entities, mut viewshed, pos, player, mut hidden, names, blocks_visibility) = data;
+        let (
+            mut map,
+            entities,
+            mut viewshed,
+            pos,
+            player,
+            mut hidden,
+            names,
+            blocks_visibility,
+            dungeon_master,
+            darkvision,
+            secret_doors,
+        ) = data;
 
         map.view_blocked.clear();
         for (block_pos, _block) in (&pos, &blocks_visibility).join() {
@@ -26,20 +42,56 @@ impl<'a> System<'a> for VisibilitySystem {
             map.view_blocked.insert(idx);
         }
 
+        // Smoke is as good as a wall for hiding behind
+        let smoky: Vec<usize> = map
+            .environment
+            .iter()
+            .filter(|(_, env)| env.smoke > 0)
+            .map(|(idx, _)| *idx)
+            .collect();
+        map.view_blocked.extend(smoky);
+
+        map.disguised.clear();
+        for (door_pos, _door, _hidden) in (&pos, &secret_doors, &hidden).join() {
+            let idx = map.xy_idx(door_pos.x, door_pos.y);
+            map.disguised.insert(idx);
+        }
+
+        // Where night can fall, only lit tiles can be made out from further than the dark allows
+        let can_be_dark = !map.outdoors || map.daylight;
+        let ambient = ambient_light(&map, dungeon_master.turn);
+
         for (ent, viewshed, pos) in (&entities, &mut viewshed, &pos).join() {
             if viewshed.dirty {
                 viewshed.dirty = false;
+
+                // Only what the player could see before needs hiding again, rather than every tile on the level
+                if player.get(ent).is_some() {
+                    for vis in viewshed.visible_tiles.iter() {
+                        if vis.x >= 0 && vis.x < map.width && vis.y >= 0 && vis.y < map.height {
+                            let idx = map.xy_idx(vis.x, vis.y);
+                            map.visible_tiles[idx] = false;
+                        }
+                    }
+                }
+
                 viewshed.visible_tiles = field_of_view(Point::new(pos.x, pos.y), viewshed.range, &*map);
                 viewshed
                     .visible_tiles
                     .retain(|p| p.x >= 0 && p.x < map.width && p.y >= 0 && p.y < map.height);
 
+                if can_be_dark {
+                    let me = Point::new(pos.x, pos.y);
+                    let dark_range = sight_in_dark(viewshed.range, ambient, darkvision.get(ent).is_some()) as f32;
+                    viewshed.visible_tiles.retain(|p| {
+                        rltk::DistanceAlg::Pythagoras.distance2d(me, *p) <= dark_range
+                            || is_lit(&map, map.xy_idx(p.x, p.y))
+                    });
+                }
+
                 // If this is the player, reveal what they can see
                 let _p: Option<&Player> = player.get(ent);
                 if let Some(_p) = _p {
-                    for t in map.visible_tiles.iter_mut() {
-                        *t = false
-                    }
                     for vis in viewshed.visible_tiles.iter() {
                         if vis.x > 0 && vis.x < map.width - 1 && vis.y > 0 && vis.y < map.height - 1 {
                             let idx = map.xy_idx(vis.x, vis.y);
@@ -61,6 +113,7 @@ impl<'a> System<'a> for VisibilitySystem {
                                         }
 
                                         hidden.remove(e);
+                                        map.disguised.remove(&idx);
                                     }
                                 }
                             });